@@ -6,6 +6,11 @@ import logging
 import re
 from neo4j import GraphDatabase
 
+# Relationship properties that identify an edge besides its endpoints
+EDGE_KEY_FIELDS = {
+    "TAINT_FLOW": ("source", "sink", "code_offset"),
+}
+
 # Configure logging
 logging.basicConfig(level=logging.INFO, format='%(asctime)s - %(levelname)s - %(message)s')
 
@@ -93,7 +98,7 @@ def import_to_neo4j(uri, user, password, project_name, graph_data):
                 # Infer type
                 if edge_type == "Calls":
                     ghost_node_types[target_id].add("Function")
-                elif edge_type in ["Packs", "Unpacks", "Acquires"]:
+                elif edge_type in ["Packs", "Unpacks", "Acquires", "GUARDED_BY", "PRODUCES_POTATO", "CONSUMES_POTATO"]:
                     ghost_node_types[target_id].add("Struct")
 
         # 4. Create Ghost Nodes
@@ -203,13 +208,22 @@ def import_to_neo4j(uri, user, password, project_name, graph_data):
             
             if not edge.get("from") or not edge.get("to"): continue
             
-            # Use MERGE for relationship
+            # Extra edge fields (e.g. TAINT_FLOW sink and path) become relationship properties
+            props = {k: v for k, v in edge.items() if k not in ("type", "from", "to")}
+
+            # Use MERGE for relationship. Edges that can repeat between the same nodes are
+            # told apart by their key fields, e.g. one TAINT_FLOW per sink instruction.
+            keys = {k: props[k] for k in EDGE_KEY_FIELDS.get(edge_type, ()) if k in props}
+            key_pattern = ""
+            if keys:
+                key_pattern = " {" + ", ".join(f"{k}: $keys.{k}" for k in keys) + "}"
             query = f"""
             MATCH (a {{id: $source_id}})
             MATCH (b {{id: $target_id}})
-            MERGE (a)-[r:{edge_type}]->(b)
+            MERGE (a)-[r:{edge_type}{key_pattern}]->(b)
+            SET r += $props
             """
-            tx.run(query, source_id=source_id, target_id=target_id)
+            tx.run(query, source_id=source_id, target_id=target_id, keys=keys, props=props)
 
     
     with driver.session() as session:
@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 
+use crate::scanner::result::Severity;
+
 #[derive(Parser)]
 #[clap(author, version, about)]
 pub struct Args {
@@ -20,6 +22,24 @@ pub struct Args {
 
     #[clap(long = "skip-build", help = "Skip build")]
     pub skip_build: bool,
+
+    #[clap(long = "sarif", help = "Path to SARIF output file")]
+    pub sarif: Option<String>,
+
+    #[clap(long = "config", help = "Path to MoveScanner.toml, defaults to <path>/MoveScanner.toml")]
+    pub config: Option<String>,
+
+    #[clap(long = "write-baseline", help = "Write current findings to the baseline file in config")]
+    pub write_baseline: bool,
+
+    #[clap(long = "fail-on", value_enum, help = "Exit with code 1 if any finding is at or above this severity")]
+    pub fail_on: Option<Severity>,
+
+    #[clap(long = "slice", help = "Slicing criterion for `-i sl`, module::function@offset or module::function@Lline")]
+    pub slice: Option<String>,
+
+    #[clap(long = "forward", help = "Print the forward slice instead of the backward slice")]
+    pub forward: bool,
 }
 
 #[derive(Parser)]
@@ -46,4 +66,11 @@ pub enum IR {
     DU,  // Tempindex def and use
     FS,  // Function Signatures
     CG,  // Function Call Graph
+    DUC, // Def-use chains from reaching definitions
+    LV,  // Live variables after each instruction
+    VR,  // Value ranges of unsigned integers
+    SL,  // Program slice of --slice
+    DOM, // Dominator tree of each function
+    PDOM, // Post-dominator tree of each function
+    CDG, // Control dependence graph of each function
 }
@@ -0,0 +1,53 @@
+use move_binary_format::file_format::FunctionDefinitionIndex;
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{liveness, packages::Packages, utils},
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct DeadStore<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for DeadStore<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Minor, DetectKind::DeadStore),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if utils::is_native(idx, stbgr) {
+                    continue;
+                }
+                let def_idx = FunctionDefinitionIndex::new(idx as u16);
+                for dead_store in liveness::get_dead_stores(idx, stbgr, &self.packages.summaries) {
+                    let name = stbgr
+                        .get_local_name(def_idx, dead_store.temp)
+                        .display(&stbgr.symbol_pool)
+                        .to_string();
+                    let detail = if dead_store.discarded {
+                        "computed and discarded"
+                    } else {
+                        "never read"
+                    };
+                    res.push(format!(
+                        "{}({} at {} {})",
+                        function.name,
+                        name,
+                        offset_detail(stbgr, function, dead_store.code_offset as usize),
+                        detail
+                    ));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{FunctionDefinitionIndex, Visibility},
+    internals::ModuleIndex,
+};
+use move_model::{model::ModuleId, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{generate_bytecode::StacklessBytecodeGenerator, packages::Packages, sui, utils},
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct InvalidOneTimeWitness<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for InvalidOneTimeWitness<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Medium, DetectKind::InvalidOneTimeWitness),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let mut res: BTreeMap<String, Vec<String>> = self
+            .packages
+            .get_module_names()
+            .into_iter()
+            .map(|mname| (mname, vec![]))
+            .collect();
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let init = match sui::get_init(stbgr) {
+                Some(init) => init,
+                None => continue,
+            };
+            let values = res.get_mut(mname).unwrap();
+            values.extend(check_init(init, stbgr));
+            if utils::get_param_count(init, stbgr) != 2 {
+                continue;
+            }
+
+            // init 以值接收的第一个参数即为 OTW
+            let self_mid = ModuleId::new(stbgr.module.self_handle_idx().into_index());
+            let candidate = sui::get_otw_candidate(stbgr);
+            let otw = match stbgr.functions[init].local_types.first() {
+                Some(Type::Struct(mid, sid, _)) if *mid == self_mid && Some(*sid) == candidate => {
+                    *sid
+                }
+                _ => {
+                    values.push(format!(
+                        "init(first parameter must be the one-time witness {})",
+                        stbgr.module.self_id().name().as_str().to_uppercase()
+                    ));
+                    continue;
+                }
+            };
+            let otw_name = stbgr.get_struct_full_name(self_mid, otw);
+            let short_name = otw_name.rsplit("::").next().unwrap().to_string();
+            for problem in sui::check_otw_shape(stbgr, otw) {
+                values.push(format!("{}({})", short_name, problem));
+            }
+            for function in stbgr.functions.iter() {
+                for (offset, bytecode) in function.code.iter().enumerate() {
+                    if let Bytecode::Call(_, _, Operation::Pack(mid, sid, _), _, _) = bytecode {
+                        if stbgr.get_struct_full_name(*mid, *sid) == otw_name {
+                            values.push(format!(
+                                "{}(packs {} at {})",
+                                function.name,
+                                short_name,
+                                offset_detail(stbgr, function, offset)
+                            ));
+                        }
+                    }
+                }
+            }
+
+            for callee in self.get_unchecked_witness_callees(init, stbgr) {
+                let (cname, fname) = callee.rsplit_once("::").unwrap();
+                if let Some(values) = res.get_mut(cname) {
+                    let finding = format!(
+                        "{}(witness not checked by {})",
+                        fname,
+                        sui::IS_ONE_TIME_WITNESS
+                    );
+                    if !values.contains(&finding) {
+                        values.push(finding);
+                    }
+                }
+            }
+        }
+        self.content.result.extend(res);
+        &self.content
+    }
+}
+
+impl<'a> InvalidOneTimeWitness<'a> {
+    /// Package functions that receive the OTW from `init` through a generic parameter
+    /// but never call `types::is_one_time_witness`, so any droppable value would be accepted.
+    fn get_unchecked_witness_callees(
+        &self,
+        init: usize,
+        stbgr: &StacklessBytecodeGenerator,
+    ) -> BTreeSet<String> {
+        let mut callees = BTreeSet::new();
+        let mut otw_temps = BTreeSet::from([0]);
+        for bytecode in stbgr.functions[init].code.iter() {
+            match bytecode {
+                Bytecode::Assign(_, dst, src, _) if otw_temps.contains(src) => {
+                    otw_temps.insert(*dst);
+                }
+                Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) => {
+                    let callee = stbgr.get_full_fname(*mid, *fid);
+                    let (callee_stbgr, callee_idx) =
+                        match self.packages.get_function_by_full_name(&callee) {
+                            Some(function) => function,
+                            None => continue,
+                        };
+                    let callee_function = &callee_stbgr.functions[callee_idx];
+                    let generic_witness = srcs.iter().enumerate().any(|(arg, src)| {
+                        otw_temps.contains(src)
+                            && matches!(
+                                callee_function.local_types.get(arg),
+                                Some(Type::TypeParameter(_))
+                            )
+                    });
+                    let checked = callee_function.code.iter().any(|bytecode| match bytecode {
+                        Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) => {
+                            sui::get_framework_fname(callee_stbgr, *mid, *fid).as_deref()
+                                == Some(sui::IS_ONE_TIME_WITNESS)
+                        }
+                        _ => false,
+                    });
+                    if generic_witness && !checked {
+                        callees.insert(callee);
+                    }
+                }
+                _ => {}
+            }
+        }
+        callees
+    }
+}
+
+/// `init` must be private, not entry, return nothing and take `&TxContext` or
+/// `&mut TxContext` last.
+fn check_init(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Vec<String> {
+    let mut problems = vec![];
+    if utils::get_visibility(idx, stbgr) != Visibility::Private {
+        problems.push("init(must be private)".to_string());
+    }
+    if utils::is_entry(idx, stbgr) {
+        problems.push("init(must not be entry)".to_string());
+    }
+    let func_def = stbgr
+        .module
+        .function_def_at(FunctionDefinitionIndex::new(idx as u16));
+    let handle = stbgr.module.function_handle_at(func_def.function);
+    if !stbgr.module.signature_at(handle.return_).is_empty() {
+        problems.push("init(must not return values)".to_string());
+    }
+    let param_count = utils::get_param_count(idx, stbgr);
+    let takes_ctx = param_count > 0
+        && match &stbgr.functions[idx].local_types[param_count - 1] {
+            Type::Reference(_, inner) => match inner.as_ref() {
+                Type::Struct(mid, sid, _) => {
+                    sui::get_framework_struct_name(stbgr, *mid, *sid).as_deref()
+                        == Some(sui::TX_CONTEXT)
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+    if param_count > 2 || !takes_ctx {
+        problems.push(
+            "init(parameters must be an optional one-time witness and TxContext)".to_string(),
+        );
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        Ability, AbilitySet, Bytecode as MoveBytecode, CompiledModule, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, with_paths, ModuleBuilder};
+
+    /// `token::TOKEN has drop { dummy_field: bool }` with `init(otw: TOKEN, ctx: &TxContext)`
+    /// and `forge(): TOKEN`, which packs the witness outside of `init`.
+    fn token_module() -> CompiledModule {
+        let mut builder = ModuleBuilder::new("token");
+        let (otw, otw_def) = builder.define_struct(
+            "TOKEN",
+            AbilitySet::EMPTY | Ability::Drop,
+            vec![("dummy_field", SignatureToken::Bool)],
+        );
+        let (_, ctx_ref) = builder.tx_context();
+        let init = builder.declare("init", vec![SignatureToken::Struct(otw), ctx_ref], vec![]);
+        let forge = builder.declare("forge", vec![], vec![SignatureToken::Struct(otw)]);
+        builder.define(init, Visibility::Private, vec![], vec![MoveBytecode::Ret]);
+        builder.define(
+            forge,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::LdFalse,
+                MoveBytecode::Pack(otw_def),
+                MoveBytecode::Ret,
+            ],
+        );
+        builder.build()
+    }
+
+    /// `public fun init(ctx: &mut TxContext): u64`.
+    fn public_init_module() -> CompiledModule {
+        let mut builder = ModuleBuilder::new("registry");
+        let (ctx_mut, _) = builder.tx_context();
+        let init = builder.declare("init", vec![ctx_mut], vec![SignatureToken::U64]);
+        builder.define(
+            init,
+            Visibility::Public,
+            vec![],
+            vec![MoveBytecode::LdU64(0), MoveBytecode::Ret],
+        );
+        builder.build()
+    }
+
+    #[test]
+    fn test_invalid_one_time_witness() {
+        let cms = with_paths(vec![token_module(), public_init_module()]);
+        let packages = Packages::new(&cms);
+        let mut detector = InvalidOneTimeWitness::new(&packages);
+        let content = detector.run();
+        let findings = findings(content);
+        assert!(findings.contains(&"init(must be private)".to_string()));
+        assert!(findings.contains(&"init(must not return values)".to_string()));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.starts_with("forge(packs TOKEN at ")));
+
+        // both `&TxContext` and `&mut TxContext` are accepted, and `TOKEN` is well-formed
+        assert_eq!(findings.len(), 3);
+    }
+}
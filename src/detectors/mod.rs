@@ -0,0 +1,45 @@
+use crate::{
+    move_ir::{
+        generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+        packages::Packages,
+    },
+    scanner::result::DetectContent,
+};
+
+pub mod dead_store;
+pub mod invalid_one_time_witness;
+pub mod mutable_reference_leak;
+pub mod narrowing_cast;
+pub mod randomness_misuse;
+pub mod recursive_function_call;
+pub mod repeated_function_call;
+pub mod shared_object_access_control;
+pub mod taint_flow;
+pub mod timestamp_dependence;
+pub mod unbounded_iteration;
+pub mod unchecked_hot_potato;
+pub mod unguarded_mint;
+pub mod unnecessary_bool_judgment;
+pub mod unnecessary_type_conversion;
+pub mod unreturned_coin_remainder;
+pub mod unused_constant;
+pub mod unused_private_functions;
+
+pub trait AbstractDetector<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self
+    where
+        Self: Sized;
+    fn run(&mut self) -> &DetectContent;
+}
+
+/// `offset`, or `offset: snippet` when the source map and the source code are loaded
+pub fn offset_detail(
+    stbgr: &StacklessBytecodeGenerator,
+    function: &FunctionInfo,
+    offset: usize,
+) -> String {
+    match stbgr.get_source_snippet(function, offset) {
+        Some(snippet) => format!("{}: {}", offset, snippet),
+        None => offset.to_string(),
+    }
+}
@@ -0,0 +1,188 @@
+use move_binary_format::file_format::Visibility;
+use move_model::ty::Type;
+use move_stackless_bytecode::stackless_bytecode::Bytecode;
+
+use crate::{
+    detectors::AbstractDetector,
+    move_ir::{
+        packages::Packages,
+        sui,
+        utils::{self, RefRoot},
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct MutableReferenceLeak<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for MutableReferenceLeak<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Medium, DetectKind::MutableReferenceLeak),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                // entry 函数的返回值无法被其他 package 使用
+                if utils::get_visibility(idx, stbgr) != Visibility::Public
+                    || utils::is_entry(idx, stbgr)
+                {
+                    continue;
+                }
+                let roots = utils::get_ref_roots(function, utils::get_param_count(idx, stbgr));
+                let mut exposed = vec![];
+                for bytecode in function.code.iter() {
+                    let srcs = match bytecode {
+                        Bytecode::Ret(_, srcs) => srcs,
+                        _ => continue,
+                    };
+                    for src in srcs.iter() {
+                        let inner = match &function.local_types[*src] {
+                            Type::Reference(true, inner) => inner.as_ref(),
+                            _ => continue,
+                        };
+                        let is_uid = match inner {
+                            Type::Struct(mid, sid, _) => {
+                                sui::get_framework_struct_name(stbgr, *mid, *sid).as_deref()
+                                    == Some(sui::UID)
+                            }
+                            _ => false,
+                        };
+                        let field = match roots.get(src) {
+                            Some(RefRoot {
+                                field: Some((mid, sid, offset)),
+                                ..
+                            }) if stbgr
+                                .get_struct_abilities(*mid, *sid)
+                                .map_or(false, |abilities| abilities.has_key()) =>
+                            {
+                                let struct_name = stbgr.get_struct_full_name(*mid, *sid);
+                                Some(format!(
+                                    "{}.{}",
+                                    struct_name.rsplit("::").next().unwrap(),
+                                    stbgr.get_field_name(*sid, *offset)
+                                ))
+                            }
+                            _ => None,
+                        };
+                        let detail = match (field, is_uid) {
+                            (Some(field), _) => format!("&mut {}", field),
+                            (None, true) => "&mut UID".to_string(),
+                            (None, false) => continue,
+                        };
+                        if !exposed.contains(&detail) {
+                            exposed.push(detail);
+                        }
+                    }
+                }
+                for detail in exposed {
+                    res.push(format!("{}(returns {})", function.name, detail));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        Ability, AbilitySet, Bytecode as MoveBytecode, SignatureToken,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_mutable_reference_leak() {
+        let mut builder = ModuleBuilder::new("registry");
+        let object = builder.module_handle(2, "object");
+        let uid = SignatureToken::Struct(builder.struct_handle(
+            object,
+            "UID",
+            AbilitySet::EMPTY | Ability::Store,
+        ));
+        let (registry, registry_def) = builder.define_struct(
+            "Registry",
+            AbilitySet::EMPTY | Ability::Key,
+            vec![("id", uid), ("count", SignatureToken::U64)],
+        );
+        let (counter, counter_def) = builder.define_struct(
+            "Counter",
+            AbilitySet::EMPTY | Ability::Store,
+            vec![("value", SignatureToken::U64)],
+        );
+        let registry_mut =
+            SignatureToken::MutableReference(Box::new(SignatureToken::Struct(registry)));
+        let registry_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(registry)));
+        let counter_mut =
+            SignatureToken::MutableReference(Box::new(SignatureToken::Struct(counter)));
+        let u64_mut = SignatureToken::MutableReference(Box::new(SignatureToken::U64));
+        let u64_ref = SignatureToken::Reference(Box::new(SignatureToken::U64));
+        let count_mut = builder.declare(
+            "count_mut",
+            vec![registry_mut.clone()],
+            vec![u64_mut.clone()],
+        );
+        let friend_count_mut = builder.declare(
+            "friend_count_mut",
+            vec![registry_mut],
+            vec![u64_mut.clone()],
+        );
+        let count = builder.declare("count", vec![registry_ref], vec![u64_ref]);
+        let value_mut = builder.declare("value_mut", vec![counter_mut], vec![u64_mut]);
+        let registry_count = builder.field_handle(registry_def, 1);
+        let counter_value = builder.field_handle(counter_def, 0);
+
+        let borrow = |field, mutable| {
+            vec![
+                MoveBytecode::MoveLoc(0),
+                if mutable {
+                    MoveBytecode::MutBorrowField(field)
+                } else {
+                    MoveBytecode::ImmBorrowField(field)
+                },
+                MoveBytecode::Ret,
+            ]
+        };
+        builder.define(
+            count_mut,
+            Visibility::Public,
+            vec![],
+            borrow(registry_count, true),
+        );
+        builder.define(
+            friend_count_mut,
+            Visibility::Friend,
+            vec![],
+            borrow(registry_count, true),
+        );
+        builder.define(
+            count,
+            Visibility::Public,
+            vec![],
+            borrow(registry_count, false),
+        );
+        builder.define(
+            value_mut,
+            Visibility::Public,
+            vec![],
+            borrow(counter_value, true),
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = MutableReferenceLeak::new(&packages);
+        let findings = findings(detector.run());
+        assert_eq!(findings, vec!["count_mut(returns &mut Registry.count)"]);
+        // 其他 package 拿不到 friend 函数的返回值，&u64 与非 key 结构体的字段也不算泄露
+        assert!(!reports(&findings, "friend_count_mut"));
+    }
+}
@@ -0,0 +1,274 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use ethnum::U256;
+use itertools::Itertools;
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        control_flow_graph, generate_bytecode::FunctionInfo, interval, packages::Packages, utils,
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct NarrowingCast<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for NarrowingCast<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Medium, DetectKind::NarrowingCast),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if utils::is_native(idx, stbgr) {
+                    continue;
+                }
+                let param_count = utils::get_param_count(idx, stbgr);
+                if param_count == 0 {
+                    continue;
+                }
+                // 只有用户能经由 public/entry 函数传入参数时，大输入才可达
+                let entry_points = self
+                    .packages
+                    .get_entry_points(&format!("{}::{}", mname, function.name));
+                if entry_points.is_empty() {
+                    continue;
+                }
+                let data_depent = &stbgr.data_dependency[idx];
+                let operands = get_operand_params(function, param_count);
+                let intervals = interval::get_intervals(idx, stbgr);
+                let max_of = |offset: usize, temp: TempIndex| {
+                    intervals
+                        .as_ref()
+                        .and_then(|intervals| intervals.before.get(&(offset as CodeOffset)))
+                        .and_then(|state| state.get(&temp))
+                        .map(|range| range.hi)
+                        .or_else(|| data_depent.get_at(function, offset, temp).max)
+                };
+                let mut casts = vec![];
+                for (offset, bytecode) in function.code.iter().enumerate() {
+                    let (dst, src) = match bytecode {
+                        Bytecode::Call(_, dsts, oper, srcs, _) if is_narrowing(oper) => {
+                            (dsts[0], srcs[0])
+                        }
+                        _ => continue,
+                    };
+                    let params = match operands.get(&offset).and_then(|srcs| srcs.first()) {
+                        Some(params) if !params.is_empty() => params,
+                        _ => continue,
+                    };
+                    // 源值的上界已经落在目标类型内，例如 x % 256 as u8 或 if (x < 256) x as u8
+                    let (src_max, target_max) = match (
+                        max_of(offset, src),
+                        utils::get_uint_bits(&function.local_types[dst]),
+                    ) {
+                        (Some(src_max), Some(bits)) => (src_max, uint_max(bits)),
+                        _ => continue,
+                    };
+                    if src_max <= target_max {
+                        continue;
+                    }
+                    casts.push((offset, dst, params.clone(), target_max));
+                }
+                if casts.is_empty() {
+                    continue;
+                }
+                let dominates = control_flow_graph::get_dominance(function);
+                for (offset, dst, params, target_max) in casts {
+                    let mut expr = String::new();
+                    if let Some(node) = data_depent.get_def(offset as CodeOffset, dst) {
+                        node.display(&mut expr, stbgr);
+                    }
+                    let param_names = params
+                        .iter()
+                        .map(|param| {
+                            stbgr
+                                .get_local_name(FunctionDefinitionIndex::new(idx as u16), *param)
+                                .display(&stbgr.symbol_pool)
+                                .to_string()
+                        })
+                        .join(", ");
+                    // 支配该 cast、一侧来自同一参数、另一侧的上界落在目标类型内的比较，
+                    // 例如 (amount as u8) 之前的 assert!(amount <= MAX_U8)
+                    let guard = operands.iter().find(|(cmp_offset, srcs)| {
+                        let strict = match &function.code[**cmp_offset] {
+                            Bytecode::Call(_, _, Operation::Lt | Operation::Gt, _, _) => true,
+                            Bytecode::Call(_, _, Operation::Le | Operation::Ge, _, _) => false,
+                            _ => return false,
+                        };
+                        let bound = match srcs.as_slice() {
+                            [lhs, rhs] if !lhs.is_disjoint(&params) && rhs.is_empty() => 1,
+                            [lhs, rhs] if !rhs.is_disjoint(&params) && lhs.is_empty() => 0,
+                            _ => return false,
+                        };
+                        let bound_max = match &function.code[**cmp_offset] {
+                            Bytecode::Call(_, _, _, cmp_srcs, _) => {
+                                max_of(**cmp_offset, cmp_srcs[bound])
+                            }
+                            _ => None,
+                        };
+                        let fits = match bound_max {
+                            Some(bound_max) if strict => bound_max <= target_max + U256::ONE,
+                            Some(bound_max) => bound_max <= target_max,
+                            None => false,
+                        };
+                        fits && dominates(**cmp_offset, offset)
+                    });
+                    let status = match guard {
+                        Some((cmp_offset, _)) => format!(
+                            "guarded by comparison at {}",
+                            offset_detail(stbgr, function, *cmp_offset)
+                        ),
+                        None => "unguarded".to_string(),
+                    };
+                    res.push(format!(
+                        "{}({}: {} at {}, {})",
+                        function.name,
+                        param_names,
+                        expr,
+                        offset_detail(stbgr, function, offset),
+                        status
+                    ));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+/// Casts that can abort because the target is narrower than some source type.
+fn is_narrowing(oper: &Operation) -> bool {
+    matches!(
+        oper,
+        Operation::CastU8
+            | Operation::CastU16
+            | Operation::CastU32
+            | Operation::CastU64
+            | Operation::CastU128
+    )
+}
+
+fn uint_max(bits: usize) -> U256 {
+    if bits >= 256 {
+        U256::MAX
+    } else {
+        (U256::ONE << bits as u32) - U256::ONE
+    }
+}
+
+/// Value parameters each operand of every call is derived from, keyed by code offset.
+/// Reference parameters are objects the caller passes in, not amounts it chooses.
+fn get_operand_params(
+    function: &FunctionInfo,
+    param_count: usize,
+) -> BTreeMap<usize, Vec<BTreeSet<usize>>> {
+    let mut params: BTreeMap<TempIndex, BTreeSet<usize>> = (0..param_count)
+        .filter(|param| !matches!(function.local_types[*param], Type::Reference(..)))
+        .map(|param| (param, BTreeSet::from([param])))
+        .collect();
+    let mut operands = BTreeMap::new();
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) => {
+                let origin = params.get(src).cloned().unwrap_or_default();
+                params.insert(*dst, origin);
+            }
+            Bytecode::Call(_, dsts, _, srcs, _) => {
+                let srcs: Vec<BTreeSet<usize>> = srcs
+                    .iter()
+                    .map(|src| params.get(src).cloned().unwrap_or_default())
+                    .collect();
+                let origin: BTreeSet<usize> = srcs.iter().flatten().copied().collect();
+                for dst in dsts.iter() {
+                    params.insert(*dst, origin.clone());
+                }
+                operands.insert(offset, srcs);
+            }
+            _ => {}
+        }
+    }
+    operands
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_narrowing_cast() {
+        let mut builder = ModuleBuilder::new("m");
+        let params = vec![SignatureToken::U64];
+        let returns = vec![SignatureToken::U8];
+        let to_u8 = builder.declare("to_u8", params.clone(), returns.clone());
+        let guarded = builder.declare("guarded", params.clone(), returns.clone());
+        let masked = builder.declare("masked", params.clone(), returns.clone());
+        let private = builder.declare("private", params, returns);
+        // to_u8(amount) { (amount as u8) }
+        let cast = vec![
+            MoveBytecode::MoveLoc(0),
+            MoveBytecode::CastU8,
+            MoveBytecode::Ret,
+        ];
+        builder.define(to_u8, Visibility::Public, vec![], cast.clone());
+        builder.define(private, Visibility::Private, vec![], cast);
+        // guarded(amount) { assert!(amount <= 255); (amount as u8) }
+        builder.define(
+            guarded,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::LdU64(255),
+                MoveBytecode::Le,
+                MoveBytecode::BrTrue(6),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Abort,
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::CastU8,
+                MoveBytecode::Ret,
+            ],
+        );
+        // masked(amount) { ((amount % 256) as u8) }
+        builder.define(
+            masked,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::LdU64(256),
+                MoveBytecode::Mod,
+                MoveBytecode::CastU8,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = NarrowingCast::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "to_u8"));
+        assert!(findings[0].ends_with(", unguarded)"));
+        // 比较支配了 cast，或者区间分析已经证明不会溢出
+        assert!(!findings
+            .iter()
+            .any(|finding| finding.starts_with("guarded(") && finding.ends_with(", unguarded)")));
+        assert!(!reports(&findings, "masked"));
+        // 没有 public/entry 函数能传入参数
+        assert!(!reports(&findings, "private"));
+    }
+}
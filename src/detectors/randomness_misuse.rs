@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::file_format::Visibility;
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+        packages::Packages,
+        sui,
+        summary::{self, FunctionSummary},
+        utils,
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct RandomnessMisuse<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for RandomnessMisuse<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Major, DetectKind::RandomnessMisuse),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                let seeds = get_random_params(idx, stbgr);
+                let calls_generator = function
+                    .code
+                    .iter()
+                    .any(|bytecode| is_framework_call(bytecode, stbgr, sui::RANDOM_NEW_GENERATOR));
+                if seeds.is_empty() && !calls_generator {
+                    continue;
+                }
+                // 其他合约可以组合调用 public 函数，在结果不利时 abort 整个交易
+                if utils::get_visibility(idx, stbgr) == Visibility::Public
+                    && !utils::is_entry(idx, stbgr)
+                {
+                    res.push(format!(
+                        "{}(public function reads {})",
+                        function.name,
+                        sui::RANDOM
+                    ));
+                }
+                if utils::is_entry(idx, stbgr) {
+                    let branch_abort =
+                        get_random_branch_abort(function, stbgr, &self.packages.summaries, seeds);
+                    if let Some((branch, abort)) = branch_abort {
+                        res.push(format!(
+                            "{}(branches on randomness at {} before abortable operation at {})",
+                            function.name,
+                            offset_detail(stbgr, function, branch),
+                            offset_detail(stbgr, function, abort)
+                        ));
+                    }
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+fn is_framework_call(bytecode: &Bytecode, stbgr: &StacklessBytecodeGenerator, name: &str) -> bool {
+    match bytecode {
+        Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) => {
+            sui::get_framework_fname(stbgr, *mid, *fid).as_deref() == Some(name)
+        }
+        _ => false,
+    }
+}
+
+/// Parameters of type `&Random`.
+fn get_random_params(idx: usize, stbgr: &StacklessBytecodeGenerator) -> BTreeSet<TempIndex> {
+    let function = &stbgr.functions[idx];
+    (0..utils::get_param_count(idx, stbgr))
+        .filter(|param| match &function.local_types[*param] {
+            Type::Reference(_, inner) => match inner.as_ref() {
+                Type::Struct(mid, sid, _) => {
+                    sui::get_framework_struct_name(stbgr, *mid, *sid).as_deref()
+                        == Some(sui::RANDOM)
+                }
+                _ => false,
+            },
+            _ => false,
+        })
+        .collect()
+}
+
+/// The first branch on a value derived from `Random`, and the first operation reachable
+/// from it on the control-flow graph that can abort: an `abort`/`assert!`, arithmetic or
+/// a cast that may overflow, or a call that may abort (package functions by their
+/// summary, e.g. a balance withdrawal). The caller can then abort or run out of gas on the
+/// unwanted outcome and retry until it wins.
+fn get_random_branch_abort(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+    summaries: &BTreeMap<String, FunctionSummary>,
+    seeds: BTreeSet<TempIndex>,
+) -> Option<(usize, usize)> {
+    let cfg = function.cfg.as_ref()?;
+    let mut tainted = seeds;
+    let mut branch = None;
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) if tainted.contains(src) => {
+                tainted.insert(*dst);
+            }
+            Bytecode::Call(_, dsts, _, srcs, _)
+                if srcs.iter().any(|src| tainted.contains(src))
+                    || is_framework_call(bytecode, stbgr, sui::RANDOM_NEW_GENERATOR) =>
+            {
+                tainted.extend(dsts.iter().copied());
+            }
+            Bytecode::Branch(_, _, _, cond) if tainted.contains(cond) => {
+                branch = Some(offset);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let branch = branch?;
+
+    // 从分支所在块的后继出发，遍历所有可达块
+    let branch_block = cfg.blocks().into_iter().find(|block| {
+        cfg.instr_indexes(*block)
+            .into_iter()
+            .flatten()
+            .any(|offset| offset as usize == branch)
+    })?;
+    let mut visited = BTreeSet::new();
+    let mut worklist = cfg.successors(branch_block).clone();
+    let mut abort = None;
+    while let Some(block) = worklist.pop() {
+        if !visited.insert(block) {
+            continue;
+        }
+        for offset in cfg.instr_indexes(block).into_iter().flatten() {
+            let offset = offset as usize;
+            let abortable = match &function.code[offset] {
+                Bytecode::Abort(..) => true,
+                Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) => {
+                    summary::callee_may_abort(summaries, &stbgr.get_full_fname(*mid, *fid))
+                }
+                Bytecode::Call(_, _, oper, _, _) => summary::may_abort(oper),
+                _ => false,
+            };
+            if abortable && abort.map_or(true, |abort| offset < abort) {
+                abort = Some(offset);
+            }
+        }
+        worklist.extend(cfg.successors(block).iter().copied());
+    }
+    abort.map(|abort| (branch, abort))
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        Ability, AbilitySet, Bytecode as MoveBytecode, SignatureToken,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_randomness_misuse() {
+        let mut builder = ModuleBuilder::new("lottery");
+        let random = builder.module_handle(2, "random");
+        let random_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(
+            builder.struct_handle(random, "Random", AbilitySet::EMPTY | Ability::Key),
+        )));
+        let generator = SignatureToken::Struct(builder.struct_handle(
+            random,
+            "RandomGenerator",
+            AbilitySet::EMPTY | Ability::Drop,
+        ));
+        let generator_mut = SignatureToken::MutableReference(Box::new(generator.clone()));
+        let (ctx_mut, _) = builder.tx_context();
+        let play = builder.declare("play", vec![random_ref.clone(), ctx_mut.clone()], vec![]);
+        let draw = builder.declare("draw", vec![random_ref.clone(), ctx_mut.clone()], vec![]);
+        let peek = builder.declare("peek", vec![random_ref.clone(), ctx_mut.clone()], vec![]);
+        let new_generator = builder.function_handle(
+            random,
+            "new_generator",
+            vec![random_ref, ctx_mut],
+            vec![generator.clone()],
+        );
+        let generate_bool = builder.function_handle(
+            random,
+            "generate_bool",
+            vec![generator_mut],
+            vec![SignatureToken::Bool],
+        );
+        // random::generate_bool(&mut random::new_generator(r, ctx))
+        let generate = vec![
+            MoveBytecode::MoveLoc(0),
+            MoveBytecode::MoveLoc(1),
+            MoveBytecode::Call(new_generator),
+            MoveBytecode::StLoc(2),
+            MoveBytecode::MutBorrowLoc(2),
+            MoveBytecode::Call(generate_bool),
+        ];
+        // entry play(r, ctx) { if (generate_bool(..)) abort 0 }
+        let mut code = generate.clone();
+        code.extend([
+            MoveBytecode::BrFalse(9),
+            MoveBytecode::LdU64(0),
+            MoveBytecode::Abort,
+            MoveBytecode::Ret,
+        ]);
+        builder.define(play, Visibility::Private, vec![generator.clone()], code);
+        builder.entry(play);
+        // entry draw(r, ctx) { generate_bool(..); }
+        let mut code = generate.clone();
+        code.extend([MoveBytecode::Pop, MoveBytecode::Ret]);
+        builder.define(draw, Visibility::Private, vec![generator.clone()], code);
+        builder.entry(draw);
+        // public peek(r, ctx) { generate_bool(..); }
+        let mut code = generate;
+        code.extend([MoveBytecode::Pop, MoveBytecode::Ret]);
+        builder.define(peek, Visibility::Public, vec![generator], code);
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = RandomnessMisuse::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "play"));
+        assert!(findings[0].starts_with("play(branches on randomness at "));
+        // 随机数没有决定分支
+        assert!(!reports(&findings, "draw"));
+        assert!(findings.contains(&"peek(public function reads 0x2::random::Random)".to_string()));
+    }
+}
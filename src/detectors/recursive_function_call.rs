@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+use petgraph::{algo::tarjan_scc, graph::NodeIndex};
+
+use crate::{
+    detectors::AbstractDetector,
+    move_ir::packages::Packages,
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct RecursiveFunctionCall<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for RecursiveFunctionCall<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Minor, DetectKind::RecursiveFunctionCall),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let graph = &self.packages.call_graph;
+        let mut res: BTreeMap<String, Vec<String>> = self
+            .packages
+            .get_module_names()
+            .into_iter()
+            .map(|mname| (mname, vec![]))
+            .collect();
+        for scc in tarjan_scc(graph) {
+            let is_cycle = scc.len() > 1 || graph.find_edge(scc[0], scc[0]).is_some();
+            if !is_cycle {
+                continue;
+            }
+            let members: BTreeSet<NodeIndex> = scc.into_iter().collect();
+            let start = *members.iter().min_by_key(|n| &graph[**n]).unwrap();
+            let path = find_cycle(self.packages, &members, start)
+                .iter()
+                .map(|n| graph[*n].as_str())
+                .join(" -> ");
+            // 环上的每个 module 都记录这条路径，跨 module 的互相递归在每个 module 中都可见
+            for node in members.iter() {
+                let (mname, fname) = graph[*node].rsplit_once("::").unwrap();
+                if let Some(values) = res.get_mut(mname) {
+                    values.push(format!("{}({})", fname, path));
+                }
+            }
+        }
+        self.content.result.extend(res);
+        &self.content
+    }
+}
+
+/// Find a call path `start -> ... -> start` that stays inside one SCC.
+fn find_cycle(
+    packages: &Packages,
+    members: &BTreeSet<NodeIndex>,
+    start: NodeIndex,
+) -> Vec<NodeIndex> {
+    let graph = &packages.call_graph;
+    let mut path = vec![start];
+    let mut visited = BTreeSet::from([start]);
+    let mut stack = vec![graph.neighbors(start).collect_vec()];
+    while let Some(successors) = stack.last_mut() {
+        match successors.pop() {
+            Some(next) if next == start => {
+                path.push(start);
+                return path;
+            }
+            Some(next) if members.contains(&next) && !visited.contains(&next) => {
+                visited.insert(next);
+                path.push(next);
+                stack.push(graph.neighbors(next).collect_vec());
+            }
+            Some(_) => {}
+            None => {
+                stack.pop();
+                path.pop();
+            }
+        }
+    }
+    // 同一个 SCC 内一定存在回到 start 的路径
+    unreachable!("no cycle through {} in its SCC", graph[start])
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_recursive_function_call() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_ty = SignatureToken::U64;
+        let ping = builder.declare("ping", vec![u64_ty.clone()], vec![]);
+        let pong = builder.declare("pong", vec![u64_ty.clone()], vec![]);
+        let caller = builder.declare("caller", vec![u64_ty], vec![]);
+        // ping(n) { if (n > 0) pong(n - 1) }, pong(n) { ping(n) }
+        builder.define(
+            ping,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Gt,
+                MoveBytecode::BrFalse(8),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Sub,
+                MoveBytecode::Call(pong),
+                MoveBytecode::Ret,
+            ],
+        );
+        builder.define(
+            pong,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(ping),
+                MoveBytecode::Ret,
+            ],
+        );
+        // caller(n) { ping(n) }，调用了环但自身不在环上
+        builder.define(
+            caller,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(ping),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = RecursiveFunctionCall::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "ping"));
+        assert!(reports(&findings, "pong"));
+        assert!(findings[0].contains("::ping -> "));
+        assert!(!reports(&findings, "caller"));
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::stackless_bytecode::{Bytecode::*, Operation};
+
+use crate::{
+    detectors::AbstractDetector,
+    move_ir::{
+        bytecode_display::oper_display,
+        control_flow_graph,
+        data_dependency::{Node, Val},
+        dataflow::get_defs,
+        generate_bytecode::StacklessBytecodeGenerator,
+        packages::Packages,
+        utils,
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct RepeatedFunctionCall<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for RepeatedFunctionCall<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Info, DetectKind::RepeatedFunctionCall),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if utils::is_native(idx, stbgr) {
+                    continue;
+                }
+                let data_depent = &stbgr.data_dependency[idx];
+                let dominates = control_flow_graph::get_dominance(function);
+                // 调用的 key -> (调用处, 实参读取的 temp)，其中任一被重新赋值后不能再复用该调用
+                let mut seen: BTreeMap<String, Vec<(usize, BTreeSet<TempIndex>)>> = BTreeMap::new();
+                let mut repeated = BTreeSet::new();
+                for (offset, code) in function.code.iter().enumerate() {
+                    for dst in get_defs(code) {
+                        for calls in seen.values_mut() {
+                            calls.retain(|(_, temps)| !temps.contains(&dst));
+                        }
+                    }
+                    match code {
+                        Call(_, dsts, oper @ Operation::Function(mid, fid, _), srcs, _) => {
+                            // 传入 &mut 的调用会修改状态，之前的调用结果不再可复用
+                            if srcs
+                                .iter()
+                                .any(|src| function.local_types[*src].is_mutable_reference())
+                            {
+                                seen.clear();
+                                continue;
+                            }
+                            if dsts.is_empty() {
+                                continue;
+                            }
+                            // 按到达调用处的定义比较实参
+                            let mut key = format!("{}", oper_display(oper, stbgr));
+                            let mut temps = BTreeSet::new();
+                            for src in srcs {
+                                key.push('|');
+                                let node = data_depent.get_at(function, offset, *src);
+                                node_key(&node, stbgr, &mut key);
+                                get_read_temps(&node, &mut temps);
+                            }
+                            // 之前的调用必须在每条到达此处的路径上，互斥分支中的调用不算重复
+                            let calls = seen.entry(key).or_default();
+                            if calls.iter().any(|(prev, _)| dominates(*prev, offset)) {
+                                repeated.insert(stbgr.get_full_fname(*mid, *fid));
+                            }
+                            calls.push((offset, temps));
+                        }
+                        Call(_, _, Operation::WriteRef, _, _) => seen.clear(),
+                        _ => {}
+                    }
+                }
+                for callee in repeated {
+                    res.push(format!("{}({})", function.name, callee));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+/// Structural key of a data-dependency tree, two arguments with the same key
+/// are computed from the same parameters, constants and operations.
+fn node_key(node: &Node, stbgr: &StacklessBytecodeGenerator, key: &mut String) {
+    match &node.value {
+        Val::ByteCode(Call(attr_id, _, oper, _, _)) => {
+            if let Operation::Function(..) = oper {
+                // 跨函数的依赖树不含实参，无法比较，视为互不相同
+                key.push_str(&format!("call#{}", attr_id.as_usize()));
+                return;
+            }
+            key.push_str(&format!("{}(", oper_display(oper, stbgr)));
+            for subnode in node.subnodes.iter() {
+                node_key(&subnode.borrow(), stbgr, key);
+                key.push(',');
+            }
+            key.push(')');
+        }
+        Val::ByteCode(_) => key.push('?'),
+        Val::Const(con) => key.push_str(&con.to_string()),
+        Val::ParamType(param, _) => key.push_str(&format!("param{}", param)),
+        Val::AssIgn(Assign(_, _, src, _)) => {
+            let subnode = node.subnodes[0].borrow();
+            if let Val::ParamType(..) = subnode.value {
+                key.push_str(&format!("$t{}", src));
+            } else {
+                node_key(&subnode, stbgr, key);
+            }
+        }
+        Val::AssIgn(_) => key.push('?'),
+        Val::Join(_) => {
+            key.push_str("join(");
+            for subnode in node.subnodes.iter() {
+                node_key(&subnode.borrow(), stbgr, key);
+                key.push('|');
+            }
+            key.push(')');
+        }
+        Val::LoopCarried(temp) => key.push_str(&format!("loop$t{}", temp)),
+    }
+}
+
+/// Locals a data-dependency tree reads through assignments, not following the trees of
+/// callees, whose temps belong to another function.
+fn get_read_temps(node: &Node, temps: &mut BTreeSet<TempIndex>) {
+    match &node.value {
+        Val::ByteCode(Call(_, _, Operation::Function(..), _, _)) => return,
+        Val::AssIgn(Assign(_, _, src, _)) => {
+            temps.insert(*src);
+        }
+        _ => {}
+    }
+    for subnode in node.subnodes.iter() {
+        get_read_temps(&subnode.borrow(), temps);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_repeated_function_call() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_ty = SignatureToken::U64;
+        let id = builder.declare("id", vec![u64_ty.clone()], vec![u64_ty.clone()]);
+        let twice = builder.declare("twice", vec![u64_ty.clone()], vec![u64_ty.clone()]);
+        let pair = builder.declare(
+            "pair",
+            vec![u64_ty.clone(), u64_ty.clone()],
+            vec![u64_ty.clone()],
+        );
+        let either = builder.declare(
+            "either",
+            vec![u64_ty.clone(), SignatureToken::Bool],
+            vec![u64_ty],
+        );
+        builder.define(
+            id,
+            Visibility::Public,
+            vec![],
+            vec![MoveBytecode::MoveLoc(0), MoveBytecode::Ret],
+        );
+        // twice(a) { id(a) + id(a) }
+        builder.define(
+            twice,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::Call(id),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(id),
+                MoveBytecode::Add,
+                MoveBytecode::Ret,
+            ],
+        );
+        // pair(a, b) { id(a) + id(b) }
+        builder.define(
+            pair,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(id),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(id),
+                MoveBytecode::Add,
+                MoveBytecode::Ret,
+            ],
+        );
+        // either(a, c) { if (c) id(a) else id(a) + 1 }
+        builder.define(
+            either,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::BrFalse(5),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(id),
+                MoveBytecode::Ret,
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(id),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Add,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = RepeatedFunctionCall::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "twice"));
+        // 实参来自不同参数
+        assert!(!reports(&findings, "pair"));
+        // 两次调用在互斥的分支中
+        assert!(!reports(&findings, "either"));
+    }
+}
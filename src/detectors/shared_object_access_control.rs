@@ -0,0 +1,255 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::file_format::{FunctionDefinitionIndex, Visibility};
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use petgraph::Direction;
+
+use crate::{
+    detectors::AbstractDetector,
+    move_ir::{
+        generate_bytecode::StacklessBytecodeGenerator,
+        packages::Packages,
+        sui::{self, CapabilityKind},
+        utils::{self, RefRoot},
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct SharedObjectAccessControl<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+// 被修改的共享对象参数 -> (调用路径, 被写的字段)
+type Mutations = BTreeMap<TempIndex, (Vec<String>, String)>;
+
+impl<'a> AbstractDetector<'a> for SharedObjectAccessControl<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Major, DetectKind::SharedObjectAccessControl),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let shared = sui::get_shared_structs(self.packages);
+        let capabilities = sui::get_capabilities(self.packages);
+        let mutated = self.get_unguarded_mutations(&shared, &capabilities);
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                let is_public = utils::get_visibility(idx, stbgr) == Visibility::Public
+                    || utils::is_entry(idx, stbgr);
+                let mutations = match mutated.get(&format!("{}::{}", mname, function.name)) {
+                    Some(mutations) if is_public => mutations,
+                    _ => continue,
+                };
+                for (param, (path, field)) in mutations.iter() {
+                    let param_name = stbgr
+                        .get_local_name(FunctionDefinitionIndex::new(idx as u16), *param)
+                        .display(&stbgr.symbol_pool)
+                        .to_string();
+                    res.push(format!(
+                        "{}({}: {} writes {})",
+                        function.name,
+                        param_name,
+                        path.join(" -> "),
+                        field
+                    ));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+impl<'a> SharedObjectAccessControl<'a> {
+    /// Shared object parameters each unguarded function modifies, directly or through
+    /// unguarded callees in the package. A guarded callee stops the propagation.
+    fn get_unguarded_mutations(
+        &self,
+        shared: &BTreeSet<String>,
+        capabilities: &BTreeMap<String, CapabilityKind>,
+    ) -> BTreeMap<String, Mutations> {
+        let guarded = self.get_guarded(shared, capabilities);
+        let mut mutated: BTreeMap<String, Mutations> = BTreeMap::new();
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                let fid = format!("{}::{}", mname, function.name);
+                if utils::is_native(idx, stbgr) || guarded.contains(&fid) {
+                    continue;
+                }
+                let param_count = utils::get_param_count(idx, stbgr);
+                let mut mutations = Mutations::new();
+                let writes =
+                    utils::get_field_writes(function, stbgr, param_count, &self.packages.summaries);
+                for write in writes {
+                    if !is_shared_mut(&function.local_types[write.param], stbgr, shared) {
+                        continue;
+                    }
+                    let (mid, sid, offset) = write.field;
+                    let struct_name = stbgr.get_struct_full_name(mid, sid);
+                    let field = format!(
+                        "{}.{}",
+                        struct_name.rsplit("::").next().unwrap(),
+                        stbgr.get_field_name(sid, offset)
+                    );
+                    mutations
+                        .entry(write.param)
+                        .or_insert_with(|| (vec![fid.clone()], field));
+                }
+                mutated.insert(fid, mutations);
+            }
+        }
+
+        // 沿调用图传播，直到不动点
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+                for (idx, function) in stbgr.functions.iter().enumerate() {
+                    let fid = format!("{}::{}", mname, function.name);
+                    if !mutated.contains_key(&fid) {
+                        continue;
+                    }
+                    let roots = utils::get_ref_roots(function, utils::get_param_count(idx, stbgr));
+                    for bytecode in function.code.iter() {
+                        let (callee, srcs) = match bytecode {
+                            Bytecode::Call(_, _, Operation::Function(mid, cid, _), srcs, _) => {
+                                (stbgr.get_full_fname(*mid, *cid), srcs)
+                            }
+                            _ => continue,
+                        };
+                        for (arg, src) in srcs.iter().enumerate() {
+                            let param = match roots.get(src) {
+                                Some(RefRoot { param, field: None }) => *param,
+                                _ => continue,
+                            };
+                            let callee_mutation = match mutated.get(&callee) {
+                                Some(mutations) => mutations.get(&arg).cloned(),
+                                None => None,
+                            };
+                            let (path, field) = match callee_mutation {
+                                Some(mutation) => mutation,
+                                None => continue,
+                            };
+                            let mutations = mutated.get_mut(&fid).unwrap();
+                            if mutations.contains_key(&param)
+                                || path.contains(&fid)
+                                || !is_shared_mut(&function.local_types[param], stbgr, shared)
+                            {
+                                continue;
+                            }
+                            let path = std::iter::once(fid.clone()).chain(path).collect();
+                            mutations.insert(param, (path, field));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        mutated
+    }
+
+    /// Functions taking a capability or checking the sender, and private or friend
+    /// functions whose callers in the package are all guarded. Public and entry functions
+    /// can be called from outside, so they only count when guarded themselves.
+    fn get_guarded(
+        &self,
+        shared: &BTreeSet<String>,
+        capabilities: &BTreeMap<String, CapabilityKind>,
+    ) -> BTreeSet<String> {
+        let mut guarded = BTreeSet::new();
+        let mut internal = vec![];
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                let fid = format!("{}::{}", mname, function.name);
+                if is_guarded(idx, stbgr, shared, capabilities) {
+                    guarded.insert(fid);
+                } else if utils::get_visibility(idx, stbgr) != Visibility::Public
+                    && !utils::is_entry(idx, stbgr)
+                {
+                    internal.push(fid);
+                }
+            }
+        }
+
+        // 沿调用图传播，直到不动点；没有调用者的函数不算受保护
+        let graph = &self.packages.call_graph;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for fid in internal.iter() {
+                if guarded.contains(fid) {
+                    continue;
+                }
+                let node = match self.packages.func_to_node.get(fid) {
+                    Some(node) => *node,
+                    None => continue,
+                };
+                let mut callers = graph
+                    .neighbors_directed(node, Direction::Incoming)
+                    .filter(|caller| *caller != node)
+                    .peekable();
+                if callers.peek().is_some()
+                    && callers.all(|caller| guarded.contains(&graph[caller]))
+                {
+                    guarded.insert(fid.clone());
+                    changed = true;
+                }
+            }
+        }
+        guarded
+    }
+}
+
+/// `&mut S` where S is a shared object
+fn is_shared_mut(ty: &Type, stbgr: &StacklessBytecodeGenerator, shared: &BTreeSet<String>) -> bool {
+    match ty {
+        Type::Reference(true, inner) => match inner.as_ref() {
+            Type::Struct(mid, sid, _) => shared.contains(&stbgr.get_struct_full_name(*mid, *sid)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_guarded(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    shared: &BTreeSet<String>,
+    capabilities: &BTreeMap<String, CapabilityKind>,
+) -> bool {
+    !sui::get_guards(idx, stbgr, capabilities, shared).is_empty()
+        || sui::checks_sender(&stbgr.functions[idx], stbgr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, shared_pool_module, with_paths};
+
+    #[test]
+    fn test_shared_object_access_control() {
+        let cms = with_paths(vec![shared_pool_module()]);
+        let packages = Packages::new(&cms);
+        // 共享的 Pool 不是 capability，不能保护以它为参数的函数
+        let capabilities = sui::get_capabilities(&packages);
+        assert!(capabilities.keys().all(|name| !name.ends_with("::Pool")));
+        assert!(capabilities.keys().any(|name| name.ends_with("::AdminCap")));
+
+        let mut detector = SharedObjectAccessControl::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "withdraw"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.starts_with("withdraw(") && finding.contains("Pool.balance")));
+        // 比较了 sender 但结果不决定 abort
+        assert!(reports(&findings, "unchecked_set"));
+        assert!(!reports(&findings, "admin_set"));
+        assert!(!reports(&findings, "checked_set"));
+        assert!(!reports(&findings, "init"));
+    }
+}
@@ -0,0 +1,93 @@
+use move_binary_format::file_format::FunctionDefinitionIndex;
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        generate_bytecode::StacklessBytecodeGenerator,
+        packages::Packages,
+        taint::{self, TaintConfig, TaintSource},
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct TaintFlow<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+    flows: Option<&'a [taint::TaintFlow]>, // None 时按默认配置计算
+}
+
+impl<'a> TaintFlow<'a> {
+    /// Report flows already computed for the scan, e.g. with the extra sources and sinks of
+    /// the `[taint]` table of MoveScanner.toml, which the graph export shares.
+    pub fn with_flows(packages: &'a Packages<'a>, flows: &'a [taint::TaintFlow]) -> Self {
+        Self {
+            flows: Some(flows),
+            ..Self::new(packages)
+        }
+    }
+}
+
+impl<'a> AbstractDetector<'a> for TaintFlow<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Info, DetectKind::TaintFlow),
+            flows: None,
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for mname in self.packages.get_all_stbgr().keys() {
+            self.content.result.insert(mname.to_string(), vec![]);
+        }
+        let computed;
+        let flows = match self.flows {
+            Some(flows) => flows,
+            None => {
+                computed = taint::get_taint_flows(self.packages, &TaintConfig::default());
+                &computed
+            }
+        };
+        for flow in flows.iter() {
+            let (stbgr, idx) = match self.packages.get_function_by_full_name(&flow.entry) {
+                Some(function) => function,
+                None => continue,
+            };
+            let source = display_source(&flow.source, idx, stbgr);
+            let (sink_stbgr, sink_idx) =
+                match self.packages.get_function_by_full_name(&flow.sink.function) {
+                    Some(function) => function,
+                    None => continue,
+                };
+            let sink_function = &sink_stbgr.functions[sink_idx];
+            let offset = offset_detail(sink_stbgr, sink_function, flow.sink.code_offset as usize);
+            let mname = flow.entry.rsplit_once("::").unwrap().0;
+            self.content
+                .result
+                .entry(mname.to_string())
+                .or_default()
+                .push(format!(
+                    "{}({} -> {} at {}; path: {})",
+                    stbgr.functions[idx].name,
+                    source,
+                    flow.sink.kind,
+                    offset,
+                    flow.path.join(" -> ")
+                ));
+        }
+        &self.content
+    }
+}
+
+/// `param amount` for a parameter of the function at `idx`, the function name for a source call.
+fn display_source(source: &TaintSource, idx: usize, stbgr: &StacklessBytecodeGenerator) -> String {
+    match source {
+        TaintSource::Param(param) => format!(
+            "param {}",
+            stbgr
+                .get_local_name(FunctionDefinitionIndex::new(idx as u16), *param)
+                .display(&stbgr.symbol_pool)
+        ),
+        TaintSource::Call(source) => source.clone(),
+    }
+}
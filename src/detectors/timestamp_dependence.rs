@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+        packages::Packages,
+        sui,
+        summary::FunctionSummary,
+        utils::{self, RefRoot},
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct TimestampDependence<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for TimestampDependence<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Minor, DetectKind::TimestampDependence),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if utils::is_native(idx, stbgr) {
+                    continue;
+                }
+                let param_count = utils::get_param_count(idx, stbgr);
+                let (comparisons, recorded) =
+                    get_timestamp_uses(function, stbgr, param_count, &self.packages.summaries);
+                if comparisons.is_empty() || recorded {
+                    continue;
+                }
+                let entry_points = self
+                    .packages
+                    .get_entry_points(&format!("{}::{}", mname, function.name));
+                if entry_points.is_empty() {
+                    continue;
+                }
+                for offset in comparisons {
+                    res.push(format!(
+                        "{}(compares {} at {} without recording it; entry points: {})",
+                        function.name,
+                        sui::CLOCK_TIMESTAMP_MS,
+                        offset_detail(stbgr, function, offset),
+                        entry_points.join(", ")
+                    ));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+/// Offsets of comparisons involving `clock::timestamp_ms`, and whether the timestamp is
+/// stored into a field of an object parameter: written through a field reference, or
+/// handed to a callee together with a `&mut` field or object it modifies, e.g.
+/// `table::add(&mut pool.last_claim, sender, now)`. A stored timestamp such as
+/// `last_claim_ms` is taken as rate limiting.
+fn get_timestamp_uses(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+    param_count: usize,
+    summaries: &BTreeMap<String, FunctionSummary>,
+) -> (Vec<usize>, bool) {
+    let roots = utils::get_ref_roots(function, param_count);
+    let mut tainted: BTreeSet<TempIndex> = BTreeSet::new();
+    let mut comparisons = vec![];
+    let mut recorded = false;
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) if tainted.contains(src) => {
+                tainted.insert(*dst);
+            }
+            Bytecode::Call(_, dsts, oper, srcs, _) => {
+                let is_timestamp = match oper {
+                    Operation::Function(mid, fid, _) => {
+                        sui::get_framework_fname(stbgr, *mid, *fid).as_deref()
+                            == Some(sui::CLOCK_TIMESTAMP_MS)
+                    }
+                    _ => false,
+                };
+                if is_timestamp {
+                    tainted.extend(dsts.iter().copied());
+                    continue;
+                }
+                if !srcs.iter().any(|src| tainted.contains(src)) {
+                    continue;
+                }
+                match oper {
+                    Operation::Lt
+                    | Operation::Le
+                    | Operation::Gt
+                    | Operation::Ge
+                    | Operation::Eq
+                    | Operation::Neq => comparisons.push(offset),
+                    Operation::WriteRef => {
+                        recorded |=
+                            matches!(roots.get(&srcs[0]), Some(RefRoot { field: Some(_), .. }));
+                    }
+                    Operation::Function(mid, fid, _) => {
+                        let callee = summaries.get(&stbgr.get_full_fname(*mid, *fid));
+                        recorded |= srcs.iter().enumerate().any(|(i, src)| {
+                            if !matches!(function.local_types[*src], Type::Reference(true, _)) {
+                                return false;
+                            }
+                            // 包外函数只认对象字段（如 table、UID）；包内函数看摘要是否修改该参数
+                            match (roots.get(src), callee) {
+                                (Some(RefRoot { field: Some(_), .. }), None) => true,
+                                (Some(_), Some(callee)) => callee.modified_params.contains(&i),
+                                _ => false,
+                            }
+                        });
+                    }
+                    _ => {}
+                }
+                tainted.extend(dsts.iter().copied());
+            }
+            _ => {}
+        }
+    }
+    (comparisons, recorded)
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        Ability, AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_timestamp_dependence() {
+        let mut builder = ModuleBuilder::new("faucet");
+        let object = builder.module_handle(2, "object");
+        let uid = SignatureToken::Struct(builder.struct_handle(
+            object,
+            "UID",
+            AbilitySet::EMPTY | Ability::Store,
+        ));
+        let clock = builder.module_handle(2, "clock");
+        let clock_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(
+            builder.struct_handle(clock, "Clock", AbilitySet::EMPTY | Ability::Key),
+        )));
+        let (state, state_def) = builder.define_struct(
+            "State",
+            AbilitySet::EMPTY | Ability::Key,
+            vec![("id", uid), ("last_claim_ms", SignatureToken::U64)],
+        );
+        let state_mut = SignatureToken::MutableReference(Box::new(SignatureToken::Struct(state)));
+        let claim = builder.declare(
+            "claim",
+            vec![clock_ref.clone(), SignatureToken::U64],
+            vec![],
+        );
+        let claim_recorded =
+            builder.declare("claim_recorded", vec![state_mut, clock_ref.clone()], vec![]);
+        let timestamp_ms = builder.function_handle(
+            clock,
+            "timestamp_ms",
+            vec![clock_ref],
+            vec![SignatureToken::U64],
+        );
+        let last_claim_ms = builder.field_handle(state_def, 1);
+
+        // claim(clock, start) { assert!(clock::timestamp_ms(clock) >= start) }
+        builder.define(
+            claim,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(timestamp_ms),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Ge,
+                MoveBytecode::BrTrue(7),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Abort,
+                MoveBytecode::Ret,
+            ],
+        );
+        // claim_recorded(state, clock) {
+        //     let now = clock::timestamp_ms(clock);
+        //     assert!(now >= state.last_claim_ms);
+        //     state.last_claim_ms = now
+        // }
+        builder.define(
+            claim_recorded,
+            Visibility::Public,
+            vec![SignatureToken::U64],
+            vec![
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(timestamp_ms),
+                MoveBytecode::StLoc(2),
+                MoveBytecode::CopyLoc(2),
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::ImmBorrowField(last_claim_ms),
+                MoveBytecode::ReadRef,
+                MoveBytecode::Ge,
+                MoveBytecode::BrTrue(11),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Abort,
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MutBorrowField(last_claim_ms),
+                MoveBytecode::WriteRef,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = TimestampDependence::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "claim"));
+        assert!(findings[0].contains("compares 0x2::clock::timestamp_ms at "));
+        // 时间戳被记录到对象字段中，视为限频
+        assert!(!reports(&findings, "claim_recorded"));
+    }
+}
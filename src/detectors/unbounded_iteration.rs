@@ -0,0 +1,284 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::{
+    stackless_bytecode::{Bytecode, Operation},
+    stackless_control_flow_graph::BlockContent,
+};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        fatloop,
+        generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+        packages::Packages,
+        sui,
+        utils::{self, RefRoot},
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UnboundedIteration<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnboundedIteration<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Medium, DetectKind::UnboundedIteration),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let shared = sui::get_shared_structs(self.packages);
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                let cfg = match function.cfg.as_ref() {
+                    Some(cfg) => cfg,
+                    None => continue,
+                };
+                let bounds = get_collection_bounds(idx, stbgr, &shared);
+                if bounds.is_empty() {
+                    continue;
+                }
+                let natural_loops = match fatloop::get_loops(function) {
+                    Ok((natural_loops, _)) => natural_loops,
+                    Err(_) => continue,
+                };
+                let mut reported = BTreeSet::new();
+                for natural_loop in natural_loops.iter() {
+                    let header = match cfg.content(natural_loop.loop_header) {
+                        BlockContent::Basic { lower, .. } => *lower as usize,
+                        BlockContent::Dummy => continue,
+                    };
+                    let collections = get_loop_bounds(function, &natural_loop.loop_body, &bounds);
+                    reported.extend(collections.into_iter().map(|c| (header, c)));
+                }
+                if reported.is_empty() {
+                    continue;
+                }
+                let entry_points = self
+                    .packages
+                    .get_entry_points(&format!("{}::{}", mname, function.name));
+                if entry_points.is_empty() {
+                    continue;
+                }
+                for (header, collection) in reported {
+                    res.push(format!(
+                        "{}({} bounds loop at {}; entry points: {})",
+                        function.name,
+                        collection,
+                        offset_detail(stbgr, function, header),
+                        entry_points.join(", ")
+                    ));
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+/// Temps derived from the size of a collection stored in a shared object, mapped to
+/// descriptions like `Pool.items via vector::length`.
+fn get_collection_bounds(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    shared: &BTreeSet<String>,
+) -> BTreeMap<TempIndex, BTreeSet<String>> {
+    let function = &stbgr.functions[idx];
+    let roots = utils::get_ref_roots(function, utils::get_param_count(idx, stbgr));
+    let mut bounds: BTreeMap<TempIndex, BTreeSet<String>> = BTreeMap::new();
+    for bytecode in function.code.iter() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) => {
+                if let Some(collections) = bounds.get(src).cloned() {
+                    bounds.entry(*dst).or_default().extend(collections);
+                }
+            }
+            Bytecode::Call(_, dsts, oper, srcs, _) => {
+                let mut collections: BTreeSet<String> = srcs
+                    .iter()
+                    .filter_map(|src| bounds.get(src))
+                    .flatten()
+                    .cloned()
+                    .collect();
+                if let Operation::Function(mid, fid, _) = oper {
+                    let callee = sui::get_framework_fname(stbgr, *mid, *fid);
+                    let is_size = callee
+                        .as_deref()
+                        .map_or(false, |callee| sui::COLLECTION_SIZE_FUNCTIONS.contains(&callee));
+                    if let (true, Some(src)) = (is_size, srcs.first()) {
+                        if let Some(RefRoot {
+                            field: Some((mid, sid, offset)),
+                            ..
+                        }) = roots.get(src)
+                        {
+                            let struct_name = stbgr.get_struct_full_name(*mid, *sid);
+                            if shared.contains(&struct_name) {
+                                collections.insert(format!(
+                                    "{}.{} via {}",
+                                    struct_name.rsplit("::").next().unwrap(),
+                                    stbgr.get_field_name(*sid, *offset),
+                                    callee.unwrap()
+                                ));
+                            }
+                        }
+                    }
+                }
+                if !collections.is_empty() {
+                    for dst in dsts.iter() {
+                        bounds.entry(*dst).or_default().extend(collections.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    bounds
+}
+
+/// Collections bounding the loop: those behind the condition of a branch that leaves it.
+fn get_loop_bounds(
+    function: &FunctionInfo,
+    loop_body: &BTreeSet<u16>,
+    bounds: &BTreeMap<TempIndex, BTreeSet<String>>,
+) -> BTreeSet<String> {
+    let cfg = function.cfg.as_ref().unwrap();
+    let body_labels: BTreeSet<_> = loop_body
+        .iter()
+        .filter_map(|block| match cfg.content(*block) {
+            BlockContent::Basic { lower, .. } => match &function.code[*lower as usize] {
+                Bytecode::Label(_, label) => Some(*label),
+                _ => None,
+            },
+            BlockContent::Dummy => None,
+        })
+        .collect();
+    let mut collections = BTreeSet::new();
+    for block in loop_body.iter() {
+        for offset in cfg.instr_indexes(*block).into_iter().flatten() {
+            if let Bytecode::Branch(_, then_label, else_label, cond) =
+                &function.code[offset as usize]
+            {
+                let exits =
+                    !body_labels.contains(then_label) || !body_labels.contains(else_label);
+                if let (true, Some(bound)) = (exits, bounds.get(cond)) {
+                    collections.extend(bound.iter().cloned());
+                }
+            }
+        }
+    }
+    collections
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        Ability, AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_unbounded_iteration() {
+        let mut builder = ModuleBuilder::new("queue");
+        let object = builder.module_handle(2, "object");
+        let uid = SignatureToken::Struct(builder.struct_handle(
+            object,
+            "UID",
+            AbilitySet::EMPTY | Ability::Store,
+        ));
+        let items = SignatureToken::Vector(Box::new(SignatureToken::U64));
+        let (queue, queue_def) = builder.define_struct(
+            "Queue",
+            AbilitySet::EMPTY | Ability::Key,
+            vec![("id", uid), ("items", items)],
+        );
+        let queue_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(queue)));
+        let (ctx_mut, _) = builder.tx_context();
+        let create = builder.declare("create", vec![ctx_mut], vec![]);
+        let process = builder.declare("process", vec![queue_ref.clone()], vec![]);
+        let process_limit = builder.declare(
+            "process_limit",
+            vec![queue_ref, SignatureToken::U64],
+            vec![],
+        );
+        let sui = builder.sui_handles();
+        let share_queue =
+            builder.instantiate(sui.share_object, vec![SignatureToken::Struct(queue)]);
+        let items_field = builder.field_handle(queue_def, 1);
+        let u64_sig = builder.signature(vec![SignatureToken::U64]);
+
+        // create(ctx) { transfer::share_object(Queue { id: object::new(ctx), items: vector[] }) }
+        builder.define(
+            create,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(sui.object_new),
+                MoveBytecode::VecPack(u64_sig, 0),
+                MoveBytecode::Pack(queue_def),
+                MoveBytecode::CallGeneric(share_queue),
+                MoveBytecode::Ret,
+            ],
+        );
+        // process(q) { let i = 0; while (i < vector::length(&q.items)) i = i + 1 }
+        builder.define(
+            process,
+            Visibility::Public,
+            vec![SignatureToken::U64],
+            vec![
+                MoveBytecode::LdU64(0),
+                MoveBytecode::StLoc(1),
+                MoveBytecode::CopyLoc(1),
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::ImmBorrowField(items_field),
+                MoveBytecode::VecLen(u64_sig),
+                MoveBytecode::Lt,
+                MoveBytecode::BrFalse(13),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Add,
+                MoveBytecode::StLoc(1),
+                MoveBytecode::Branch(2),
+                MoveBytecode::Ret,
+            ],
+        );
+        // process_limit(q, limit) { let i = 0; while (i < limit) i = i + 1 }
+        builder.define(
+            process_limit,
+            Visibility::Public,
+            vec![SignatureToken::U64],
+            vec![
+                MoveBytecode::LdU64(0),
+                MoveBytecode::StLoc(2),
+                MoveBytecode::CopyLoc(2),
+                MoveBytecode::CopyLoc(1),
+                MoveBytecode::Lt,
+                MoveBytecode::BrFalse(11),
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Add,
+                MoveBytecode::StLoc(2),
+                MoveBytecode::Branch(2),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = UnboundedIteration::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "process"));
+        assert!(findings[0].contains("Queue.items via 0x1::vector::length bounds loop"));
+        assert!(findings[0].ends_with("::queue::process)"));
+        // 循环次数由调用者传入的参数决定
+        assert!(!reports(&findings, "process_limit"));
+    }
+}
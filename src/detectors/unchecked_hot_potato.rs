@@ -0,0 +1,226 @@
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+use move_model::{
+    ast::TempIndex,
+    ty::{PrimitiveType, Type},
+};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        abort,
+        generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+        packages::Packages,
+        sui,
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UncheckedHotPotato<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UncheckedHotPotato<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Major, DetectKind::UncheckedHotPotato),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let potatoes = sui::get_hot_potatoes(self.packages);
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for function in stbgr.functions.iter() {
+                for (offset, name) in sui::get_consumed_potatoes(function, stbgr, &potatoes) {
+                    let (sid, fields) = match &function.code[offset] {
+                        Bytecode::Call(_, dsts, Operation::Unpack(_, sid, _), _, _) => (sid, dsts),
+                        _ => continue,
+                    };
+                    let field_names = (0..fields.len())
+                        .map(|i| stbgr.get_field_name(*sid, i))
+                        .filter(|name| name != "dummy_field")
+                        .collect_vec();
+                    if field_names.is_empty() {
+                        continue;
+                    }
+                    let seeds = fields.iter().copied().collect();
+                    let mut visited = BTreeSet::new();
+                    if !self.flows_to_comparison(stbgr, function, seeds, offset, &mut visited) {
+                        res.push(format!(
+                            "{}(unpacks {} at {} without comparing {})",
+                            function.name,
+                            name.rsplit("::").next().unwrap(),
+                            offset_detail(stbgr, function, offset),
+                            field_names.join(", ")
+                        ));
+                    }
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+impl<'a> UncheckedHotPotato<'a> {
+    /// Whether a value derived from `seeds` reaches a comparison deciding an abort, in this
+    /// function after `start` or in a package callee it is passed to, e.g.
+    /// `assert!(coin::value(&payment) >= amount + fee)`. A bool returned by a callee taking
+    /// such a value counts as a comparison of the caller.
+    fn flows_to_comparison(
+        &self,
+        stbgr: &StacklessBytecodeGenerator,
+        function: &FunctionInfo,
+        seeds: BTreeSet<TempIndex>,
+        start: usize,
+        visited: &mut BTreeSet<(String, TempIndex)>,
+    ) -> bool {
+        let mut tainted = seeds;
+        let mut comparisons = BTreeSet::new();
+        for bytecode in function.code[start..].iter() {
+            match bytecode {
+                Bytecode::Assign(_, dst, src, _) if tainted.contains(src) => {
+                    tainted.insert(*dst);
+                }
+                Bytecode::Call(_, dsts, oper, srcs, _)
+                    if srcs.iter().any(|src| tainted.contains(src)) =>
+                {
+                    match oper {
+                        Operation::Lt
+                        | Operation::Le
+                        | Operation::Gt
+                        | Operation::Ge
+                        | Operation::Eq
+                        | Operation::Neq => {
+                            comparisons.extend(dsts.iter().copied());
+                        }
+                        Operation::Function(mid, fid, _) => {
+                            let callee = stbgr.get_full_fname(*mid, *fid);
+                            if let Some((callee_stbgr, callee_idx)) =
+                                self.packages.get_function_by_full_name(&callee)
+                            {
+                                for (arg, src) in srcs.iter().enumerate() {
+                                    if !tainted.contains(src)
+                                        || !visited.insert((callee.clone(), arg))
+                                    {
+                                        continue;
+                                    }
+                                    if self.flows_to_comparison(
+                                        callee_stbgr,
+                                        &callee_stbgr.functions[callee_idx],
+                                        BTreeSet::from([arg]),
+                                        0,
+                                        visited,
+                                    ) {
+                                        return true;
+                                    }
+                                }
+                            }
+                            comparisons.extend(dsts.iter().copied().filter(|dst| {
+                                function.local_types[*dst] == Type::Primitive(PrimitiveType::Bool)
+                            }));
+                        }
+                        _ => {}
+                    }
+                    tainted.extend(dsts.iter().copied());
+                }
+                _ => {}
+            }
+        }
+        abort::controls_abort(function, &comparisons)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_unchecked_hot_potato() {
+        let mut builder = ModuleBuilder::new("flash");
+        let (receipt, receipt_def) = builder.define_struct(
+            "Receipt",
+            AbilitySet::EMPTY,
+            vec![("amount", SignatureToken::U64)],
+        );
+        let receipt = SignatureToken::Struct(receipt);
+        let checked = builder.declare(
+            "repay_checked",
+            vec![receipt.clone(), SignatureToken::U64],
+            vec![],
+        );
+        let discarded = builder.declare(
+            "repay_discarded",
+            vec![receipt.clone(), SignatureToken::U64],
+            vec![],
+        );
+        let ignored = builder.declare("repay_ignored", vec![receipt], vec![]);
+        // repay_checked(receipt, paid) {
+        //     let Receipt { amount } = receipt;
+        //     assert!(paid >= amount)
+        // }
+        builder.define(
+            checked,
+            Visibility::Public,
+            vec![SignatureToken::U64],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Unpack(receipt_def),
+                MoveBytecode::StLoc(2),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::Ge,
+                MoveBytecode::BrTrue(9),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Abort,
+                MoveBytecode::Ret,
+            ],
+        );
+        // repay_discarded(receipt, paid) { let Receipt { amount } = receipt; amount >= paid; }
+        builder.define(
+            discarded,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Unpack(receipt_def),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Ge,
+                MoveBytecode::Pop,
+                MoveBytecode::Ret,
+            ],
+        );
+        // repay_ignored(receipt) { let Receipt { amount: _ } = receipt; }
+        builder.define(
+            ignored,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Unpack(receipt_def),
+                MoveBytecode::Pop,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = UncheckedHotPotato::new(&packages);
+        let findings = findings(detector.run());
+        assert!(!reports(&findings, "repay_checked"));
+        // 比较结果被丢弃，不决定 abort
+        assert!(reports(&findings, "repay_discarded"));
+        assert!(reports(&findings, "repay_ignored"));
+        assert!(findings[0].ends_with("without comparing amount)"));
+    }
+}
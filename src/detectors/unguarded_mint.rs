@@ -0,0 +1,164 @@
+use move_binary_format::file_format::Visibility;
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{
+        coin_flow::{self, FlowKind},
+        packages::Packages,
+        sui, utils,
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UnguardedMint<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnguardedMint<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Critical, DetectKind::UnguardedMint),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let shared = sui::get_shared_structs(self.packages);
+        let capabilities = sui::get_capabilities(self.packages);
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                let is_public = utils::get_visibility(idx, stbgr) == Visibility::Public
+                    || utils::is_entry(idx, stbgr);
+                // TreasuryCap 存在共享对象的字段里时，任何人都能铸币，所以共享对象不算保护
+                if !is_public
+                    || utils::is_native(idx, stbgr)
+                    || !sui::get_guards(idx, stbgr, &capabilities, &shared).is_empty()
+                    || sui::checks_sender(function, stbgr)
+                {
+                    continue;
+                }
+                for flow in coin_flow::get_coin_flows(function, stbgr) {
+                    if flow.kind == FlowKind::Mint {
+                        res.push(format!(
+                            "{}({} at {})",
+                            function.name,
+                            flow.callee,
+                            offset_detail(stbgr, function, flow.code_offset)
+                        ));
+                    }
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        Ability, AbilitySet, Bytecode as MoveBytecode, SignatureToken,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_unguarded_mint() {
+        let mut builder = ModuleBuilder::new("vault");
+        let key_store = AbilitySet::EMPTY | Ability::Key | Ability::Store;
+        let object = builder.module_handle(2, "object");
+        let uid = SignatureToken::Struct(builder.struct_handle(
+            object,
+            "UID",
+            AbilitySet::EMPTY | Ability::Store,
+        ));
+        let coin = builder.module_handle(2, "coin");
+        let treasury_cap =
+            SignatureToken::Struct(builder.struct_handle(coin, "TreasuryCap", key_store));
+        let coin_ty = SignatureToken::Struct(builder.struct_handle(coin, "Coin", key_store));
+        let cap_mut = SignatureToken::MutableReference(Box::new(treasury_cap.clone()));
+        let (vault, vault_def) = builder.define_struct(
+            "Vault",
+            AbilitySet::EMPTY | Ability::Key,
+            vec![("id", uid), ("cap", treasury_cap.clone())],
+        );
+        let vault_mut = SignatureToken::MutableReference(Box::new(SignatureToken::Struct(vault)));
+        let (ctx_mut, _) = builder.tx_context();
+        let create = builder.declare("create", vec![treasury_cap, ctx_mut.clone()], vec![]);
+        let mint_from_vault = builder.declare(
+            "mint_from_vault",
+            vec![vault_mut, SignatureToken::U64, ctx_mut.clone()],
+            vec![coin_ty.clone()],
+        );
+        let mint_with_cap = builder.declare(
+            "mint_with_cap",
+            vec![cap_mut.clone(), SignatureToken::U64, ctx_mut.clone()],
+            vec![coin_ty.clone()],
+        );
+        let sui = builder.sui_handles();
+        let mint = builder.function_handle(
+            coin,
+            "mint",
+            vec![cap_mut, SignatureToken::U64, ctx_mut],
+            vec![coin_ty],
+        );
+        let cap_field = builder.field_handle(vault_def, 1);
+        let share_vault =
+            builder.instantiate(sui.share_object, vec![SignatureToken::Struct(vault)]);
+
+        // create(cap, ctx) { transfer::share_object(Vault { id: object::new(ctx), cap }) }
+        builder.define(
+            create,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(sui.object_new),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Pack(vault_def),
+                MoveBytecode::CallGeneric(share_vault),
+                MoveBytecode::Ret,
+            ],
+        );
+        // mint_from_vault(vault, amount, ctx) { coin::mint(&mut vault.cap, amount, ctx) }
+        builder.define(
+            mint_from_vault,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MutBorrowField(cap_field),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::Call(mint),
+                MoveBytecode::Ret,
+            ],
+        );
+        // mint_with_cap(cap, amount, ctx) { coin::mint(cap, amount, ctx) }
+        builder.define(
+            mint_with_cap,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::Call(mint),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = UnguardedMint::new(&packages);
+        let findings = findings(detector.run());
+        // 共享的 Vault 不算保护，任何人都能用里面的 TreasuryCap 铸币
+        assert!(reports(&findings, "mint_from_vault"));
+        assert!(findings[0].contains("0x2::coin::mint at "));
+        assert!(!reports(&findings, "mint_with_cap"));
+        assert_eq!(findings.len(), 1);
+    }
+}
@@ -0,0 +1,112 @@
+use move_stackless_bytecode::stackless_bytecode::{Bytecode::*, Constant, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{packages::Packages, utils},
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UnnecessaryBoolJudgment<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnnecessaryBoolJudgment<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Minor, DetectKind::UnnecessaryBoolJudgment),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for function in stbgr.functions.iter() {
+                let data_depent = &stbgr.data_dependency[function.idx];
+                for (offset, code) in function.code.iter().enumerate() {
+                    // x == true, x != false, !(x == false) 中都有一个操作数来自 LdTrue/LdFalse
+                    if let Call(_, _, Operation::Eq | Operation::Neq, srcs, _) = code {
+                        let compares_bool_const = srcs.iter().any(|src| {
+                            matches!(
+                                utils::get_origin_bytecode(function, data_depent, *src, offset),
+                                Some(Load(_, _, Constant::Bool(_)))
+                            )
+                        });
+                        if compares_bool_const {
+                            res.push(format!(
+                                "{}({})",
+                                function.name,
+                                offset_detail(stbgr, function, offset)
+                            ));
+                        }
+                    }
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_unnecessary_bool_judgment() {
+        let mut builder = ModuleBuilder::new("m");
+        let is_true = builder.declare(
+            "is_true",
+            vec![SignatureToken::Bool],
+            vec![SignatureToken::Bool],
+        );
+        let same = builder.declare(
+            "same",
+            vec![SignatureToken::Bool, SignatureToken::Bool],
+            vec![SignatureToken::Bool],
+        );
+        // is_true(x) { x == true }
+        builder.define(
+            is_true,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::LdTrue,
+                MoveBytecode::Eq,
+                MoveBytecode::Ret,
+            ],
+        );
+        // same(x, c) { let y = if (c) true else false; y == x }
+        builder.define(
+            same,
+            Visibility::Public,
+            vec![SignatureToken::Bool],
+            vec![
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::BrFalse(5),
+                MoveBytecode::LdTrue,
+                MoveBytecode::StLoc(2),
+                MoveBytecode::Branch(7),
+                MoveBytecode::LdFalse,
+                MoveBytecode::StLoc(2),
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Eq,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = UnnecessaryBoolJudgment::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "is_true"));
+        // y 有两个到达定义，不是某个确定的常量
+        assert!(!reports(&findings, "same"));
+    }
+}
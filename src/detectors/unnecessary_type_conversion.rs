@@ -0,0 +1,144 @@
+use move_stackless_bytecode::stackless_bytecode::{Bytecode::*, Operation};
+
+use crate::{
+    detectors::{offset_detail, AbstractDetector},
+    move_ir::{packages::Packages, utils},
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UnnecessaryTypeConversion<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnnecessaryTypeConversion<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Minor, DetectKind::UnnecessaryTypeConversion),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for function in stbgr.functions.iter() {
+                let data_depent = &stbgr.data_dependency[function.idx];
+                for (offset, code) in function.code.iter().enumerate() {
+                    let (dst, src) = match code {
+                        Call(_, dsts, oper, srcs, _) if is_cast(oper) => (dsts[0], srcs[0]),
+                        _ => continue,
+                    };
+                    let src_ty = &function.local_types[src];
+                    let dst_ty = &function.local_types[dst];
+                    // 1. x: u64, x as u64
+                    let same_type = src_ty == dst_ty;
+                    // 2. x: u64, (x as u128) as u64, 扩展后直接收窄，中间没有运算
+                    let origin = utils::get_origin_bytecode(function, data_depent, src, offset);
+                    let widen_then_narrow = match origin {
+                        Some(Call(_, _, inner_oper, inner_srcs, _)) if is_cast(inner_oper) => {
+                            match (
+                                utils::get_uint_bits(&function.local_types[inner_srcs[0]]),
+                                utils::get_uint_bits(src_ty),
+                                utils::get_uint_bits(dst_ty),
+                            ) {
+                                (Some(origin), Some(widened), Some(target)) => {
+                                    origin < widened && target < widened && target >= origin
+                                }
+                                _ => false,
+                            }
+                        }
+                        _ => false,
+                    };
+                    if same_type || widen_then_narrow {
+                        res.push(format!(
+                            "{}({})",
+                            function.name,
+                            offset_detail(stbgr, function, offset)
+                        ));
+                    }
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+fn is_cast(oper: &Operation) -> bool {
+    matches!(
+        oper,
+        Operation::CastU8
+            | Operation::CastU16
+            | Operation::CastU32
+            | Operation::CastU64
+            | Operation::CastU128
+            | Operation::CastU256
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_unnecessary_type_conversion() {
+        let mut builder = ModuleBuilder::new("m");
+        let round_trip = builder.declare(
+            "round_trip",
+            vec![SignatureToken::U64],
+            vec![SignatureToken::U64],
+        );
+        let either = builder.declare(
+            "either",
+            vec![
+                SignatureToken::U64,
+                SignatureToken::U128,
+                SignatureToken::Bool,
+            ],
+            vec![SignatureToken::U64],
+        );
+        // round_trip(x) { ((x as u128) as u64) }
+        builder.define(
+            round_trip,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::CastU128,
+                MoveBytecode::CastU64,
+                MoveBytecode::Ret,
+            ],
+        );
+        // either(x, z, c) { let y = if (c) (x as u128) else z; (y as u64) }
+        builder.define(
+            either,
+            Visibility::Public,
+            vec![SignatureToken::U128],
+            vec![
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::BrFalse(6),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::CastU128,
+                MoveBytecode::StLoc(3),
+                MoveBytecode::Branch(8),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::StLoc(3),
+                MoveBytecode::MoveLoc(3),
+                MoveBytecode::CastU64,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = UnnecessaryTypeConversion::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "round_trip"));
+        // 只有一条路径上的 y 来自扩展，收窄不是多余的
+        assert!(!reports(&findings, "either"));
+    }
+}
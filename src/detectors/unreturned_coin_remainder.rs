@@ -0,0 +1,234 @@
+use std::collections::BTreeSet;
+
+use move_binary_format::file_format::FunctionDefinitionIndex;
+use move_model::ty::Type;
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use crate::{
+    detectors::AbstractDetector,
+    move_ir::{
+        coin_flow::{self, CoinFlow, FlowKind},
+        generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+        packages::Packages,
+        sui, utils,
+    },
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+const VALUE_TYPES: [&str; 2] = ["0x2::coin::Coin", "0x2::balance::Balance"];
+// 把值存入对象的动态字段，(函数, 值参数的位置)
+const DYNAMIC_FIELD_ADD_FUNCTIONS: [(&str, usize); 2] = [
+    ("0x2::dynamic_field::add", 2),
+    ("0x2::dynamic_object_field::add", 2),
+];
+
+pub struct UnreturnedCoinRemainder<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnreturnedCoinRemainder<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Major, DetectKind::UnreturnedCoinRemainder),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut res = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if utils::is_native(idx, stbgr) {
+                    continue;
+                }
+                let params: Vec<usize> = (0..utils::get_param_count(idx, stbgr))
+                    .filter(|param| is_value_type(&function.local_types[*param], stbgr))
+                    .collect();
+                if params.is_empty() {
+                    continue;
+                }
+                let flows = coin_flow::get_coin_flows(function, stbgr);
+                for param in params {
+                    if let Some(detail) = check_remainder(function, stbgr, &flows, param) {
+                        let param_name = stbgr
+                            .get_local_name(FunctionDefinitionIndex::new(idx as u16), param)
+                            .display(&stbgr.symbol_pool)
+                            .to_string();
+                        res.push(format!("{}({}: {})", function.name, param_name, detail));
+                    }
+                }
+            }
+            self.content.result.insert(mname.to_string(), res);
+        }
+        &self.content
+    }
+}
+
+/// `Coin<T>` / `Balance<T>` held by value
+fn is_value_type(ty: &Type, stbgr: &StacklessBytecodeGenerator) -> bool {
+    match ty {
+        Type::Struct(mid, sid, _) => sui::get_framework_struct_name(stbgr, *mid, *sid)
+            .map_or(false, |name| VALUE_TYPES.contains(&name.as_str())),
+        _ => false,
+    }
+}
+
+/// A Coin parameter that is split and whose remainder is then deposited into another
+/// balance, packed into a struct, stored in a dynamic field or destroyed with
+/// `destroy_zero` (which aborts on any change) instead of being returned or transferred,
+/// e.g. the change of a payment kept by the pool.
+fn check_remainder(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+    flows: &[CoinFlow],
+    param: usize,
+) -> Option<String> {
+    let mut values = BTreeSet::from([param]);
+    let mut refs = BTreeSet::new();
+    let mut split = None;
+    let mut deposit = None;
+    for (code_offset, bytecode) in function.code.iter().enumerate() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) => {
+                if values.contains(src) {
+                    values.insert(*dst);
+                }
+                if refs.contains(src) {
+                    refs.insert(*dst);
+                }
+            }
+            Bytecode::Call(_, dsts, Operation::BorrowLoc, srcs, _) if values.contains(&srcs[0]) => {
+                refs.insert(dsts[0]);
+            }
+            // 打包后的结构体被返回或转账时，余额仍交还给了调用者
+            Bytecode::Call(_, dsts, Operation::Pack(mid, sid, _), srcs, _)
+                if srcs.iter().any(|src| values.contains(src)) =>
+            {
+                values.extend(dsts.iter().copied());
+                let struct_name = stbgr.get_struct_full_name(*mid, *sid);
+                deposit = Some(format!(
+                    "packed into {}",
+                    struct_name.rsplit("::").next().unwrap()
+                ));
+            }
+            Bytecode::Call(_, dsts, Operation::Function(mid, fid, _), srcs, _) => {
+                let callee = sui::get_framework_fname(stbgr, *mid, *fid).unwrap_or_default();
+                let flow = flows.iter().find(|flow| flow.code_offset == code_offset);
+                match flow.map(|flow| flow.kind) {
+                    Some(FlowKind::Withdraw) if refs.contains(&srcs[0]) => {
+                        split = split.or(flow.map(|flow| flow.amount.clone()));
+                    }
+                    Some(FlowKind::Deposit) if srcs.iter().any(|src| values.contains(src)) => {
+                        deposit = Some(format!("deposited via {}", callee));
+                    }
+                    Some(FlowKind::Destroy) if values.contains(&srcs[0]) => {
+                        deposit = Some(format!("destroyed via {}, aborting unless zero", callee));
+                    }
+                    Some(FlowKind::Transfer) if values.contains(&srcs[0]) => return None,
+                    Some(FlowKind::Convert) if values.contains(&srcs[0]) => {
+                        values.extend(dsts.iter().copied());
+                    }
+                    _ if callee == "0x2::coin::balance_mut" && refs.contains(&srcs[0]) => {
+                        refs.extend(dsts.iter().copied());
+                    }
+                    _ if DYNAMIC_FIELD_ADD_FUNCTIONS.iter().any(|(name, arg)| {
+                        callee == *name && srcs.get(*arg).map_or(false, |src| values.contains(src))
+                    }) =>
+                    {
+                        deposit = Some(format!("stored via {}", callee));
+                    }
+                    _ => {}
+                }
+            }
+            Bytecode::Ret(_, srcs) if srcs.iter().any(|src| values.contains(src)) => return None,
+            _ => {}
+        }
+    }
+    match (split, deposit) {
+        (Some(amount), Some(deposit)) => {
+            Some(format!("split {} then remainder {}", amount, deposit))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{findings, reports, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_unreturned_coin_remainder() {
+        let mut builder = ModuleBuilder::new("shop");
+        let coin = builder.module_handle(2, "coin");
+        let coin_ty =
+            SignatureToken::Struct(builder.struct_handle(coin, "Coin", AbilitySet::EMPTY));
+        let coin_mut = SignatureToken::MutableReference(Box::new(coin_ty.clone()));
+        let pay = builder.declare(
+            "pay",
+            vec![coin_ty.clone(), SignatureToken::U64],
+            vec![coin_ty.clone()],
+        );
+        let split_off = builder.declare(
+            "split_off",
+            vec![coin_ty.clone(), SignatureToken::U64],
+            vec![coin_ty.clone(), coin_ty.clone()],
+        );
+        let split = builder.function_handle(
+            coin,
+            "split",
+            vec![coin_mut, SignatureToken::U64],
+            vec![coin_ty.clone()],
+        );
+        let destroy_zero =
+            builder.function_handle(coin, "destroy_zero", vec![coin_ty.clone()], vec![]);
+        // pay(payment, amount) {
+        //     let paid = coin::split(&mut payment, amount);
+        //     coin::destroy_zero(payment);
+        //     paid
+        // }
+        builder.define(
+            pay,
+            Visibility::Public,
+            vec![coin_ty],
+            vec![
+                MoveBytecode::MutBorrowLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(split),
+                MoveBytecode::StLoc(2),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(destroy_zero),
+                MoveBytecode::MoveLoc(2),
+                MoveBytecode::Ret,
+            ],
+        );
+        // split_off(payment, amount) { (coin::split(&mut payment, amount), payment) }
+        builder.define(
+            split_off,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MutBorrowLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(split),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let mut detector = UnreturnedCoinRemainder::new(&packages);
+        let findings = findings(detector.run());
+        assert!(reports(&findings, "pay"));
+        let destroyed = "remainder destroyed via 0x2::coin::destroy_zero, aborting unless zero";
+        assert!(findings[0].contains(destroyed));
+        // 余额随返回值交还给调用者
+        assert!(!reports(&findings, "split_off"));
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeSet;
+
+use move_binary_format::{access::ModuleAccess, file_format::Bytecode as MoveBytecode};
+
+use crate::{
+    detectors::{unused_private_functions::get_live_functions, AbstractDetector},
+    move_ir::{packages::Packages, sui, utils},
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UnusedConstant<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnusedConstant<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Info, DetectKind::UnusedConstant),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        // 只被死代码引用的常量同样视为未使用
+        let live = get_live_functions(self.packages);
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut used = BTreeSet::new();
+            for (idx, func_def) in stbgr.module.function_defs().iter().enumerate() {
+                let fname = utils::get_function_name(idx, stbgr);
+                if !live.contains(&format!("{}::{}", mname, fname)) {
+                    continue;
+                }
+                if let Some(code) = &func_def.code {
+                    for bytecode in code.code.iter() {
+                        match bytecode {
+                            MoveBytecode::LdConst(const_idx) => {
+                                used.insert(const_idx.0 as usize);
+                            }
+                            // clever error 的错误信息常量不通过 LdConst 加载，而是编码在 abort code 中
+                            MoveBytecode::LdU64(abort_code) => {
+                                used.extend(sui::get_clever_error_constant(*abort_code));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            let mut unused = vec![];
+            for idx in 0..stbgr.module.constant_pool().len() {
+                if !used.contains(&idx) {
+                    unused.push(format!("const_{}({})", idx, stbgr.get_constant_value(idx)));
+                }
+            }
+            self.content.result.insert(mname.to_string(), unused);
+        }
+        &self.content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_ir::test_utils::{
+        findings, load_source, test_only_module, with_paths, TEST_ONLY_SOURCE,
+    };
+
+    #[test]
+    fn test_unused_constant() {
+        let cms = with_paths(vec![test_only_module()]);
+        let mut packages = Packages::new(&cms);
+        let mname = packages.get_module_names()[0].clone();
+        load_source(&mut packages, &mname, TEST_ONLY_SOURCE);
+        let mut detector = UnusedConstant::new(&packages);
+        let findings = findings(detector.run());
+        // helper 与 #[test_only] setup 可达，dead 与 test_looking 是死代码
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0].starts_with("const_1("));
+        assert!(findings[1].starts_with("const_3("));
+    }
+}
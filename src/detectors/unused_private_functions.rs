@@ -0,0 +1,90 @@
+use std::collections::BTreeSet;
+
+use move_binary_format::file_format::Visibility;
+use petgraph::visit::Dfs;
+
+use crate::{
+    detectors::AbstractDetector,
+    move_ir::{generate_bytecode::StacklessBytecodeGenerator, packages::Packages, utils},
+    scanner::result::{DetectContent, DetectKind, Severity},
+};
+
+pub struct UnusedPrivateFunctions<'a> {
+    packages: &'a Packages<'a>,
+    content: DetectContent,
+}
+
+impl<'a> AbstractDetector<'a> for UnusedPrivateFunctions<'a> {
+    fn new(packages: &'a Packages<'a>) -> Self {
+        Self {
+            packages,
+            content: DetectContent::new(Severity::Minor, DetectKind::UnusedPrivateFunctions),
+        }
+    }
+
+    fn run(&mut self) -> &DetectContent {
+        let live = get_live_functions(self.packages);
+        for (mname, stbgr) in self.packages.get_all_stbgr().iter() {
+            let mut unused = vec![];
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if utils::is_native(idx, stbgr) {
+                    continue;
+                }
+                if !live.contains(&format!("{}::{}", mname, function.name)) {
+                    unused.push(function.name.clone());
+                }
+            }
+            self.content.result.insert(mname.to_string(), unused);
+        }
+        &self.content
+    }
+}
+
+/// Functions the outside world can invoke: public and entry functions, the Sui
+/// module initializer `init`, and test-only functions.
+fn is_root(idx: usize, stbgr: &StacklessBytecodeGenerator) -> bool {
+    let name = &stbgr.functions[idx].name;
+    utils::get_visibility(idx, stbgr) == Visibility::Public
+        || utils::is_entry(idx, stbgr)
+        || name == "init"
+        || utils::is_test_only(idx, stbgr)
+}
+
+/// Every function reachable from a root on the package-wide call graph.
+/// Private and friend functions outside this set are dead code, including those
+/// only called by other dead functions.
+pub fn get_live_functions(packages: &Packages) -> BTreeSet<String> {
+    let mut live = BTreeSet::new();
+    let mut dfs = Dfs::empty(&packages.call_graph);
+    for (mname, stbgr) in packages.get_all_stbgr().iter() {
+        for (idx, function) in stbgr.functions.iter().enumerate() {
+            if !is_root(idx, stbgr) {
+                continue;
+            }
+            dfs.move_to(packages.func_to_node[&format!("{}::{}", mname, function.name)]);
+            while let Some(node) = dfs.next(&packages.call_graph) {
+                live.insert(packages.call_graph[node].clone());
+            }
+        }
+    }
+    live
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::move_ir::test_utils::{
+        findings, load_source, test_only_module, with_paths, TEST_ONLY_SOURCE,
+    };
+
+    #[test]
+    fn test_unused_private_functions() {
+        let cms = with_paths(vec![test_only_module()]);
+        let mut packages = Packages::new(&cms);
+        let mname = packages.get_module_names()[0].clone();
+        load_source(&mut packages, &mname, TEST_ONLY_SOURCE);
+        let mut detector = UnusedPrivateFunctions::new(&packages);
+        // setup 带 #[test_only]，test_looking 只是名字像测试函数
+        assert_eq!(findings(detector.run()), vec!["dead", "test_looking"]);
+    }
+}
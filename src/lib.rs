@@ -3,6 +3,7 @@
 
 // mod列表
 pub mod cli;
+pub mod detectors;
 pub mod move_ir;
 pub mod utils;
 pub mod scanner;
\ No newline at end of file
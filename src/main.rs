@@ -1,25 +1,50 @@
 #![allow(non_snake_case)]
 use MoveScanner::{
     cli::parser::{Cli, SubCommands},
-    scanner::{detectors::GraphGenerator, printer::Printer, option::Options},
+    scanner::{
+        detectors::GraphGenerator,
+        error::{ExitCode, ScanResult},
+        option::Options,
+        printer::Printer,
+    },
 };
+use ansi_term::Colour;
 use clap::Parser;
 // use env_logger;
 fn main() {
     // env_logger::init();
     let cli = Cli::parse();
-    let option = Options::build_options(cli.args);
+    let exit_code = match run(cli) {
+        Ok(exit_code) => exit_code,
+        Err(e) => {
+            eprintln!("{}", Colour::Red.paint(e.to_string()));
+            e.exit_code()
+        }
+    };
+    std::process::exit(exit_code as i32);
+}
+
+fn run(cli: Cli) -> ScanResult<ExitCode> {
+    let option = Options::build_options(cli.args)?;
     match &cli.command {
         Some(SubCommands::Printer) => {
             // todo: 代码优化
             let mut printer = Printer::new(option);
-            printer.run();
+            printer.run()?;
+            Ok(ExitCode::Success)
         }
         // Default: Graph Generation
         _ => {
+            let fail_on = option.fail_on.clone();
             let mut generator = GraphGenerator::new(option);
-            generator.run();
+            generator.run()?;
             // detector.output_result(); // Removed output.json generation
+            match fail_on {
+                Some(threshold) if generator.result.has_findings_at_least(&threshold) => {
+                    Ok(ExitCode::Findings)
+                }
+                _ => Ok(ExitCode::Success),
+            }
         }
     }
 }
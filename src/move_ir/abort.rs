@@ -0,0 +1,742 @@
+// 函数 abort 的路径条件：显式 abort、算术溢出、cast 失败、vector 越界、被调函数 abort
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use ethnum::U256;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode as MoveBytecode, CodeOffset, FunctionDefinitionIndex},
+};
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::{
+    stackless_bytecode::{Bytecode, Constant, Operation},
+    stackless_control_flow_graph::BlockContent,
+};
+
+use super::{
+    bytecode_display::oper_display,
+    control_flow_graph::{self, BlockId},
+    data_dependency::{get_uint_max, Node, Val},
+    dataflow::DefSite,
+    def_use::DefUse,
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    interval::{self, Interval},
+    packages::Packages,
+    sui, summary, utils,
+};
+
+// 展开数据依赖树的最大深度，更深处记为 ..
+const MAX_EXPR_DEPTH: usize = 8;
+// 每个 abort 点最多列出的路径数
+const MAX_PATHS: usize = 8;
+
+// 索引越界时 abort 的 vector 函数及索引参数的位置
+const VECTOR_INDEX_FUNCTIONS: [(&str, usize); 5] = [
+    ("0x1::vector::borrow", 1),
+    ("0x1::vector::borrow_mut", 1),
+    ("0x1::vector::remove", 1),
+    ("0x1::vector::swap_remove", 1),
+    ("0x1::vector::swap", 1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbortKind {
+    Abort,
+    Overflow,
+    Underflow,
+    DivisionByZero,
+    ShiftOverflow,
+    CastOverflow,
+    OutOfBounds,
+    Callee(String), // 可能 abort 的被调函数的全名
+}
+
+impl fmt::Display for AbortKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbortKind::Abort => write!(f, "abort"),
+            AbortKind::Overflow => write!(f, "overflow"),
+            AbortKind::Underflow => write!(f, "underflow"),
+            AbortKind::DivisionByZero => write!(f, "division by zero"),
+            AbortKind::ShiftOverflow => write!(f, "shift overflow"),
+            AbortKind::CastOverflow => write!(f, "cast overflow"),
+            AbortKind::OutOfBounds => write!(f, "vector index out of bounds"),
+            AbortKind::Callee(callee) => write!(f, "{} aborts", callee),
+        }
+    }
+}
+
+/// What the function aborts with: a constant or computed abort code, or the VM status of
+/// an arithmetic or vector error.
+#[derive(Debug, Clone)]
+pub enum ErrorCode {
+    Constant(Constant),
+    Expr(String),
+    Status(&'static str),
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCode::Constant(constant) => write!(f, "{}", constant),
+            ErrorCode::Expr(expr) => write!(f, "{}", expr),
+            ErrorCode::Status(status) => write!(f, "{}", status),
+        }
+    }
+}
+
+/// One way for a function to abort: the instruction, and the conjunction of the branch
+/// conditions leading to it with the condition making the instruction itself fail,
+/// expressed over parameters and object fields.
+#[derive(Debug, Clone)]
+pub struct AbortCondition {
+    pub function: String,
+    pub code_offset: CodeOffset,
+    pub kind: AbortKind,
+    pub conditions: Vec<String>,
+    // 被调函数 abort 时为 None，错误码见被调函数自身的 AbortCondition
+    pub error_code: Option<ErrorCode>,
+    // 错误码所取的常量在常量池中的下标：LdConst 加载的常量，或 clever error 指向的常量
+    pub error_constant: Option<usize>,
+}
+
+/// Source-like expression of a data-dependency tree, naming parameters and fields.
+struct ExprPrinter<'a, 'b> {
+    idx: usize,
+    stbgr: &'b StacklessBytecodeGenerator<'a>,
+}
+
+impl<'a, 'b> ExprPrinter<'a, 'b> {
+    fn print(&self, node: &Node) -> String {
+        self.print_at(node, 0)
+    }
+
+    fn print_at(&self, node: &Node, depth: usize) -> String {
+        if depth >= MAX_EXPR_DEPTH {
+            return "..".to_string();
+        }
+        let sub = |i: usize| -> String {
+            node.subnodes.get(i).map_or("?".to_string(), |subnode| {
+                self.print_at(&subnode.borrow(), depth + 1)
+            })
+        };
+        match &node.value {
+            Val::ParamType(param, _) => self.local_name(*param),
+            Val::Const(constant) => constant.to_string(),
+            Val::AssIgn(_) => sub(0),
+            Val::LoopCarried(temp) => format!("$t{}", temp),
+            Val::Join(_) => {
+                let exprs: BTreeSet<String> = (0..node.subnodes.len()).map(sub).collect();
+                if exprs.len() == 1 {
+                    exprs.into_iter().next().unwrap()
+                } else {
+                    format!(
+                        "join({})",
+                        exprs.into_iter().collect::<Vec<_>>().join(" | ")
+                    )
+                }
+            }
+            Val::ByteCode(Bytecode::Call(_, _, oper, _, _)) => match oper {
+                Operation::Not => format!("!{}", sub(0)),
+                Operation::CastU8 => format!("({} as u8)", sub(0)),
+                Operation::CastU16 => format!("({} as u16)", sub(0)),
+                Operation::CastU32 => format!("({} as u32)", sub(0)),
+                Operation::CastU64 => format!("({} as u64)", sub(0)),
+                Operation::CastU128 => format!("({} as u128)", sub(0)),
+                Operation::CastU256 => format!("({} as u256)", sub(0)),
+                Operation::BorrowField(_, sid, _, offset) => {
+                    format!("{}.{}", sub(0), self.stbgr.get_field_name(*sid, *offset))
+                }
+                Operation::BorrowLoc | Operation::FreezeRef | Operation::ReadRef => sub(0),
+                Operation::Function(mid, fid, _) => {
+                    match sui::get_framework_fname(self.stbgr, *mid, *fid) {
+                        // 框架函数的子节点即实参
+                        Some(callee) => format!(
+                            "{}({})",
+                            callee,
+                            (0..node.subnodes.len())
+                                .map(sub)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                        // 包内函数的子节点是其返回值的依赖，不展开
+                        None => format!("{}(..)", fid.symbol().display(&self.stbgr.symbol_pool)),
+                    }
+                }
+                _ => match get_operator(oper) {
+                    Some(op) => format!("({} {} {})", sub(0), op, sub(1)),
+                    None => format!(
+                        "{}({})",
+                        oper_display(oper, self.stbgr),
+                        (0..node.subnodes.len())
+                            .map(sub)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                },
+            },
+            Val::ByteCode(_) => "?".to_string(),
+        }
+    }
+
+    /// `!cond`, flipping the comparison when `cond` is one.
+    fn print_negated(&self, node: &Node) -> String {
+        let node = skip_assign(node);
+        if let Val::ByteCode(Bytecode::Call(_, _, oper, _, _)) = &node.value {
+            let flipped = match oper {
+                Operation::Lt => Some(">="),
+                Operation::Le => Some(">"),
+                Operation::Gt => Some("<="),
+                Operation::Ge => Some("<"),
+                Operation::Eq => Some("!="),
+                Operation::Neq => Some("=="),
+                _ => None,
+            };
+            if let (Some(op), 2) = (flipped, node.subnodes.len()) {
+                return format!(
+                    "({} {} {})",
+                    self.print_at(&node.subnodes[0].borrow(), 1),
+                    op,
+                    self.print_at(&node.subnodes[1].borrow(), 1)
+                );
+            }
+            if let (Operation::Not, 1) = (oper, node.subnodes.len()) {
+                return self.print_at(&node.subnodes[0].borrow(), 1);
+            }
+        }
+        format!("!{}", self.print(&node))
+    }
+
+    fn local_name(&self, temp: TempIndex) -> String {
+        self.stbgr
+            .get_local_name(FunctionDefinitionIndex::new(self.idx as u16), temp)
+            .display(&self.stbgr.symbol_pool)
+            .to_string()
+    }
+}
+
+fn get_operator(oper: &Operation) -> Option<&'static str> {
+    let op = match oper {
+        Operation::Add => "+",
+        Operation::Sub => "-",
+        Operation::Mul => "*",
+        Operation::Div => "/",
+        Operation::Mod => "%",
+        Operation::BitOr => "|",
+        Operation::BitAnd => "&",
+        Operation::Xor => "^",
+        Operation::Shl => "<<",
+        Operation::Shr => ">>",
+        Operation::Lt => "<",
+        Operation::Gt => ">",
+        Operation::Le => "<=",
+        Operation::Ge => ">=",
+        Operation::Eq => "==",
+        Operation::Neq => "!=",
+        Operation::Or => "||",
+        Operation::And => "&&",
+        _ => return None,
+    };
+    Some(op)
+}
+
+/// The node an assignment (or a join of a single definition) copies.
+fn skip_assign(node: &Node) -> Node {
+    match &node.value {
+        Val::AssIgn(_) | Val::Join(_) if node.subnodes.len() == 1 => {
+            skip_assign(&node.subnodes[0].borrow())
+        }
+        _ => node.clone(),
+    }
+}
+
+/// Branch conditions under which the block runs, one conjunction per chain of controlling
+/// branches from the entry, outermost first. A block run unconditionally has one empty path.
+fn get_path_conditions(
+    function: &FunctionInfo,
+    control_dependence: &BTreeMap<BlockId, BTreeSet<(BlockId, BlockId)>>,
+    printer: &ExprPrinter,
+    node_of: &dyn Fn(usize, TempIndex) -> Node,
+    block: BlockId,
+    visiting: &mut BTreeSet<BlockId>,
+) -> Vec<Vec<String>> {
+    let controllers = match control_dependence.get(&block) {
+        Some(controllers) if !controllers.is_empty() => controllers,
+        _ => return vec![vec![]],
+    };
+    visiting.insert(block);
+    let mut paths = vec![];
+    for (from, to) in controllers.iter() {
+        // 条件取到达分支指令（控制块的最后一条指令）的定义
+        let branch = match function.cfg.as_ref().map(|cfg| cfg.content(*from)) {
+            Some(BlockContent::Basic { upper, .. }) => *upper as usize,
+            _ => continue,
+        };
+        let literal =
+            control_flow_graph::get_branch_outcome(function, *from, *to).map(|(cond, outcome)| {
+                let node = node_of(branch, cond);
+                if outcome {
+                    printer.print(&node)
+                } else {
+                    printer.print_negated(&node)
+                }
+            });
+        // 循环中的分支控制依赖于自身，不再向上展开
+        let outer = if visiting.contains(from) {
+            vec![vec![]]
+        } else {
+            get_path_conditions(
+                function,
+                control_dependence,
+                printer,
+                node_of,
+                *from,
+                visiting,
+            )
+        };
+        for mut path in outer {
+            path.extend(literal.clone());
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        if paths.len() >= MAX_PATHS {
+            paths.truncate(MAX_PATHS);
+            break;
+        }
+    }
+    visiting.remove(&block);
+    paths
+}
+
+/// `MAX_U64` for a u64 result, the maximum itself otherwise.
+fn uint_max_name(max: U256) -> String {
+    match max
+        .checked_add(U256::ONE)
+        .map(|bound| bound.trailing_zeros())
+    {
+        Some(bits) if bits > 0 && bits % 8 == 0 => format!("MAX_U{}", bits),
+        _ => "MAX_U256".to_string(),
+    }
+}
+
+/// Constant pool index of the abort code read by the instruction at `offset`: the constant
+/// it was loaded from with `LdConst`, or the error constant a Sui clever error names. None
+/// when the code is computed, has several definitions, or is a plain literal, rather than
+/// guessing from its value.
+fn get_error_constant(
+    stbgr: &StacklessBytecodeGenerator,
+    function: &FunctionInfo,
+    def_use: &DefUse,
+    mut offset: CodeOffset,
+    mut temp: TempIndex,
+) -> Option<usize> {
+    // 沿 Assign 回溯到加载错误码的指令
+    for _ in 0..function.code.len() {
+        let defs = def_use.defs_reaching(offset, temp);
+        let def = match (defs.len(), defs.into_iter().next()) {
+            (1, Some(DefSite::Code(def))) => def,
+            _ => return None,
+        };
+        match &function.code[def as usize] {
+            Bytecode::Assign(_, _, src, _) => {
+                offset = def;
+                temp = *src;
+            }
+            Bytecode::Load(attr_id, _, _) => {
+                let code_offset = *function.code_offsets.get(attr_id)?;
+                let func_def = stbgr
+                    .module
+                    .function_def_at(FunctionDefinitionIndex::new(function.idx as u16));
+                return match func_def.code.as_ref()?.code.get(code_offset as usize)? {
+                    MoveBytecode::LdConst(const_idx) => Some(const_idx.0 as usize),
+                    MoveBytecode::LdU64(abort_code) => sui::get_clever_error_constant(*abort_code),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Every abort condition of the function `module::function`, in code order. Arithmetic
+/// and casts the interval analysis proves safe are left out, and so is unreachable code.
+pub fn get_abort_conditions(packages: &Packages, full_name: &str) -> Vec<AbortCondition> {
+    let (stbgr, idx) = match packages.get_function_by_full_name(full_name) {
+        Some(function) => function,
+        None => return vec![],
+    };
+    let function = &stbgr.functions[idx];
+    let cfg = match function.cfg.as_ref() {
+        Some(cfg) if !utils::is_native(idx, stbgr) => cfg,
+        _ => return vec![],
+    };
+    let printer = ExprPrinter { idx, stbgr };
+    let data_depent = &stbgr.data_dependency[idx];
+    // 取到达该指令的定义，而不是 temp 在整个函数中所有定义的 join
+    let node_of = |offset: usize, temp: TempIndex| data_depent.get_at(function, offset, temp);
+    let expr_of = |offset: usize, temp: TempIndex| printer.print(&node_of(offset, temp));
+    let intervals = interval::get_intervals(idx, stbgr);
+    let range_of = |offset: usize, temp: TempIndex| -> Option<Interval> {
+        let state = intervals.as_ref()?.before.get(&(offset as CodeOffset))?;
+        state.get(&temp).copied()
+    };
+    let def_use = DefUse::new(idx, stbgr);
+    let control_dependence = control_flow_graph::get_control_dependence(function);
+    let mut block_of = BTreeMap::new();
+    for block in cfg.blocks() {
+        for offset in cfg.instr_indexes(block).into_iter().flatten() {
+            block_of.insert(offset, block);
+        }
+    }
+
+    let mut abort_conditions = vec![];
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        // 区间分析不可达的指令
+        if let Some(intervals) = intervals.as_ref() {
+            if !intervals.before.contains_key(&(offset as CodeOffset)) {
+                continue;
+            }
+        }
+        let mut error_constant = None;
+        let (kind, condition, error_code) = match bytecode {
+            Bytecode::Abort(_, src) => {
+                let error_code = match skip_assign(&node_of(offset, *src)).value {
+                    Val::Const(constant) => ErrorCode::Constant(constant),
+                    _ => ErrorCode::Expr(expr_of(offset, *src)),
+                };
+                error_constant = def_use.as_ref().and_then(|def_use| {
+                    get_error_constant(stbgr, function, def_use, offset as CodeOffset, *src)
+                });
+                (AbortKind::Abort, None, Some(error_code))
+            }
+            Bytecode::Call(_, dsts, oper, srcs, _) => {
+                let arithmetic = ErrorCode::Status("ARITHMETIC_ERROR");
+                let (l, r) = (
+                    srcs.first().and_then(|src| range_of(offset, *src)),
+                    srcs.get(1).and_then(|src| range_of(offset, *src)),
+                );
+                let max = dsts
+                    .first()
+                    .and_then(|dst| get_uint_max(&function.local_types[*dst]));
+                match oper {
+                    Operation::Add | Operation::Mul => {
+                        let max = match max {
+                            Some(max) => max,
+                            None => continue,
+                        };
+                        let safe = match (oper, l, r) {
+                            (Operation::Add, Some(l), Some(r)) => {
+                                l.hi.checked_add(r.hi).map_or(false, |hi| hi <= max)
+                            }
+                            (Operation::Mul, Some(l), Some(r)) => {
+                                l.hi.checked_mul(r.hi).map_or(false, |hi| hi <= max)
+                            }
+                            _ => false,
+                        };
+                        if safe {
+                            continue;
+                        }
+                        let op = get_operator(oper).unwrap();
+                        let condition = format!(
+                            "{} {} {} > {}",
+                            expr_of(offset, srcs[0]),
+                            op,
+                            expr_of(offset, srcs[1]),
+                            uint_max_name(max)
+                        );
+                        (AbortKind::Overflow, Some(condition), Some(arithmetic))
+                    }
+                    Operation::Sub => {
+                        if let (Some(l), Some(r)) = (l, r) {
+                            if l.lo >= r.hi {
+                                continue;
+                            }
+                        }
+                        let condition = format!(
+                            "{} < {}",
+                            expr_of(offset, srcs[0]),
+                            expr_of(offset, srcs[1])
+                        );
+                        (AbortKind::Underflow, Some(condition), Some(arithmetic))
+                    }
+                    Operation::Div | Operation::Mod => {
+                        if r.map_or(false, |r| r.lo > U256::ZERO) {
+                            continue;
+                        }
+                        let condition = format!("{} == 0", expr_of(offset, srcs[1]));
+                        (AbortKind::DivisionByZero, Some(condition), Some(arithmetic))
+                    }
+                    Operation::Shl | Operation::Shr => {
+                        let bits = match dsts
+                            .first()
+                            .and_then(|dst| utils::get_uint_bits(&function.local_types[*dst]))
+                        {
+                            Some(bits) => bits,
+                            None => continue,
+                        };
+                        if r.map_or(false, |r| r.hi < U256::from(bits as u64)) {
+                            continue;
+                        }
+                        let condition = format!("{} >= {}", expr_of(offset, srcs[1]), bits);
+                        (AbortKind::ShiftOverflow, Some(condition), Some(arithmetic))
+                    }
+                    Operation::CastU8
+                    | Operation::CastU16
+                    | Operation::CastU32
+                    | Operation::CastU64
+                    | Operation::CastU128 => {
+                        let max = match max {
+                            Some(max) => max,
+                            None => continue,
+                        };
+                        if l.map_or(false, |l| l.hi <= max) {
+                            continue;
+                        }
+                        let condition =
+                            format!("{} > {}", expr_of(offset, srcs[0]), uint_max_name(max));
+                        (AbortKind::CastOverflow, Some(condition), Some(arithmetic))
+                    }
+                    Operation::Function(mid, fid, _) => {
+                        let callee = sui::get_framework_fname(stbgr, *mid, *fid);
+                        let vector_index = VECTOR_INDEX_FUNCTIONS
+                            .iter()
+                            .find(|(name, _)| callee.as_deref() == Some(*name));
+                        if let Some((name, arg)) = vector_index {
+                            let length = format!("vector::length({})", expr_of(offset, srcs[0]));
+                            let mut condition =
+                                format!("{} >= {}", expr_of(offset, srcs[*arg]), length);
+                            if *name == "0x1::vector::swap" {
+                                condition = format!(
+                                    "{} || {} >= {}",
+                                    condition,
+                                    expr_of(offset, srcs[2]),
+                                    length
+                                );
+                            }
+                            let error_code = ErrorCode::Status("VECTOR_OPERATION_ERROR");
+                            (AbortKind::OutOfBounds, Some(condition), Some(error_code))
+                        } else if callee.as_deref() == Some("0x1::vector::pop_back") {
+                            let condition =
+                                format!("vector::length({}) == 0", expr_of(offset, srcs[0]));
+                            let error_code = ErrorCode::Status("VECTOR_OPERATION_ERROR");
+                            (AbortKind::OutOfBounds, Some(condition), Some(error_code))
+                        } else {
+                            // 包外函数视为可能 abort
+                            let callee = stbgr.get_full_fname(*mid, *fid);
+                            if !summary::callee_may_abort(&packages.summaries, &callee) {
+                                continue;
+                            }
+                            (AbortKind::Callee(callee), None, None)
+                        }
+                    }
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+        let block = match block_of.get(&(offset as CodeOffset)) {
+            Some(block) => *block,
+            None => continue,
+        };
+        let paths = get_path_conditions(
+            function,
+            &control_dependence,
+            &printer,
+            &node_of,
+            block,
+            &mut BTreeSet::new(),
+        );
+        for mut conditions in paths {
+            conditions.extend(condition.clone());
+            abort_conditions.push(AbortCondition {
+                function: full_name.to_string(),
+                code_offset: offset as CodeOffset,
+                kind: kind.clone(),
+                conditions,
+                error_code: error_code.clone(),
+                error_constant,
+            });
+        }
+    }
+    abort_conditions
+}
+
+/// Conditions of the branches an explicit `abort` is control dependent on, e.g. the
+/// comparison of `assert!(x > 0, E)` or of `if (x == 0) abort E`.
+pub fn get_abort_branch_conditions(function: &FunctionInfo) -> BTreeSet<TempIndex> {
+    let cfg = match function.cfg.as_ref() {
+        Some(cfg) => cfg,
+        None => return BTreeSet::new(),
+    };
+    let control_dependence = control_flow_graph::get_control_dependence(function);
+    let mut conditions = BTreeSet::new();
+    for block in cfg.blocks() {
+        let aborts = cfg
+            .instr_indexes(block)
+            .into_iter()
+            .flatten()
+            .any(|offset| matches!(function.code[offset as usize], Bytecode::Abort(..)));
+        if !aborts {
+            continue;
+        }
+        for (controller, _) in control_dependence.get(&block).into_iter().flatten() {
+            if let BlockContent::Basic { upper, .. } = cfg.content(*controller) {
+                if let Bytecode::Branch(_, _, _, cond) = &function.code[*upper as usize] {
+                    conditions.insert(*cond);
+                }
+            }
+        }
+    }
+    conditions
+}
+
+/// Whether one of `temps` decides a branch to an `abort`, directly or through copies and
+/// `!`, `&&`, `||`. A comparison that only computes an unused bool is no check.
+pub fn controls_abort(function: &FunctionInfo, temps: &BTreeSet<TempIndex>) -> bool {
+    if temps.is_empty() {
+        return false;
+    }
+    let mut derived = temps.clone();
+    for bytecode in function.code.iter() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) if derived.contains(src) => {
+                derived.insert(*dst);
+            }
+            Bytecode::Call(_, dsts, Operation::Not | Operation::And | Operation::Or, srcs, _)
+                if srcs.iter().any(|src| derived.contains(src)) =>
+            {
+                derived.extend(dsts.iter().copied());
+            }
+            _ => {}
+        }
+    }
+    !get_abort_branch_conditions(function).is_disjoint(&derived)
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::test_utils::{full_name, with_paths, ModuleBuilder};
+
+    fn conditions_of(packages: &Packages, fname: &str) -> Vec<AbortCondition> {
+        get_abort_conditions(packages, &full_name(packages, fname))
+    }
+
+    #[test]
+    fn test_abort_conditions() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_ty = || SignatureToken::U64;
+        let vector_ref =
+            SignatureToken::Reference(Box::new(SignatureToken::Vector(Box::new(u64_ty()))));
+        let check = builder.declare(
+            "check",
+            vec![vector_ref, u64_ty(), u64_ty(), SignatureToken::U128],
+            vec![],
+        );
+        let clever = builder.declare("clever", vec![u64_ty()], vec![]);
+        let literal = builder.declare("literal", vec![u64_ty()], vec![]);
+        let e_too_small = builder.u64_constant(7);
+        // 与错误码同值的无关常量，不能被当作 abort 的常量
+        builder.u64_constant(7);
+        let e_zero = builder.u64_constant(9);
+        let u64_vector = builder.signature(vec![u64_ty()]);
+
+        // check(v, i, x, y) {
+        //     assert!(x > 10, E_TOO_SMALL);
+        //     x + 1;
+        //     (y as u8);
+        //     *vector::borrow(v, i);
+        // }
+        builder.define(
+            check,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(2),
+                MoveBytecode::LdU64(10),
+                MoveBytecode::Gt,
+                MoveBytecode::BrTrue(6),
+                MoveBytecode::LdConst(e_too_small),
+                MoveBytecode::Abort,
+                MoveBytecode::CopyLoc(2),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Add,
+                MoveBytecode::Pop,
+                MoveBytecode::MoveLoc(3),
+                MoveBytecode::CastU8,
+                MoveBytecode::Pop,
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::VecImmBorrow(u64_vector),
+                MoveBytecode::ReadRef,
+                MoveBytecode::Pop,
+                MoveBytecode::Ret,
+            ],
+        );
+        // clever(x) { assert!(x != 0, EZero) }，以 clever error 编码常量下标
+        let assert_nonzero = |abort_code: u64| {
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Neq,
+                MoveBytecode::BrTrue(6),
+                MoveBytecode::LdU64(abort_code),
+                MoveBytecode::Abort,
+                MoveBytecode::Ret,
+            ]
+        };
+        let clever_code = 0x8000_0000_0000_0000 | (12 << 32) | (3 << 16) | e_zero.0 as u64;
+        builder.define(
+            clever,
+            Visibility::Public,
+            vec![],
+            assert_nonzero(clever_code),
+        );
+        // literal(x) { assert!(x != 0, 7) }，字面量错误码不对应任何常量
+        builder.define(literal, Visibility::Public, vec![], assert_nonzero(7));
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+
+        let conditions = conditions_of(&packages, "check");
+        let kinds: Vec<AbortKind> = conditions.iter().map(|c| c.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AbortKind::Abort,
+                AbortKind::Overflow,
+                AbortKind::CastOverflow,
+                AbortKind::OutOfBounds
+            ]
+        );
+        // assert! 失败的分支，以及其后每条指令所在的分支
+        let abort = &conditions[0];
+        assert_eq!(abort.conditions.len(), 1);
+        assert!(abort.conditions[0].ends_with("<= 10)"));
+        assert_eq!(abort.error_constant, Some(e_too_small.0 as usize));
+        assert!(matches!(abort.error_code, Some(ErrorCode::Constant(_))));
+        for condition in conditions[1..].iter() {
+            assert_eq!(condition.conditions.len(), 2);
+            assert!(condition.conditions[0].ends_with("> 10)"));
+            assert_eq!(condition.error_constant, None);
+        }
+        assert!(conditions[1].conditions[1].ends_with("+ 1 > MAX_U64"));
+        assert!(conditions[2].conditions[1].ends_with("> MAX_U8"));
+        assert!(conditions[3].conditions[1].contains(">= vector::length("));
+
+        let conditions = conditions_of(&packages, "clever");
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].error_constant, Some(e_zero.0 as usize));
+
+        let conditions = conditions_of(&packages, "literal");
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].kind, AbortKind::Abort);
+        assert_eq!(conditions[0].error_constant, None);
+    }
+}
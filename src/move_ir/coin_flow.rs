@@ -0,0 +1,250 @@
+// 函数内 Coin / Balance 价值流动的符号化摘要
+use std::fmt;
+
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use super::{
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    sui,
+};
+
+pub const MINT_FUNCTIONS: [&str; 4] = [
+    "0x2::coin::mint",
+    "0x2::coin::mint_and_transfer",
+    "0x2::coin::mint_balance",
+    "0x2::balance::increase_supply",
+];
+// 消耗整个 Coin / Balance 并把价值并入其他余额
+pub const DEPOSIT_FUNCTIONS: [&str; 4] = [
+    "0x2::coin::join",
+    "0x2::coin::put",
+    "0x2::balance::join",
+    "0x2::balance::decrease_supply",
+];
+// 销毁 Coin / Balance，余额不为零时中止
+pub const DESTROY_FUNCTIONS: [&str; 2] = ["0x2::coin::destroy_zero", "0x2::balance::destroy_zero"];
+// 从已有余额中取出一部分
+pub const WITHDRAW_FUNCTIONS: [&str; 3] =
+    ["0x2::coin::split", "0x2::coin::take", "0x2::balance::split"];
+pub const TRANSFER_FUNCTIONS: [&str; 2] =
+    ["0x2::transfer::public_transfer", "0x2::transfer::transfer"];
+pub const VALUE_FUNCTIONS: [&str; 2] = ["0x2::coin::value", "0x2::balance::value"];
+pub const ZERO_FUNCTIONS: [&str; 2] = ["0x2::coin::zero", "0x2::balance::zero"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlowKind {
+    Mint,     // 凭空增加供应
+    Withdraw, // 从 Coin / Balance 中拆出
+    Deposit,  // 并入 Coin / Balance 或减少供应
+    Destroy,  // destroy_zero，只能销毁空的 Coin / Balance
+    Transfer, // 转给地址
+    Convert,  // coin::into_balance / coin::from_balance，价值不变
+    Value,    // 读取余额，不移动价值
+    Zero,     // 创建空的 Coin / Balance
+}
+
+impl fmt::Display for FlowKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            FlowKind::Mint => "mint",
+            FlowKind::Withdraw => "withdraw",
+            FlowKind::Deposit => "deposit",
+            FlowKind::Destroy => "destroy",
+            FlowKind::Transfer => "transfer",
+            FlowKind::Convert => "convert",
+            FlowKind::Value => "value",
+            FlowKind::Zero => "zero",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CoinFlow {
+    pub code_offset: usize,
+    pub kind: FlowKind,
+    pub callee: String,
+    pub srcs: Vec<TempIndex>,
+    pub amount: String, // 数据依赖树展开的金额表达式
+}
+
+impl fmt::Display for CoinFlow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} via {} at {}",
+            self.kind, self.amount, self.callee, self.code_offset
+        )
+    }
+}
+
+/// Every value-moving or value-reading framework call of a function, in code order. The
+/// amount is the data-dependency expression of the split/mint amount, `value(<source>)`
+/// when the whole Coin/Balance moves or is read, and `0` for a new empty one.
+pub fn get_coin_flows(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+) -> Vec<CoinFlow> {
+    let data_depent = &stbgr.data_dependency[function.idx];
+    // 取到达调用处的定义，而不是 temp 在整个函数中的所有定义
+    let display = |offset: usize, temp: TempIndex| {
+        let mut res = String::new();
+        data_depent
+            .get_at(function, offset, temp)
+            .display(&mut res, stbgr);
+        res
+    };
+    let mut flows = vec![];
+    for (code_offset, bytecode) in function.code.iter().enumerate() {
+        let (callee, srcs) = match bytecode {
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) => {
+                match sui::get_framework_fname(stbgr, *mid, *fid) {
+                    Some(callee) => (callee, srcs),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+        let kind = if MINT_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Mint
+        } else if WITHDRAW_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Withdraw
+        } else if DEPOSIT_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Deposit
+        } else if DESTROY_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Destroy
+        } else if TRANSFER_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Transfer
+        } else if callee == "0x2::coin::into_balance" || callee == "0x2::coin::from_balance" {
+            FlowKind::Convert
+        } else if VALUE_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Value
+        } else if ZERO_FUNCTIONS.contains(&callee.as_str()) {
+            FlowKind::Zero
+        } else {
+            continue;
+        };
+        let amount = match kind {
+            // split(self, amount) / mint(cap, amount) / increase_supply(supply, amount)
+            FlowKind::Mint | FlowKind::Withdraw if srcs.len() > 1 => display(code_offset, srcs[1]),
+            FlowKind::Deposit if srcs.len() > 1 => {
+                format!("value({})", display(code_offset, srcs[1]))
+            }
+            FlowKind::Zero => "0".to_string(),
+            _ if !srcs.is_empty() => format!("value({})", display(code_offset, srcs[0])),
+            _ => String::new(),
+        };
+        flows.push(CoinFlow {
+            code_offset,
+            kind,
+            callee,
+            srcs: srcs.clone(),
+            amount,
+        });
+    }
+    flows
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::{
+        packages::Packages,
+        test_utils::{full_name, with_paths, ModuleBuilder},
+    };
+
+    #[test]
+    fn test_coin_flows() {
+        let mut builder = ModuleBuilder::new("m");
+        let coin = builder.module_handle(2, "coin");
+        let balance = builder.module_handle(2, "balance");
+        let coin_ty =
+            SignatureToken::Struct(builder.struct_handle(coin, "Coin", AbilitySet::EMPTY));
+        let balance_ty =
+            SignatureToken::Struct(builder.struct_handle(balance, "Balance", AbilitySet::EMPTY));
+        let coin_ref = SignatureToken::Reference(Box::new(coin_ty.clone()));
+        let coin_mut = SignatureToken::MutableReference(Box::new(coin_ty.clone()));
+        let take = builder.declare(
+            "take",
+            vec![coin_mut.clone(), SignatureToken::U64],
+            vec![coin_ty.clone()],
+        );
+        let value =
+            builder.function_handle(coin, "value", vec![coin_ref], vec![SignatureToken::U64]);
+        let zero = builder.function_handle(balance, "zero", vec![], vec![balance_ty]);
+        let split = builder.function_handle(
+            coin,
+            "split",
+            vec![coin_mut, SignatureToken::U64],
+            vec![coin_ty],
+        );
+        // take(c, amount) { coin::value(c); balance::zero(); coin::split(c, amount) }
+        builder.define(
+            take,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::FreezeRef,
+                MoveBytecode::Call(value),
+                MoveBytecode::Pop,
+                MoveBytecode::Call(zero),
+                MoveBytecode::Pop,
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(split),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let flows = packages.get_coin_flows(&full_name(&packages, "take"));
+        let kinds: Vec<FlowKind> = flows.iter().map(|flow| flow.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![FlowKind::Value, FlowKind::Zero, FlowKind::Withdraw]
+        );
+        assert_eq!(flows[0].callee, "0x2::coin::value");
+        assert!(flows[0].amount.starts_with("value("));
+        assert_eq!(flows[1].callee, "0x2::balance::zero");
+        assert_eq!(flows[1].amount, "0");
+        assert_eq!(flows[2].callee, "0x2::coin::split");
+        assert!(flows[1].code_offset < flows[2].code_offset);
+    }
+
+    #[test]
+    fn test_destroy_zero_flow() {
+        let mut builder = ModuleBuilder::new("m");
+        let balance = builder.module_handle(2, "balance");
+        let balance_ty =
+            SignatureToken::Struct(builder.struct_handle(balance, "Balance", AbilitySet::EMPTY));
+        let burn = builder.declare("burn", vec![balance_ty.clone()], vec![]);
+        let destroy_zero =
+            builder.function_handle(balance, "destroy_zero", vec![balance_ty], vec![]);
+        // burn(b) { balance::destroy_zero(b) }
+        builder.define(
+            burn,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(destroy_zero),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let flows = packages.get_coin_flows(&full_name(&packages, "burn"));
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].kind, FlowKind::Destroy);
+        assert_eq!(flows[0].callee, "0x2::balance::destroy_zero");
+        assert!(flows[0].amount.starts_with("value("));
+    }
+}
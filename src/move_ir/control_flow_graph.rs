@@ -1,16 +1,28 @@
-use crate::{move_ir::generate_bytecode::StacklessBytecodeGenerator, utils::utils::DotWeight};
+use crate::{
+    move_ir::generate_bytecode::StacklessBytecodeGenerator,
+    utils::{graph as dom, utils::DotWeight},
+};
 use move_binary_format::file_format::CodeOffset;
+use move_model::ast::TempIndex;
 use move_stackless_bytecode::{
     stackless_bytecode::{Bytecode, Label},
     stackless_control_flow_graph::{BlockContent, StacklessControlFlowGraph},
 };
 use petgraph::{dot::Dot, graph::Graph};
-use std::{collections::BTreeMap, fs, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
 
 use super::bytecode_display::display;
 use super::generate_bytecode::FunctionInfo;
 pub type BlockId = CodeOffset;
 
+/// Virtual exit every block without successors flows to, so that post-dominance is
+/// defined for functions with several returns and aborts.
+pub const EXIT_BLOCK: BlockId = BlockId::MAX;
+
 struct DotCFGBlock<'env> {
     block_id: BlockId,
     content: BlockContent,
@@ -111,3 +123,181 @@ pub fn generate_cfg_in_dot_format<'env>(
     }
     fs::write(&dotfile, &dot_graph).expect("generating dot file for CFG");
 }
+
+/// Block graph of the function with the virtual `EXIT_BLOCK`, None for native functions.
+pub fn get_block_graph(function: &FunctionInfo) -> Option<dom::Graph<BlockId>> {
+    let cfg = function.cfg.as_ref()?;
+    let mut nodes = cfg.blocks();
+    let mut edges = vec![];
+    for block in nodes.iter() {
+        let successors = cfg.successors(*block);
+        if successors.is_empty() {
+            edges.push((*block, EXIT_BLOCK));
+        }
+        edges.extend(successors.iter().map(|succ| (*block, *succ)));
+    }
+    nodes.push(EXIT_BLOCK);
+    Some(dom::Graph::new(cfg.entry_block(), nodes, edges))
+}
+
+/// Immediate dominator of every reachable block but the entry.
+pub fn get_dominator_tree(function: &FunctionInfo) -> BTreeMap<BlockId, BlockId> {
+    get_block_graph(function)
+        .map(|graph| graph.compute_immediate_dominators())
+        .unwrap_or_default()
+}
+
+/// `dominates(a, b)`: every path from the entry to offset `b` passes offset `a`.
+pub fn get_dominance(function: &FunctionInfo) -> impl Fn(usize, usize) -> bool {
+    let mut block_of = BTreeMap::new();
+    if let Some(cfg) = function.cfg.as_ref() {
+        for block in cfg.blocks() {
+            for offset in cfg.instr_indexes(block).into_iter().flatten() {
+                block_of.insert(offset as usize, block);
+            }
+        }
+    }
+    let idom = get_dominator_tree(function);
+    move |a, b| {
+        let (block_a, mut block) = match (block_of.get(&a), block_of.get(&b)) {
+            (Some(block_a), Some(block_b)) => (*block_a, *block_b),
+            _ => return false,
+        };
+        if block == block_a {
+            return a < b;
+        }
+        while let Some(parent) = idom.get(&block) {
+            if *parent == block_a {
+                return true;
+            }
+            block = *parent;
+        }
+        false
+    }
+}
+
+/// Immediate post-dominator of every block that reaches `EXIT_BLOCK`.
+pub fn get_post_dominator_tree(function: &FunctionInfo) -> BTreeMap<BlockId, BlockId> {
+    get_block_graph(function)
+        .map(|graph| graph.compute_immediate_post_dominators(EXIT_BLOCK))
+        .unwrap_or_default()
+}
+
+/// Branch edges `(branch block, successor)` each block is control dependent on.
+pub fn get_control_dependence(
+    function: &FunctionInfo,
+) -> BTreeMap<BlockId, BTreeSet<(BlockId, BlockId)>> {
+    get_block_graph(function)
+        .map(|graph| graph.compute_control_dependence_edges(EXIT_BLOCK))
+        .unwrap_or_default()
+}
+
+/// Condition temp of the conditional branch ending `from`, and whether the edge to `to`
+/// is taken when it is true. None if `from` does not end with a conditional branch.
+pub fn get_branch_outcome(
+    function: &FunctionInfo,
+    from: BlockId,
+    to: BlockId,
+) -> Option<(TempIndex, bool)> {
+    let cfg = function.cfg.as_ref()?;
+    let (upper, lower) = match (cfg.content(from), cfg.content(to)) {
+        (BlockContent::Basic { upper, .. }, BlockContent::Basic { lower, .. }) => (*upper, *lower),
+        _ => return None,
+    };
+    match (
+        &function.code[upper as usize],
+        &function.code[lower as usize],
+    ) {
+        (Bytecode::Branch(_, then_label, _, cond), Bytecode::Label(_, label)) => {
+            Some((*cond, then_label == label))
+        }
+        _ => None,
+    }
+}
+
+/// Writes a graph over the blocks of the function with the given labelled edges, leaving
+/// out blocks without edges.
+fn generate_block_graph_in_dot_format<'env>(
+    function: &'env FunctionInfo,
+    edges: Vec<(BlockId, BlockId, String)>,
+    dotfile: PathBuf,
+    stbgr: &'env StacklessBytecodeGenerator,
+) {
+    let label_offsets = Bytecode::label_offsets(&function.code);
+    let mut graph = Graph::<String, String>::new();
+    let mut node_map = BTreeMap::new();
+    let blocks: BTreeSet<BlockId> = edges
+        .iter()
+        .flat_map(|(from, to, _)| [*from, *to])
+        .collect();
+    for block_id in blocks {
+        let node = match function.cfg.as_ref() {
+            Some(cfg) if block_id != EXIT_BLOCK => DotCFGBlock {
+                block_id,
+                content: *cfg.content(block_id),
+                label_offsets: &label_offsets,
+                function,
+                stbgr,
+            }
+            .to_string(),
+            _ => "[Exit]".to_string(),
+        };
+        node_map.insert(block_id, graph.add_node(node));
+    }
+    for (from, to, label) in edges {
+        graph.add_edge(node_map[&from], node_map[&to], label);
+    }
+    let dot_graph = format!(
+        "{}",
+        Dot::with_attr_getters(&graph, &[], &|_, _| "".to_string(), &|_, _| {
+            "shape=box".to_string()
+        })
+    );
+    if let Some(parent) = dotfile.parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent).expect("create path failed.");
+        }
+    }
+    fs::write(&dotfile, &dot_graph).expect("generating dot file for block graph");
+}
+
+/// Dominator tree, or post-dominator tree rooted at the virtual exit, with an edge from
+/// each immediate (post-)dominator to the blocks it immediately (post-)dominates.
+pub fn generate_dom_tree_in_dot_format<'env>(
+    function: &'env FunctionInfo,
+    dotfile: PathBuf,
+    stbgr: &'env StacklessBytecodeGenerator,
+    post: bool,
+) {
+    let tree = if post {
+        get_post_dominator_tree(function)
+    } else {
+        get_dominator_tree(function)
+    };
+    let edges = tree
+        .into_iter()
+        .map(|(block, idom)| (idom, block, String::new()))
+        .collect();
+    generate_block_graph_in_dot_format(function, edges, dotfile, stbgr);
+}
+
+/// Control-dependence graph with an edge from each branch block to the blocks it decides
+/// whether to run, labelled with the branch outcome that runs them.
+pub fn generate_cdg_in_dot_format<'env>(
+    function: &'env FunctionInfo,
+    dotfile: PathBuf,
+    stbgr: &'env StacklessBytecodeGenerator,
+) {
+    let edges = get_control_dependence(function)
+        .into_iter()
+        .flat_map(|(block, controllers)| {
+            controllers.into_iter().map(move |(from, to)| {
+                let label = get_branch_outcome(function, from, to)
+                    .map(|(_, outcome)| outcome.to_string())
+                    .unwrap_or_default();
+                (from, block, label)
+            })
+        })
+        .collect();
+    generate_block_graph_in_dot_format(function, edges, dotfile, stbgr);
+}
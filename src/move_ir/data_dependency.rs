@@ -1,7 +1,7 @@
 use ethnum::U256;
 use std::{
     cell::RefCell,
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     ops::{Rem, Sub},
     rc::Rc,
     str::FromStr,
@@ -10,10 +10,13 @@ use std::{
 
 use move_binary_format::{
     access::ModuleAccess,
-    file_format::{FunctionDefinitionIndex, FunctionHandleIndex},
+    file_format::{CodeOffset, FunctionDefinitionIndex, FunctionHandleIndex},
     views::FunctionHandleView,
 };
-use move_model::ty::{PrimitiveType, Type, TypeDisplayContext};
+use move_model::{
+    ast::TempIndex,
+    ty::{PrimitiveType, Type, TypeDisplayContext},
+};
 use move_stackless_bytecode::stackless_bytecode::{
     AssignKind,
     Bytecode::{self, *},
@@ -21,15 +24,24 @@ use move_stackless_bytecode::stackless_bytecode::{
     Operation::{self, *},
 };
 
-use super::{bytecode_display::oper_display, generate_bytecode::StacklessBytecodeGenerator};
+use super::{
+    bytecode_display::oper_display,
+    dataflow::{self, DefSite, ReachingDefinitions},
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    summary::FunctionSummary,
+};
 
 #[derive(Debug, Clone)]
 pub enum Val {
     ByteCode(Bytecode), // 运算符
     // 无子节点
     Const(Constant),  // 常量
-    ParamType(Type),  // 函数参数类型
+    ParamType(TempIndex, Type), // 函数参数及其类型
     AssIgn(Bytecode), // move copy store
+    // 控制流汇合处，子节点为到达该处的各个定义
+    Join(TempIndex),
+    // 来自循环下一轮迭代（回边）的定义，尚未建立，无子节点
+    LoopCarried(TempIndex),
 }
 
 #[derive(Debug, Clone)]
@@ -134,12 +146,20 @@ impl Node {
             Val::Const(_) => {
                 is_const = is_const && true;
             }
-            Val::ParamType(_) => {
+            Val::ParamType(..) => {
                 is_const = is_const && false;
             }
             Val::AssIgn(_) => {
                 is_const = is_const && self.subnodes[0].borrow().is_const();
             }
+            Val::Join(_) => {
+                for subnode in self.subnodes.iter() {
+                    is_const = is_const && subnode.borrow().is_const();
+                }
+            }
+            Val::LoopCarried(_) => {
+                is_const = false;
+            }
         };
         is_const
     }
@@ -163,7 +183,7 @@ impl Node {
                 let str = format!("{}", con).to_string();
                 res.push_str(str.as_str());
             }
-            Val::ParamType(param) => {
+            Val::ParamType(_, param) => {
                 let tctx = TypeDisplayContext::WithoutEnv {
                     symbol_pool: &stbgr.symbol_pool,
                     reverse_struct_table: &stbgr.reverse_struct_table,
@@ -183,13 +203,30 @@ impl Node {
                 // res.truncate(res.len()-2);
                 // res.push_str(")");
             }
+            Val::Join(_) => {
+                res.push_str("join(");
+                for subnode in self.subnodes.iter() {
+                    subnode.borrow().display(res, stbgr);
+                    res.push_str(" | ");
+                }
+                res.truncate(res.len() - 3);
+                res.push_str(")");
+            }
+            Val::LoopCarried(temp) => {
+                res.push_str(&format!("$t{}", temp));
+            }
         };
     }
 }
 
 #[derive(Debug, Clone)]
 pub struct DataDepent {
+    // 每个 temp 在整个函数中所有定义的 join，与程序点无关；某处读到的值用 get_at
     pub data: BTreeMap<usize, Node>,
+    // 每条指令定义的 temp 的依赖，(code offset, temp)
+    pub defs: BTreeMap<(CodeOffset, usize), Node>,
+    params: BTreeMap<usize, Node>,
+    reaching: BTreeMap<CodeOffset, BTreeMap<TempIndex, BTreeSet<DefSite>>>,
 }
 
 impl DataDepent {
@@ -197,19 +234,99 @@ impl DataDepent {
         self.data.insert(dst, node);
     }
 
+    fn insert_def(&mut self, offset: usize, dst: usize, node: Node) {
+        self.defs.insert((offset as CodeOffset, dst), node);
+    }
+
+    /// Dependency of `src` as read by the instruction at `offset`: the join of every
+    /// definition of `src` reaching it on the CFG.
+    pub fn get_at(&self, function: &FunctionInfo, offset: usize, src: usize) -> Node {
+        let ty = &function.local_types[src];
+        let mut nodes = vec![];
+        let sites = self
+            .reaching
+            .get(&(offset as CodeOffset))
+            .and_then(|state| state.get(&src));
+        for site in sites.into_iter().flatten() {
+            match site {
+                DefSite::Param => nodes.extend(self.params.get(&src).cloned()),
+                DefSite::Code(def) => match self.defs.get(&(*def, src)) {
+                    Some(node) => nodes.push(node.clone()),
+                    None => nodes.push(Node::new(Val::LoopCarried(src), get_uint_max(ty), false)),
+                },
+            }
+        }
+        join_nodes(src, nodes, ty)
+    }
+
+    /// Definitions of `src` reaching the instruction at `offset`; empty when unreachable.
+    pub fn defs_reaching(&self, offset: usize, src: usize) -> BTreeSet<DefSite> {
+        self.reaching
+            .get(&(offset as CodeOffset))
+            .and_then(|state| state.get(&src))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Dependency of `dst` as defined by the instruction at `offset`.
+    pub fn get_def(&self, offset: CodeOffset, dst: usize) -> Option<&Node> {
+        self.defs.get(&(offset, dst))
+    }
+
+    /// Dependency of each returned value, joined over every `Ret` of the function.
+    pub fn get_returns(&self, function: &FunctionInfo) -> Vec<Node> {
+        let mut returns: Vec<(usize, Vec<Node>)> = vec![];
+        for (offset, code) in function.code.iter().enumerate() {
+            if let Bytecode::Ret(_, rets) = code {
+                for (i, ret) in rets.iter().enumerate() {
+                    if returns.len() <= i {
+                        returns.push((*ret, vec![]));
+                    }
+                    returns[i].1.push(self.get_at(function, offset, *ret));
+                }
+            }
+        }
+        returns
+            .into_iter()
+            .map(|(ret, nodes)| join_nodes(ret, nodes, &function.local_types[ret]))
+            .collect()
+    }
+
+    /// `data` of every temp from the nodes of all its definitions.
+    fn join_defs(&mut self, function: &FunctionInfo) {
+        let mut nodes: BTreeMap<usize, Vec<Node>> = BTreeMap::new();
+        for (temp, node) in self.params.iter() {
+            nodes.entry(*temp).or_default().push(node.clone());
+        }
+        for ((_, temp), node) in self.defs.iter() {
+            nodes.entry(*temp).or_default().push(node.clone());
+        }
+        for (temp, nodes) in nodes {
+            let node = join_nodes(temp, nodes, &function.local_types[temp]);
+            self.data.insert(temp, node);
+        }
+    }
+
     pub fn get(&self, src: usize) -> Node {
         self.data.get(&src).unwrap().clone()
     }
 }
 
 impl<'a> StacklessBytecodeGenerator<'a> {
-    pub fn get_data_dependency(&mut self, stbgrs: &mut Vec<StacklessBytecodeGenerator>) {
+    pub fn get_data_dependency(
+        &mut self,
+        stbgrs: &mut Vec<StacklessBytecodeGenerator>,
+        summaries: &BTreeMap<String, FunctionSummary>,
+    ) {
         for (idx, function) in self.functions.iter().enumerate() {
             let function_handle_idx = FunctionHandleIndex::new(idx as u16);
             let function_handle = self.module.function_handle_at(function_handle_idx);
             let view = FunctionHandleView::new(self.module, function_handle);
             let mut data_depent = DataDepent {
                 data: BTreeMap::new(),
+                defs: BTreeMap::new(),
+                params: BTreeMap::new(),
+                reaching: BTreeMap::new(),
             };
 
             let function_defintion_idx = FunctionDefinitionIndex::new(idx as u16);
@@ -224,17 +341,25 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                 let ty = &function.local_types[i];
                 let uint_max = get_uint_max(ty);
                 let node = Node::new(
-                    Val::ParamType(function.local_types[i].clone()),
+                    Val::ParamType(i, function.local_types[i].clone()),
                     uint_max,
                     false,
                 );
-                data_depent.insert_or_modify(i, node);
+                data_depent.params.insert(i, node);
             }
 
-            for code in function.code.iter() {
+            // 读取 temp 时取所有到达定义的 join，而不是线性扫描中的最后一次赋值
+            let reaching_definitions = ReachingDefinitions {
+                param_count: view.arg_count(),
+            };
+            data_depent.reaching = dataflow::solve(&reaching_definitions, function)
+                .map(|result| result.before)
+                .unwrap_or_default();
+
+            for (offset, code) in function.code.iter().enumerate() {
                 match code {
                     Assign(_, dst, src, _) => {
-                        let node = data_depent.get(*src);
+                        let node = data_depent.get_at(function, offset, *src);
                         let node = Rc::new(RefCell::new(node));
                         let node = Node::new_with_node(
                             Val::AssIgn(code.clone()),
@@ -242,13 +367,14 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                             node.borrow().max,
                             false,
                         );
-                        data_depent.insert_or_modify(*dst, node);
+                        data_depent.insert_def(offset, *dst, node);
                     }
                     Call(_, dsts, oper, srcs, _) => {
                         match oper {
                             // 简单的跨函数分析，如果结果来自函数调用的结果，则进入函数内部通过return指令拿到返回值的依赖
                             Function(mid, fid, _) => {
                                 let mut nodes: Vec<Rc<RefCell<Node>>> = vec![];
+                                let mut resolved = false;
                                 // packages通过ModuleName找到被调函数的module
                                 let mut option_stbgr = None;
                                 let mname = &self.module_names[mid.to_usize()];
@@ -270,13 +396,11 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                                         }
                                     }
                                     if let Some(idx) = idx {
+                                        resolved = true;
                                         let other_dd = &other_stbgr.data_dependency[idx];
                                         let other_funtion = &other_stbgr.functions[idx];
-                                        if let Some(Bytecode::Ret(_, rets)) = other_funtion.code.last() {
-                                            for i in 0..rets.len() {
-                                                let node = other_dd.get(rets[i]);
-                                                nodes.push(Rc::new(RefCell::new(node)));
-                                            }
+                                        for node in other_dd.get_returns(other_funtion) {
+                                            nodes.push(Rc::new(RefCell::new(node)));
                                         }
                                     }
                                 } else if mid.to_usize() == 0 { // 本cm的函数
@@ -289,33 +413,41 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                                     }
                                     if let Some(idx) = idx {
                                         if idx < self.data_dependency.len() {
+                                            resolved = true;
                                             let other_dd = &self.data_dependency[idx];
                                             let other_funtion = &self.functions[idx];
-                                            if let Some(Bytecode::Ret(_, rets)) = other_funtion.code.last() {
-                                                for i in 0..rets.len() {
-                                                    let node = other_dd.get(rets[i]);
-                                                    nodes.push(Rc::new(RefCell::new(node)));
-                                                }
+                                            for node in other_dd.get_returns(other_funtion) {
+                                                nodes.push(Rc::new(RefCell::new(node)));
                                             }
                                         }
                                     }
                                 } else {
                                     for src in srcs {
-                                        let node = data_depent.get(*src);
+                                        let node = data_depent.get_at(function, offset, *src);
                                         nodes.push(Rc::new(RefCell::new(node)));
                                     }
                                 }
 
-                                for dst in dsts {
+                                // 被调函数尚未分析（在本模块中靠后或递归）时，按摘要只依赖相关的参数
+                                let summary = summaries.get(&self.get_full_fname(*mid, *fid)).filter(|_| !resolved);
+                                for (i, dst) in dsts.iter().enumerate() {
                                     let ty = &function.local_types[*dst];
                                     let max = get_uint_max(ty);
-                                    let node = Node::newy_with_nodes(Val::ByteCode(code.clone()), nodes.clone(), max, false);
-                                    data_depent.insert_or_modify(*dst, node.clone());
+                                    let nodes = match summary {
+                                        Some(summary) => summary
+                                            .get_return_deps(i)
+                                            .iter()
+                                            .map(|param| Rc::new(RefCell::new(data_depent.get_at(function, offset, srcs[*param]))))
+                                            .collect(),
+                                        None => nodes.clone(),
+                                    };
+                                    let node = Node::newy_with_nodes(Val::ByteCode(code.clone()), nodes, max, false);
+                                    data_depent.insert_def(offset, *dst, node.clone());
                                 }
                             },
                             Operation::Sub | Add | Operation::Mul | Div | Mod | BitOr | BitAnd | Xor | Shl | Shr  => { // 二元操作
-                                let lnode = data_depent.get(srcs[0]);
-                                let rnode = data_depent.get(srcs[1]);
+                                let lnode = data_depent.get_at(function, offset, srcs[0]);
+                                let rnode = data_depent.get_at(function, offset, srcs[1]);
                                 let ty = &function.local_types[dsts[0]];
                                 // println!("{} {}", srcs[0], srcs[1]);
                                 // let mut res = "".to_string();
@@ -323,40 +455,40 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                                 // println!("{}", res);
                                 let (max, is_constant) = binary_operation_max(oper, lnode.max, rnode.max, lnode.is_constant, rnode.is_constant, ty);
                                 let node = Node::new_with_binary_nodes(Val::ByteCode(code.clone()),Rc::new(RefCell::new(lnode)),Rc::new(RefCell::new(rnode)), max, is_constant);
-                                data_depent.insert_or_modify(dsts[0], node);
+                                data_depent.insert_def(offset, dsts[0], node);
                             },
                             Lt | Gt | Le | Ge | Or | And | Eq | Neq => { // 二元操作，返回值为bool，参数类型不确定
-                                let lnode = data_depent.get(srcs[0]);
-                                let rnode = data_depent.get(srcs[1]);
+                                let lnode = data_depent.get_at(function, offset, srcs[0]);
+                                let rnode = data_depent.get_at(function, offset, srcs[1]);
                                 let node = Node::new_with_binary_nodes(Val::ByteCode(code.clone()),Rc::new(RefCell::new(lnode)),Rc::new(RefCell::new(rnode)), None, false);
-                                data_depent.insert_or_modify(dsts[0], node);
+                                data_depent.insert_def(offset, dsts[0], node);
                             },
                             CastU8 | CastU16 | CastU32 | CastU64 | CastU128 | CastU256 => { // 一元操作
-                                let node = data_depent.get(srcs[0]);
+                                let node = data_depent.get_at(function, offset, srcs[0]);
                                 // 源数据的最大值和cast的范围，取最小值
                                 let ty = &function.local_types[dsts[0]];
                                 let ty_max = get_uint_max(ty);
                                 let max = get_min_uint(node.max, ty_max);
                                 let is_constant = node.is_constant;
                                 let node = Node::new_with_node(Val::ByteCode(code.clone()),Rc::new(RefCell::new(node)), max, is_constant);
-                                data_depent.insert_or_modify(dsts[0], node);
+                                data_depent.insert_def(offset, dsts[0], node);
                             },
                             Not => {
-                                let node = data_depent.get(srcs[0]);
+                                let node = data_depent.get_at(function, offset, srcs[0]);
                                 let node = Node::new_with_node(Val::ByteCode(code.clone()),Rc::new(RefCell::new(node)), None, false);
-                                data_depent.insert_or_modify(dsts[0], node);
+                                data_depent.insert_def(offset, dsts[0], node);
                             },
                             Pack(_, _, _) => { // n -> 1
                                 let mut nodes = vec![];
                                 for src in srcs {
-                                    let node = data_depent.get(*src);
+                                    let node = data_depent.get_at(function, offset, *src);
                                     nodes.push(Rc::new(RefCell::new(node)));
                                 }
                                 let node = Node::newy_with_nodes(Val::ByteCode(code.clone()), nodes, None, false);
-                                data_depent.insert_or_modify(dsts[0], node);
+                                data_depent.insert_def(offset, dsts[0], node);
                             },
                             Unpack(_, _, _) => { // 1 -> n
-                                let node = data_depent.get(srcs[0]);
+                                let node = data_depent.get_at(function, offset, srcs[0]);
                                 let node_rc = Rc::new(RefCell::new(node.clone()));
                                 let children = &node.subnodes;
                                 for (i, dst) in dsts.iter().enumerate() {
@@ -372,17 +504,17 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                                         get_uint_max(ty)
                                     };
                                     let node = Node::new_with_node(Val::ByteCode(code.clone()), node_rc.clone(), max, false);
-                                    data_depent.insert_or_modify(*dst, node.clone());
+                                    data_depent.insert_def(offset, *dst, node.clone());
 
                                 }
                             },
                             Exists(_, _, _) | FreezeRef | BorrowField(_, _, _, _) | BorrowLoc | // 1 -> 1 TODO
                                 ReadRef | BorrowGlobal(_, _, _) | MoveFrom(_, _, _) => {
-                                let node = data_depent.get(srcs[0]);
+                                let node = data_depent.get_at(function, offset, srcs[0]);
                                 let ty = &function.local_types[dsts[0]];
                                 let max = get_uint_max(ty);
                                 let node = Node::new_with_node(Val::ByteCode(code.clone()),Rc::new(RefCell::new(node)), max, false);
-                                data_depent.insert_or_modify(dsts[0], node);
+                                data_depent.insert_def(offset, dsts[0], node);
                             },
                             _ => {
                                 // WriteRef MoveTo 2 -> 0
@@ -393,18 +525,42 @@ impl<'a> StacklessBytecodeGenerator<'a> {
                     Load(_, dst, con) => {
                         let constant = get_uint_constant(con);
                         let node = Node::new(Val::Const(con.clone()), constant, true);
-                        data_depent.insert_or_modify(*dst, node.clone());
+                        data_depent.insert_def(offset, *dst, node.clone());
                     }
                     _ => {
                         continue;
                     }
                 }
             }
+            data_depent.join_defs(function);
             self.data_dependency.push(data_depent);
         }
     }
 }
 
+/// A single definition is its own node, several are joined under a `Val::Join` whose
+/// max covers all of them. No definition at all (e.g. unreachable code) is left unknown.
+fn join_nodes(temp: usize, mut nodes: Vec<Node>, ty: &Type) -> Node {
+    match nodes.len() {
+        0 => Node::new(Val::LoopCarried(temp), get_uint_max(ty), false),
+        1 => nodes.pop().unwrap(),
+        _ => {
+            let max = if nodes.iter().all(|node| node.max.is_some()) {
+                nodes.iter().filter_map(|node| node.max).max()
+            } else {
+                get_uint_max(ty)
+            };
+            let is_constant = nodes.iter().all(|node| node.is_constant)
+                && nodes.iter().all(|node| node.max == nodes[0].max);
+            let nodes = nodes
+                .into_iter()
+                .map(|node| Rc::new(RefCell::new(node)))
+                .collect();
+            Node::newy_with_nodes(Val::Join(temp), nodes, max, is_constant)
+        }
+    }
+}
+
 #[allow(unused)]
 fn is_uint(ty: &Type) -> bool {
     let mut flag = false;
@@ -424,7 +580,7 @@ fn is_uint(ty: &Type) -> bool {
     flag
 }
 
-fn get_uint_max(ty: &Type) -> Option<U256> {
+pub fn get_uint_max(ty: &Type) -> Option<U256> {
     if let Type::Primitive(bty) = ty {
         match bty {
             PrimitiveType::U8 => Some(U256::from_str("255").unwrap()),
@@ -440,7 +596,7 @@ fn get_uint_max(ty: &Type) -> Option<U256> {
     }
 }
 
-fn get_uint_constant(constant: &Constant) -> Option<U256> {
+pub fn get_uint_constant(constant: &Constant) -> Option<U256> {
     match constant {
         Constant::U8(c) => Some(U256::from(*c)),
         Constant::U16(c) => Some(U256::from(*c)),
@@ -0,0 +1,368 @@
+// 基于 StacklessControlFlowGraph 的 worklist 数据流分析框架
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use move_binary_format::file_format::CodeOffset;
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::stackless_bytecode::Bytecode;
+
+use super::{control_flow_graph::BlockId, generate_bytecode::FunctionInfo};
+
+/// A join-semilattice of finite height, so that the worklist reaches a fixed point.
+pub trait JoinSemiLattice: Clone + PartialEq {
+    /// Least upper bound of `self` and `other`, stored in `self`. Returns whether `self` changed.
+    fn join(&mut self, other: &Self) -> bool;
+}
+
+impl<T: Ord + Clone> JoinSemiLattice for BTreeSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        let len = self.len();
+        self.extend(other.iter().cloned());
+        self.len() != len
+    }
+}
+
+// 逐键合并，缺失的键视为 bottom
+impl<K: Ord + Clone, V: JoinSemiLattice> JoinSemiLattice for BTreeMap<K, V> {
+    fn join(&mut self, other: &Self) -> bool {
+        let mut changed = false;
+        for (key, value) in other.iter() {
+            match self.get_mut(key) {
+                Some(current) => changed |= current.join(value),
+                None => {
+                    self.insert(key.clone(), value.clone());
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+pub trait DataflowAnalysis {
+    type State: JoinSemiLattice;
+    const DIRECTION: Direction;
+
+    /// State at the function boundary: the entry of a forward analysis, every exit of a
+    /// backward one.
+    fn boundary_state(&self, function: &FunctionInfo) -> Self::State;
+
+    /// Initial state of every other program point.
+    fn bottom(&self, function: &FunctionInfo) -> Self::State;
+
+    /// Effect of one instruction, applied in the direction of the analysis.
+    fn transfer(&self, offset: CodeOffset, bytecode: &Bytecode, state: &mut Self::State);
+
+    /// State flowing along the CFG edge `from -> to` of a forward analysis, e.g. assuming
+    /// the branch condition. The default passes it through unchanged.
+    fn refine_edge(
+        &self,
+        _function: &FunctionInfo,
+        _from: BlockId,
+        _to: BlockId,
+        _state: &mut Self::State,
+    ) {
+    }
+
+    /// Extrapolate `next` from `previous` once a block has been revisited `WIDENING_DELAY`
+    /// times, so that lattices of unbounded height still converge. The default keeps `next`.
+    fn widen(&self, _previous: &Self::State, _next: &mut Self::State) {}
+}
+
+const WIDENING_DELAY: usize = 3;
+
+/// Fixed-point states around every instruction, in program order whatever the direction:
+/// `before[offset]` holds right before the instruction executes, `after[offset]` right after.
+#[derive(Debug, Clone)]
+pub struct DataflowResult<S> {
+    pub before: BTreeMap<CodeOffset, S>,
+    pub after: BTreeMap<CodeOffset, S>,
+}
+
+/// Solve `analysis` over the CFG of `function` with a worklist of basic blocks.
+/// None for native functions, which have no CFG.
+pub fn solve<A: DataflowAnalysis>(
+    analysis: &A,
+    function: &FunctionInfo,
+) -> Option<DataflowResult<A::State>> {
+    let cfg = function.cfg.as_ref()?;
+    let blocks = cfg.blocks();
+    let mut successors: BTreeMap<BlockId, Vec<BlockId>> = BTreeMap::new();
+    let mut predecessors: BTreeMap<BlockId, Vec<BlockId>> =
+        blocks.iter().map(|block| (*block, vec![])).collect();
+    for block in blocks.iter() {
+        let succs = cfg.successors(*block).clone();
+        for succ in succs.iter() {
+            predecessors.entry(*succ).or_default().push(*block);
+        }
+        successors.insert(*block, succs);
+    }
+    // 前向分析沿后继传播，后向分析沿前驱传播
+    let (inputs, outputs) = match A::DIRECTION {
+        Direction::Forward => (&predecessors, &successors),
+        Direction::Backward => (&successors, &predecessors),
+    };
+    let is_boundary = |block: BlockId| match A::DIRECTION {
+        Direction::Forward => block == cfg.entry_block(),
+        Direction::Backward => successors[&block].is_empty(),
+    };
+    let instructions = |block: BlockId| -> Vec<CodeOffset> {
+        let mut offsets: Vec<CodeOffset> = cfg.instr_indexes(block).into_iter().flatten().collect();
+        if A::DIRECTION == Direction::Backward {
+            offsets.reverse();
+        }
+        offsets
+    };
+
+    let mut block_out: BTreeMap<BlockId, A::State> = blocks
+        .iter()
+        .map(|block| (*block, analysis.bottom(function)))
+        .collect();
+    let mut worklist: VecDeque<BlockId> = match A::DIRECTION {
+        Direction::Forward => blocks.iter().copied().collect(),
+        Direction::Backward => blocks.iter().rev().copied().collect(),
+    };
+    let mut queued: BTreeSet<BlockId> = worklist.iter().copied().collect();
+    let mut visits: BTreeMap<BlockId, usize> = BTreeMap::new();
+    while let Some(block) = worklist.pop_front() {
+        queued.remove(&block);
+        let boundary = is_boundary(block);
+        let mut state = block_input(analysis, function, block, inputs, &block_out, boundary);
+        for offset in instructions(block) {
+            analysis.transfer(offset, &function.code[offset as usize], &mut state);
+        }
+        let visit = visits.entry(block).or_default();
+        *visit += 1;
+        if *visit > WIDENING_DELAY {
+            analysis.widen(&block_out[&block], &mut state);
+        }
+        if block_out[&block] != state {
+            block_out.insert(block, state);
+            for next in outputs[&block].iter() {
+                if queued.insert(*next) {
+                    worklist.push_back(*next);
+                }
+            }
+        }
+    }
+
+    // 不动点上逐条指令重放一次，记录每条指令前后的状态
+    let mut result = DataflowResult {
+        before: BTreeMap::new(),
+        after: BTreeMap::new(),
+    };
+    for block in blocks.iter() {
+        let mut state = block_input(
+            analysis,
+            function,
+            *block,
+            inputs,
+            &block_out,
+            is_boundary(*block),
+        );
+        for offset in instructions(*block) {
+            let incoming = state.clone();
+            analysis.transfer(offset, &function.code[offset as usize], &mut state);
+            let (before, after) = match A::DIRECTION {
+                Direction::Forward => (incoming, state.clone()),
+                Direction::Backward => (state.clone(), incoming),
+            };
+            result.before.insert(offset, before);
+            result.after.insert(offset, after);
+        }
+    }
+    Some(result)
+}
+
+fn block_input<A: DataflowAnalysis>(
+    analysis: &A,
+    function: &FunctionInfo,
+    block: BlockId,
+    inputs: &BTreeMap<BlockId, Vec<BlockId>>,
+    block_out: &BTreeMap<BlockId, A::State>,
+    is_boundary: bool,
+) -> A::State {
+    let mut state = if is_boundary {
+        analysis.boundary_state(function)
+    } else {
+        analysis.bottom(function)
+    };
+    for input in inputs[&block].iter() {
+        if A::DIRECTION == Direction::Forward {
+            let mut incoming = block_out[input].clone();
+            analysis.refine_edge(function, *input, block, &mut incoming);
+            state.join(&incoming);
+        } else {
+            state.join(&block_out[input]);
+        }
+    }
+    state
+}
+
+/// Where a value of a temp was defined: on entry for parameters, otherwise at an instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DefSite {
+    Param,
+    Code(CodeOffset),
+}
+
+/// Temps an instruction assigns. Writes through references are not definitions of the
+/// referenced local, as in the stackless bytecode itself.
+pub fn get_defs(bytecode: &Bytecode) -> Vec<TempIndex> {
+    match bytecode {
+        Bytecode::Assign(_, dst, _, _) | Bytecode::Load(_, dst, _) => vec![*dst],
+        Bytecode::Call(_, dsts, _, _, _) => dsts.clone(),
+        _ => vec![],
+    }
+}
+
+/// Temps an instruction reads, matching `FunctionInfo::use_attrid`.
+pub fn get_uses(bytecode: &Bytecode) -> Vec<TempIndex> {
+    match bytecode {
+        Bytecode::Assign(_, _, src, _) => vec![*src],
+        Bytecode::Call(_, _, _, srcs, _) | Bytecode::Ret(_, srcs) => srcs.clone(),
+        Bytecode::Branch(_, _, _, src) | Bytecode::Abort(_, src) => vec![*src],
+        _ => vec![],
+    }
+}
+
+/// Forward may-analysis of the definitions of each temp reaching a program point.
+pub struct ReachingDefinitions {
+    pub param_count: usize,
+}
+
+impl DataflowAnalysis for ReachingDefinitions {
+    type State = BTreeMap<TempIndex, BTreeSet<DefSite>>;
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn boundary_state(&self, _function: &FunctionInfo) -> Self::State {
+        (0..self.param_count)
+            .map(|param| (param, BTreeSet::from([DefSite::Param])))
+            .collect()
+    }
+
+    fn bottom(&self, _function: &FunctionInfo) -> Self::State {
+        BTreeMap::new()
+    }
+
+    fn transfer(&self, offset: CodeOffset, bytecode: &Bytecode, state: &mut Self::State) {
+        for dst in get_defs(bytecode) {
+            state.insert(dst, BTreeSet::from([DefSite::Code(offset)]));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use move_model::ty::{PrimitiveType, Type};
+    use move_stackless_bytecode::{
+        stackless_bytecode::{AttrId, Constant, Label, Operation},
+        stackless_control_flow_graph::StacklessControlFlowGraph,
+    };
+
+    use super::*;
+
+    /// Function over `code` with its CFG and def/use offsets, for testing analyses
+    /// without a compiled module.
+    pub(crate) fn make_function(code: Vec<Bytecode>, local_types: Vec<Type>) -> FunctionInfo {
+        let mut function = FunctionInfo::new(0, "f".to_string());
+        function.def_attrid = vec![vec![]; local_types.len()];
+        function.use_attrid = vec![vec![]; local_types.len()];
+        for (offset, bytecode) in code.iter().enumerate() {
+            for dst in get_defs(bytecode) {
+                function.def_attrid[dst].push(offset);
+            }
+            for src in get_uses(bytecode) {
+                function.use_attrid[src].push(offset);
+            }
+        }
+        function.cfg = Some(StacklessControlFlowGraph::new_forward(&code));
+        function.code = code;
+        function.local_types = local_types;
+        function
+    }
+
+    pub(crate) fn attr() -> AttrId {
+        AttrId::new(0)
+    }
+
+    pub(crate) fn u64_ty() -> Type {
+        Type::Primitive(PrimitiveType::U64)
+    }
+
+    pub(crate) fn bool_ty() -> Type {
+        Type::Primitive(PrimitiveType::Bool)
+    }
+
+    fn sites(sites: &[DefSite]) -> BTreeSet<DefSite> {
+        sites.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_join_semilattice() {
+        let mut state: BTreeMap<TempIndex, BTreeSet<DefSite>> =
+            BTreeMap::from([(0, sites(&[DefSite::Param]))]);
+        let other = BTreeMap::from([
+            (0, sites(&[DefSite::Code(3)])),
+            (1, sites(&[DefSite::Code(4)])),
+        ]);
+        assert!(state.join(&other));
+        assert_eq!(state[&0], sites(&[DefSite::Param, DefSite::Code(3)]));
+        assert_eq!(state[&1], sites(&[DefSite::Code(4)]));
+        assert!(!state.join(&other));
+    }
+
+    #[test]
+    fn test_reaching_definitions_on_branch() {
+        // if ($t0) $t1 = 1 else $t1 = 2; return $t1
+        let code = vec![
+            Bytecode::Branch(attr(), Label::new(0), Label::new(1), 0),
+            Bytecode::Label(attr(), Label::new(0)),
+            Bytecode::Load(attr(), 1, Constant::U64(1)),
+            Bytecode::Jump(attr(), Label::new(2)),
+            Bytecode::Label(attr(), Label::new(1)),
+            Bytecode::Load(attr(), 1, Constant::U64(2)),
+            Bytecode::Jump(attr(), Label::new(2)),
+            Bytecode::Label(attr(), Label::new(2)),
+            Bytecode::Ret(attr(), vec![1]),
+        ];
+        let function = make_function(code, vec![bool_ty(), u64_ty()]);
+        let result = solve(&ReachingDefinitions { param_count: 1 }, &function).unwrap();
+        assert_eq!(result.before[&0][&0], sites(&[DefSite::Param]));
+        assert!(!result.before[&2].contains_key(&1));
+        assert_eq!(result.after[&2][&1], sites(&[DefSite::Code(2)]));
+        assert_eq!(
+            result.before[&8][&1],
+            sites(&[DefSite::Code(2), DefSite::Code(5)])
+        );
+        assert_eq!(result.before[&8][&0], sites(&[DefSite::Param]));
+    }
+
+    #[test]
+    fn test_reaching_definitions_on_loop() {
+        // while ($t0 < $t0) $t0 = 0; return $t0
+        let code = vec![
+            Bytecode::Label(attr(), Label::new(0)),
+            Bytecode::Call(attr(), vec![1], Operation::Lt, vec![0, 0], None),
+            Bytecode::Branch(attr(), Label::new(1), Label::new(2), 1),
+            Bytecode::Label(attr(), Label::new(1)),
+            Bytecode::Load(attr(), 0, Constant::U64(0)),
+            Bytecode::Jump(attr(), Label::new(0)),
+            Bytecode::Label(attr(), Label::new(2)),
+            Bytecode::Ret(attr(), vec![0]),
+        ];
+        let function = make_function(code, vec![u64_ty(), bool_ty()]);
+        let result = solve(&ReachingDefinitions { param_count: 1 }, &function).unwrap();
+        // 回边把循环体内的定义带回循环头
+        let both = sites(&[DefSite::Param, DefSite::Code(4)]);
+        assert_eq!(result.before[&1][&0], both);
+        assert_eq!(result.before[&7][&0], both);
+        assert_eq!(result.after[&4][&0], sites(&[DefSite::Code(4)]));
+        assert_eq!(result.before[&2][&1], sites(&[DefSite::Code(1)]));
+    }
+}
@@ -0,0 +1,129 @@
+// 基于到达定义的 def-use / use-def 链
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use itertools::Itertools;
+use move_binary_format::file_format::CodeOffset;
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::stackless_bytecode::Bytecode;
+
+use super::{
+    bytecode_display,
+    dataflow::{self, get_defs, get_uses, DefSite, ReachingDefinitions},
+    generate_bytecode::StacklessBytecodeGenerator,
+    utils,
+};
+
+#[derive(Debug, Clone)]
+pub struct DefUse {
+    // (use offset, temp) -> 到达该处的定义
+    use_def: BTreeMap<(CodeOffset, TempIndex), BTreeSet<DefSite>>,
+    // (定义, temp) -> 使用该定义的指令
+    def_use: BTreeMap<(DefSite, TempIndex), BTreeSet<CodeOffset>>,
+}
+
+impl DefUse {
+    /// Chains of the function at `idx`, None for native functions.
+    pub fn new(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Option<Self> {
+        let function = &stbgr.functions[idx];
+        let param_count = utils::get_param_count(idx, stbgr);
+        let reaching = dataflow::solve(&ReachingDefinitions { param_count }, function)?;
+        let mut use_def = BTreeMap::new();
+        let mut def_use: BTreeMap<(DefSite, TempIndex), BTreeSet<CodeOffset>> = (0..param_count)
+            .map(|param| ((DefSite::Param, param), BTreeSet::new()))
+            .collect();
+        for (offset, bytecode) in function.code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            for dst in get_defs(bytecode) {
+                def_use.entry((DefSite::Code(offset), dst)).or_default();
+            }
+            // 不可达指令没有到达定义
+            let state = match reaching.before.get(&offset) {
+                Some(state) => state,
+                None => continue,
+            };
+            for src in get_uses(bytecode) {
+                let defs = state.get(&src).cloned().unwrap_or_default();
+                for def in defs.iter() {
+                    def_use.entry((*def, src)).or_default().insert(offset);
+                }
+                use_def.insert((offset, src), defs);
+            }
+        }
+        Some(Self { use_def, def_use })
+    }
+
+    /// Definitions of `temp` that reach the instruction at `offset`.
+    pub fn defs_reaching(&self, offset: CodeOffset, temp: TempIndex) -> BTreeSet<DefSite> {
+        self.use_def
+            .get(&(offset, temp))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Instructions using each temp defined at `offset`.
+    pub fn uses_of(&self, offset: CodeOffset) -> BTreeMap<TempIndex, BTreeSet<CodeOffset>> {
+        self.uses_of_def(DefSite::Code(offset))
+    }
+
+    /// Instructions using the entry value of a parameter.
+    pub fn uses_of_param(&self, param: TempIndex) -> BTreeSet<CodeOffset> {
+        self.def_use
+            .get(&(DefSite::Param, param))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn uses_of_def(&self, def: DefSite) -> BTreeMap<TempIndex, BTreeSet<CodeOffset>> {
+        self.def_use
+            .range((def, 0)..=(def, TempIndex::MAX))
+            .map(|((_, temp), uses)| (*temp, uses.clone()))
+            .collect()
+    }
+
+    /// Stackless bytecode annotated with the definitions reaching each use
+    /// (`$t1 <- [param, 4]`) and the uses of each definition (`$t3 -> [7, 9]`).
+    pub fn display(&self, idx: usize, stbgr: &StacklessBytecodeGenerator) -> String {
+        let function = &stbgr.functions[idx];
+        let label_offsets = Bytecode::label_offsets(&function.code);
+        let mut f = String::new();
+        writeln!(f, "fun {} {{", function.name).unwrap();
+        for param in 0..utils::get_param_count(idx, stbgr) {
+            writeln!(
+                f,
+                "     param $t{} -> [{}]",
+                param,
+                self.uses_of_param(param).iter().join(", ")
+            )
+            .unwrap();
+        }
+        for (offset, bytecode) in function.code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            let code = bytecode_display::display(bytecode, &label_offsets, stbgr).to_string();
+            let mut notes = vec![];
+            for src in get_uses(bytecode).into_iter().unique() {
+                let defs = self
+                    .defs_reaching(offset, src)
+                    .iter()
+                    .map(|def| match def {
+                        DefSite::Param => "param".to_string(),
+                        DefSite::Code(def) => def.to_string(),
+                    })
+                    .join(", ");
+                notes.push(format!("$t{} <- [{}]", src, defs));
+            }
+            for (dst, uses) in self.uses_of(offset) {
+                notes.push(format!("$t{} -> [{}]", dst, uses.iter().join(", ")));
+            }
+            if notes.is_empty() {
+                writeln!(f, "{:>3}: {}", offset, code).unwrap();
+            } else {
+                writeln!(f, "{:>3}: {:<40} // {}", offset, code, notes.join("; ")).unwrap();
+            }
+        }
+        writeln!(f, "}}").unwrap();
+        f
+    }
+}
@@ -1,8 +1,14 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 use move_binary_format::file_format::CodeOffset;
 use move_model::ast::{self, TempIndex};
-use move_stackless_bytecode::{stackless_bytecode::{AttrId, Label, Bytecode, Operation, BorrowNode, AbortAction, PropKind}, stackless_control_flow_graph::{StacklessControlFlowGraph, BlockContent}};
+use move_stackless_bytecode::{
+    stackless_bytecode::{AbortAction, AttrId, BorrowNode, Bytecode, Label, Operation, PropKind},
+    stackless_control_flow_graph::{BlockContent, StacklessControlFlowGraph},
+};
 
 use crate::{
     move_ir::{control_flow_graph::BlockId, generate_bytecode::FunctionInfo},
@@ -15,28 +21,65 @@ pub struct FatLoop {
     pub val_targets: BTreeSet<TempIndex>,
     pub mut_targets: BTreeMap<TempIndex, bool>,
     pub back_edges: BTreeSet<CodeOffset>,
-    pub sub_loops: Vec<NaturalLoop<BlockId>>
+    pub sub_loops: Vec<NaturalLoop<BlockId>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct LoopAnnotation {
     pub fat_loops: BTreeMap<BlockId, FatLoop>,
+    // 不可归约时循环由强连通分量近似得到
+    pub reducible: bool,
+}
+
+/// Why the loops of a function could not be annotated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoopError {
+    NativeFunction,
+    DummyBlockInLoop(BlockId),
+    HeaderWithoutLabel(BlockId),
+    LatchNotBranchingToHeader(BlockId),
+}
+
+impl fmt::Display for LoopError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoopError::NativeFunction => write!(f, "native function has no control flow graph"),
+            LoopError::DummyBlockInLoop(block) => {
+                write!(f, "loop contains dummy block {}", block)
+            }
+            LoopError::HeaderWithoutLabel(block) => {
+                write!(f, "loop header block {} does not start with a label", block)
+            }
+            LoopError::LatchNotBranchingToHeader(block) => {
+                write!(
+                    f,
+                    "loop latch block {} does not branch into the header",
+                    block
+                )
+            }
+        }
+    }
 }
 
 fn collect_loop_invariants(
     cfg: &StacklessControlFlowGraph,
     function: &FunctionInfo,
     loop_header: BlockId,
-) -> BTreeMap<CodeOffset, (AttrId, ast::Exp)> {
+) -> Result<BTreeMap<CodeOffset, (AttrId, ast::Exp)>, LoopError> {
     let code = &function.code;
     // let asserts_as_invariants = &func_target.data.loop_invariants;
     let asserts_as_invariants: BTreeSet<AttrId> = BTreeSet::new();
 
     let mut invariants = BTreeMap::new();
-    for (index, code_offset) in cfg.instr_indexes(loop_header).unwrap().enumerate() {
+    let instr_indexes = cfg
+        .instr_indexes(loop_header)
+        .ok_or(LoopError::DummyBlockInLoop(loop_header))?;
+    for (index, code_offset) in instr_indexes.enumerate() {
         let bytecode = &code[code_offset as usize];
         if index == 0 {
-            assert!(matches!(bytecode, Bytecode::Label(_, _)));
+            if !matches!(bytecode, Bytecode::Label(_, _)) {
+                return Err(LoopError::HeaderWithoutLabel(loop_header));
+            }
         } else {
             match bytecode {
                 Bytecode::Prop(attr_id, PropKind::Assert, exp)
@@ -48,7 +91,7 @@ fn collect_loop_invariants(
             }
         }
     }
-    invariants
+    Ok(invariants)
 }
 
 pub fn modifies(
@@ -142,7 +185,7 @@ fn collect_loop_targets(
     cfg: &StacklessControlFlowGraph,
     function: &FunctionInfo,
     sub_loops: &[NaturalLoop<BlockId>],
-) -> (BTreeSet<TempIndex>, BTreeMap<TempIndex, bool>) {
+) -> Result<(BTreeSet<TempIndex>, BTreeMap<TempIndex, bool>), LoopError> {
     let code = &function.code;
     let mut val_targets = BTreeSet::new();
     let mut mut_targets = BTreeMap::new();
@@ -154,7 +197,7 @@ fn collect_loop_targets(
     for block_id in fat_loop_body {
         for code_offset in cfg
             .instr_indexes(block_id)
-            .expect("A loop body should never contain a dummy block")
+            .ok_or(LoopError::DummyBlockInLoop(block_id))?
         {
             let bytecode = &code[code_offset as usize];
             let (bc_val_targets, bc_mut_targets) = modifies(bytecode, function);
@@ -169,7 +212,7 @@ fn collect_loop_targets(
             }
         }
     }
-    (val_targets, mut_targets)
+    Ok((val_targets, mut_targets))
 }
 
 fn collect_loop_back_edges(
@@ -177,89 +220,97 @@ fn collect_loop_back_edges(
     cfg: &StacklessControlFlowGraph,
     header_label: Label,
     sub_loops: &[NaturalLoop<BlockId>],
-) -> BTreeSet<CodeOffset> {
+) -> Result<BTreeSet<CodeOffset>, LoopError> {
     sub_loops
         .iter()
         .map(|l| {
             let code_offset = match cfg.content(l.loop_latch) {
-                BlockContent::Dummy => {
-                    panic!("A loop body should never contain a dummy block")
-                }
+                BlockContent::Dummy => return Err(LoopError::DummyBlockInLoop(l.loop_latch)),
                 BlockContent::Basic { upper, .. } => *upper,
             };
             match &code[code_offset as usize] {
                 Bytecode::Jump(_, goto_label) if *goto_label == header_label => {}
                 Bytecode::Branch(_, if_label, else_label, _)
                     if *if_label == header_label || *else_label == header_label => {}
-                _ => panic!("The latch bytecode of a loop does not branch into the header"),
+                // 落空进入紧随其后的循环头
+                _ if matches!(
+                    code.get(code_offset as usize + 1),
+                    Some(Bytecode::Label(_, label)) if *label == header_label
+                ) => {}
+                _ => return Err(LoopError::LatchNotBranchingToHeader(l.loop_latch)),
             };
-            code_offset
+            Ok(code_offset)
         })
         .collect()
 }
 
-pub fn get_loops(function: &FunctionInfo) -> (Vec<NaturalLoop<u16>>, LoopAnnotation) {
+/// Natural loops of the function and their annotation. An irreducible control-flow graph,
+/// which hand-written or optimized bytecode may have, gets its loops from the strongly
+/// connected components instead and `reducible` set to false.
+pub fn get_loops(
+    function: &FunctionInfo,
+) -> Result<(Vec<NaturalLoop<BlockId>>, LoopAnnotation), LoopError> {
     let code = &function.code;
-    if let Some(cfg) = function.cfg.as_ref(){
-        let entry = cfg.entry_block();
-        let nodes = cfg.blocks();
-        let edges: Vec<(BlockId, BlockId)> = nodes
-            .iter()
-            .flat_map(|x| {
-                cfg.successors(*x)
-                    .iter()
-                    .map(|y| (*x, *y))
-                    .collect::<Vec<(BlockId, BlockId)>>()
-            })
-            .collect();
-        let graph = Graph::new(entry, nodes, edges);
-        let natural_loops = graph.compute_reducible().expect(
-            "A well-formed Move function is expected to have a reducible control-flow graph",
-        );
-    
-        // collect shared headers from loops
-        let mut fat_headers = BTreeMap::new();
-        for single_loop in natural_loops.clone() {
-            fat_headers
-                .entry(single_loop.loop_header)
-                .or_insert_with(Vec::new)
-                .push(single_loop);
-        }
-    
-        let mut fat_loops = BTreeMap::new();
-        for (fat_root, sub_loops) in fat_headers {
-            // get the label of the scc root
-            let label = match cfg.content(fat_root) {
-                BlockContent::Dummy => panic!("A loop header should never be a dummy block"),
-                BlockContent::Basic { lower, upper: _ } => match code[*lower as usize] {
-                Bytecode::Label(_, label) => label,
-                    _ => panic!("A loop header block is expected to start with a Label bytecode"),
-                },
-            };
-    
-            let invariants = collect_loop_invariants(cfg, function, fat_root);
-            let (val_targets, mut_targets) =
-                collect_loop_targets(cfg, function, &sub_loops);
-            let back_edges = collect_loop_back_edges(code, &cfg, label, &sub_loops);
-    
-            // done with all information collection.
-            fat_loops.insert(
-                fat_root,
-                FatLoop {
-                    invariants,
-                    val_targets,
-                    mut_targets,
-                    back_edges,
-                    sub_loops
-                },
-            );
-        }
-    
-        ( natural_loops, LoopAnnotation { fat_loops } )
-    } else {
-        panic!("cfg is none: please check if function is native.");
+    let cfg = function.cfg.as_ref().ok_or(LoopError::NativeFunction)?;
+    let entry = cfg.entry_block();
+    let nodes = cfg.blocks();
+    let edges: Vec<(BlockId, BlockId)> = nodes
+        .iter()
+        .flat_map(|x| {
+            cfg.successors(*x)
+                .iter()
+                .map(|y| (*x, *y))
+                .collect::<Vec<(BlockId, BlockId)>>()
+        })
+        .collect();
+    let graph = Graph::new(entry, nodes, edges);
+    let (natural_loops, reducible) = match graph.compute_reducible() {
+        Some(natural_loops) => (natural_loops, true),
+        None => (graph.compute_loops_by_scc(), false),
+    };
+
+    // collect shared headers from loops
+    let mut fat_headers = BTreeMap::new();
+    for single_loop in natural_loops.clone() {
+        fat_headers
+            .entry(single_loop.loop_header)
+            .or_insert_with(Vec::new)
+            .push(single_loop);
     }
 
-    // let cfg = function.cfg.as_ref().unwrap();
+    let mut fat_loops = BTreeMap::new();
+    for (fat_root, sub_loops) in fat_headers {
+        // get the label of the scc root
+        let label = match cfg.content(fat_root) {
+            BlockContent::Dummy => return Err(LoopError::DummyBlockInLoop(fat_root)),
+            BlockContent::Basic { lower, upper: _ } => match code[*lower as usize] {
+                Bytecode::Label(_, label) => label,
+                _ => return Err(LoopError::HeaderWithoutLabel(fat_root)),
+            },
+        };
+
+        let invariants = collect_loop_invariants(cfg, function, fat_root)?;
+        let (val_targets, mut_targets) = collect_loop_targets(cfg, function, &sub_loops)?;
+        let back_edges = collect_loop_back_edges(code, cfg, label, &sub_loops)?;
+
+        // done with all information collection.
+        fat_loops.insert(
+            fat_root,
+            FatLoop {
+                invariants,
+                val_targets,
+                mut_targets,
+                back_edges,
+                sub_loops,
+            },
+        );
+    }
 
+    Ok((
+        natural_loops,
+        LoopAnnotation {
+            fat_loops,
+            reducible,
+        },
+    ))
 }
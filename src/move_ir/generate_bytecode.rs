@@ -39,6 +39,7 @@ pub struct FunctionInfo {
     pub code: Vec<Bytecode>,
     pub local_types: Vec<Type>,
     pub location_table: BTreeMap<AttrId, Loc>,
+    pub code_offsets: BTreeMap<AttrId, CodeOffset>, // 每条 stackless 指令对应的原始字节码 offset
     pub loop_invariants: BTreeSet<AttrId>,
     pub fallthrough_labels: BTreeSet<Label>,
     pub cfg: Option<StacklessControlFlowGraph>,
@@ -55,6 +56,7 @@ impl FunctionInfo {
             code: vec![],
             local_types: vec![],
             location_table: BTreeMap::new(),
+            code_offsets: BTreeMap::new(),
             loop_invariants: BTreeSet::new(),
             fallthrough_labels: BTreeSet::new(),
             cfg: None,
@@ -81,6 +83,7 @@ pub struct StacklessBytecodeGenerator<'a> {
     pub functions: Vec<FunctionInfo>,
     pub data_dependency: Vec<DataDepent>,
     pub bytecode_file_path: &'a std::path::PathBuf,
+    pub source_code: Option<String>,
 }
 
 impl<'a> StacklessBytecodeGenerator<'a> {
@@ -188,6 +191,7 @@ impl<'a> StacklessBytecodeGenerator<'a> {
             func_to_node: BTreeMap::new(),
             call_graph: Graph::new(),
             bytecode_file_path: path,
+            source_code: None,
         }
     }
 
@@ -0,0 +1,483 @@
+// 无符号整数的区间（取值范围）分析
+// 使用者：NarrowingCast 判断 cast 是否可能越界，abort 条件剔除不会溢出的算术与 cast
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Write},
+};
+
+use ethnum::U256;
+use move_binary_format::file_format::CodeOffset;
+use move_model::ast::TempIndex;
+use move_stackless_bytecode::{
+    stackless_bytecode::{Bytecode, Operation},
+    stackless_control_flow_graph::BlockContent,
+};
+
+use super::{
+    bytecode_display,
+    control_flow_graph::BlockId,
+    data_dependency::{get_uint_constant, get_uint_max},
+    dataflow::{self, get_defs, DataflowAnalysis, DataflowResult, Direction, JoinSemiLattice},
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    utils,
+};
+
+/// Closed range `[lo, hi]` of an unsigned integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub lo: U256,
+    pub hi: U256,
+}
+
+impl Interval {
+    pub fn new(lo: U256, hi: U256) -> Self {
+        Self { lo, hi }
+    }
+
+    pub fn constant(value: U256) -> Self {
+        Self::new(value, value)
+    }
+
+    /// Every value of `max`'s type.
+    pub fn full(max: U256) -> Self {
+        Self::new(U256::ZERO, max)
+    }
+
+    fn clamp(self, max: U256) -> Self {
+        Self::new(self.lo.min(max), self.hi.min(max))
+    }
+}
+
+impl JoinSemiLattice for Interval {
+    fn join(&mut self, other: &Self) -> bool {
+        let joined = Self::new(self.lo.min(other.lo), self.hi.max(other.hi));
+        let changed = joined != *self;
+        *self = joined;
+        changed
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+/// Forward analysis of the range of every unsigned temp. Ranges are narrowed on the edges
+/// of `Lt/Le/Gt/Ge/Eq` branches, which also covers the success path of `assert!`, and only
+/// keep values for which arithmetic does not abort.
+pub struct IntervalAnalysis<'a> {
+    pub function: &'a FunctionInfo,
+    pub param_count: usize,
+}
+
+impl<'a> IntervalAnalysis<'a> {
+    fn type_max(&self, temp: TempIndex) -> Option<U256> {
+        get_uint_max(&self.function.local_types[temp])
+    }
+
+    fn eval(
+        &self,
+        oper: &Operation,
+        srcs: &[TempIndex],
+        dst: TempIndex,
+        state: &State,
+    ) -> Option<Interval> {
+        let max = self.type_max(dst)?;
+        let operand = |i: usize| srcs.get(i).and_then(|src| state.get(src)).copied();
+        let full = Interval::full(max);
+        let range = match oper {
+            Operation::CastU8
+            | Operation::CastU16
+            | Operation::CastU32
+            | Operation::CastU64
+            | Operation::CastU128
+            | Operation::CastU256 => operand(0).map_or(full, |src| src.clamp(max)),
+            _ => {
+                let (a, b) = match (operand(0), operand(1)) {
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return Some(full),
+                };
+                match oper {
+                    // 溢出会 abort，继续执行的路径上结果不超过类型上界
+                    Operation::Add => Interval::new(
+                        a.lo.checked_add(b.lo).unwrap_or(max),
+                        a.hi.checked_add(b.hi).unwrap_or(max),
+                    )
+                    .clamp(max),
+                    Operation::Sub => {
+                        Interval::new(a.lo.saturating_sub(b.hi), a.hi.saturating_sub(b.lo))
+                    }
+                    Operation::Mul => Interval::new(
+                        a.lo.checked_mul(b.lo).unwrap_or(max),
+                        a.hi.checked_mul(b.hi).unwrap_or(max),
+                    )
+                    .clamp(max),
+                    Operation::Div => {
+                        Interval::new(a.lo / b.hi.max(U256::ONE), a.hi / b.lo.max(U256::ONE))
+                    }
+                    Operation::Mod if b.hi > U256::ZERO => {
+                        Interval::new(U256::ZERO, a.hi.min(b.hi - U256::ONE))
+                    }
+                    Operation::Shr => Interval::new(shr(a.lo, b.hi), shr(a.hi, b.lo)),
+                    // 左移不会 abort，高位丢失时结果可以是任意值
+                    Operation::Shl if b.hi < U256::from(256u32) => {
+                        let hi =
+                            a.hi.checked_shl(b.hi.as_u32())
+                                .filter(|hi| *hi <= max && (*hi >> b.hi.as_u32()) == a.hi);
+                        match hi {
+                            Some(hi) => Interval::new(a.lo << b.lo.as_u32(), hi),
+                            None => full,
+                        }
+                    }
+                    Operation::BitAnd => Interval::new(U256::ZERO, a.hi.min(b.hi)),
+                    Operation::BitOr | Operation::Xor => {
+                        let bits = 256 - a.hi.max(b.hi).leading_zeros();
+                        Interval::new(U256::ZERO, low_mask(bits).min(max))
+                    }
+                    _ => full,
+                }
+            }
+        };
+        Some(range)
+    }
+}
+
+type State = BTreeMap<TempIndex, Interval>;
+
+impl<'a> DataflowAnalysis for IntervalAnalysis<'a> {
+    type State = State;
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn boundary_state(&self, _function: &FunctionInfo) -> Self::State {
+        (0..self.param_count)
+            .filter_map(|param| Some((param, Interval::full(self.type_max(param)?))))
+            .collect()
+    }
+
+    fn bottom(&self, _function: &FunctionInfo) -> Self::State {
+        BTreeMap::new()
+    }
+
+    fn transfer(&self, _offset: CodeOffset, bytecode: &Bytecode, state: &mut Self::State) {
+        let ranges: Vec<(TempIndex, Option<Interval>)> = match bytecode {
+            Bytecode::Assign(_, dst, src, _) => vec![(*dst, state.get(src).copied())],
+            Bytecode::Load(_, dst, constant) => {
+                vec![(*dst, get_uint_constant(constant).map(Interval::constant))]
+            }
+            Bytecode::Call(_, dsts, oper, srcs, _) if dsts.len() == 1 => {
+                vec![(dsts[0], self.eval(oper, srcs, dsts[0], state))]
+            }
+            // 函数返回值、Unpack 等无法推断，取类型的完整范围
+            _ => get_defs(bytecode)
+                .into_iter()
+                .map(|dst| (dst, self.type_max(dst).map(Interval::full)))
+                .collect(),
+        };
+        for (dst, range) in ranges {
+            match range {
+                Some(range) => state.insert(dst, range),
+                None => state.remove(&dst),
+            };
+        }
+    }
+
+    fn refine_edge(&self, function: &FunctionInfo, from: BlockId, to: BlockId, state: &mut State) {
+        let cfg = function.cfg.as_ref().unwrap();
+        let (lower, upper) = match cfg.content(from) {
+            BlockContent::Basic { lower, upper } => (*lower as usize, *upper as usize),
+            BlockContent::Dummy => return,
+        };
+        let (then_label, else_label, cond) = match &function.code[upper] {
+            Bytecode::Branch(_, then_label, else_label, cond) => (*then_label, *else_label, *cond),
+            _ => return,
+        };
+        let to_label = match cfg.content(to) {
+            BlockContent::Basic { lower, .. } => match &function.code[*lower as usize] {
+                Bytecode::Label(_, label) => *label,
+                _ => return,
+            },
+            BlockContent::Dummy => return,
+        };
+        let mut taken = if to_label == then_label && to_label != else_label {
+            true
+        } else if to_label == else_label && to_label != then_label {
+            false
+        } else {
+            return;
+        };
+        // 在块内找到条件的比较，Not 取反
+        let mut cond = cond;
+        let (oper, lhs, rhs) = loop {
+            match find_def(function, lower, upper, cond) {
+                Some((_, Bytecode::Call(_, _, Operation::Not, srcs, _))) => {
+                    taken = !taken;
+                    cond = srcs[0];
+                }
+                Some((_, Bytecode::Call(_, _, oper, srcs, _))) if srcs.len() == 2 => {
+                    break (oper.clone(), srcs[0], srcs[1]);
+                }
+                _ => return,
+            }
+        };
+        let (a, b) = match (state.get(&lhs), state.get(&rhs)) {
+            (Some(a), Some(b)) => (*a, *b),
+            _ => return,
+        };
+        // 统一为 x < y 或 x <= y
+        let (strict, x, y, x_range, y_range) = match (oper, taken) {
+            (Operation::Lt, true) | (Operation::Ge, false) => (true, lhs, rhs, a, b),
+            (Operation::Le, true) | (Operation::Gt, false) => (false, lhs, rhs, a, b),
+            (Operation::Gt, true) | (Operation::Le, false) => (true, rhs, lhs, b, a),
+            (Operation::Ge, true) | (Operation::Lt, false) => (false, rhs, lhs, b, a),
+            (Operation::Eq, true) | (Operation::Neq, false) => {
+                let both = Interval::new(a.lo.max(b.lo), a.hi.min(b.hi));
+                if both.lo <= both.hi {
+                    assume(function, lower, upper, lhs, both, state);
+                    assume(function, lower, upper, rhs, both, state);
+                }
+                return;
+            }
+            _ => return,
+        };
+        let gap = if strict { U256::ONE } else { U256::ZERO };
+        let x_hi = match y_range.hi.checked_sub(gap) {
+            Some(hi) => x_range.hi.min(hi),
+            None => return, // x < 0 不可能成立
+        };
+        let y_lo = y_range.lo.max(x_range.lo.saturating_add(gap));
+        if x_range.lo <= x_hi && y_lo <= y_range.hi {
+            let (x_range, y_range) = (
+                Interval::new(x_range.lo, x_hi),
+                Interval::new(y_lo, y_range.hi),
+            );
+            assume(function, lower, upper, x, x_range, state);
+            assume(function, lower, upper, y, y_range, state);
+        }
+    }
+
+    fn widen(&self, previous: &State, next: &mut State) {
+        for (temp, range) in next.iter_mut() {
+            let (max, previous) = match (self.type_max(*temp), previous.get(temp)) {
+                (Some(max), Some(previous)) => (max, previous),
+                _ => continue,
+            };
+            if range.lo < previous.lo {
+                range.lo = U256::ZERO;
+            }
+            if range.hi > previous.hi {
+                range.hi = max;
+            }
+        }
+    }
+}
+
+fn shr(value: U256, shift: U256) -> U256 {
+    if shift >= U256::from(256u32) {
+        U256::ZERO
+    } else {
+        value >> shift.as_u32()
+    }
+}
+
+fn low_mask(bits: u32) -> U256 {
+    if bits >= 256 {
+        U256::MAX
+    } else {
+        (U256::ONE << bits) - U256::ONE
+    }
+}
+
+/// Last definition of `temp` in `lower..upper` of a block.
+fn find_def(
+    function: &FunctionInfo,
+    lower: usize,
+    upper: usize,
+    temp: TempIndex,
+) -> Option<(usize, &Bytecode)> {
+    (lower..upper)
+        .rev()
+        .map(|offset| (offset, &function.code[offset]))
+        .find(|(_, bytecode)| get_defs(bytecode).contains(&temp))
+}
+
+/// Narrow `temp` to `range`, and the local it was copied from in this block, so that
+/// `if (x < 100)` also bounds later reads of `x`.
+fn assume(
+    function: &FunctionInfo,
+    lower: usize,
+    upper: usize,
+    temp: TempIndex,
+    range: Interval,
+    state: &mut State,
+) {
+    state.insert(temp, range);
+    if let Some((offset, Bytecode::Assign(_, _, src, _))) = find_def(function, lower, upper, temp) {
+        if find_def(function, offset + 1, upper, *src).is_none() {
+            state.insert(*src, range);
+        }
+    }
+}
+
+/// Range of every unsigned temp before and after each instruction, None for natives.
+pub fn get_intervals(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+) -> Option<DataflowResult<BTreeMap<TempIndex, Interval>>> {
+    let function = &stbgr.functions[idx];
+    let analysis = IntervalAnalysis {
+        function,
+        param_count: utils::get_param_count(idx, stbgr),
+    };
+    dataflow::solve(&analysis, function)
+}
+
+/// Stackless bytecode annotated with the range of each unsigned temp it defines.
+pub fn display(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Option<String> {
+    let function = &stbgr.functions[idx];
+    let intervals = get_intervals(idx, stbgr)?;
+    let label_offsets = Bytecode::label_offsets(&function.code);
+    let mut f = String::new();
+    writeln!(f, "fun {} {{", function.name).unwrap();
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        let code = bytecode_display::display(bytecode, &label_offsets, stbgr).to_string();
+        let ranges: Vec<String> = match intervals.after.get(&(offset as CodeOffset)) {
+            Some(after) => get_defs(bytecode)
+                .into_iter()
+                .filter_map(|dst| Some(format!("$t{} in {}", dst, after.get(&dst)?)))
+                .collect(),
+            None => vec![],
+        };
+        if ranges.is_empty() {
+            writeln!(f, "{:>3}: {}", offset, code).unwrap();
+        } else {
+            writeln!(f, "{:>3}: {:<40} // {}", offset, code, ranges.join("; ")).unwrap();
+        }
+    }
+    writeln!(f, "}}").unwrap();
+    Some(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use move_model::ty::Type;
+    use move_stackless_bytecode::stackless_bytecode::{Constant, Label};
+
+    use super::*;
+    use crate::move_ir::dataflow::tests::{attr, bool_ty, make_function, u64_ty};
+
+    fn range(lo: u64, hi: u64) -> Interval {
+        Interval::new(U256::from(lo), U256::from(hi))
+    }
+
+    fn u64_max() -> U256 {
+        U256::from(u64::MAX)
+    }
+
+    fn eval(oper: Operation, a: Interval, b: Interval) -> Interval {
+        let function = make_function(vec![], vec![u64_ty(); 3]);
+        let analysis = IntervalAnalysis {
+            function: &function,
+            param_count: 2,
+        };
+        let state = State::from([(0, a), (1, b)]);
+        analysis.eval(&oper, &[0, 1], 2, &state).unwrap()
+    }
+
+    #[test]
+    fn test_transfer_functions() {
+        assert_eq!(
+            eval(Operation::Sub, range(10, 20), range(3, 5)),
+            range(5, 17)
+        );
+        // 下溢会 abort，继续执行时结果不小于 0
+        assert_eq!(eval(Operation::Sub, range(0, 4), range(3, 5)), range(0, 1));
+        assert_eq!(eval(Operation::Shl, range(1, 3), range(2, 2)), range(4, 12));
+        // 高位移出时结果可以是任意值
+        assert_eq!(
+            eval(Operation::Shl, range(1, 1 << 63), range(1, 1)),
+            Interval::full(u64_max())
+        );
+        assert_eq!(
+            eval(Operation::Mod, range(0, 1000), range(1, 10)),
+            range(0, 9)
+        );
+        assert_eq!(eval(Operation::Mod, range(0, 5), range(1, 10)), range(0, 5));
+        assert_eq!(
+            eval(Operation::Mod, range(0, 5), range(0, 0)),
+            Interval::full(u64_max())
+        );
+    }
+
+    #[test]
+    fn test_widen() {
+        let function = make_function(vec![], vec![u64_ty(), u64_ty(), bool_ty()]);
+        let analysis = IntervalAnalysis {
+            function: &function,
+            param_count: 0,
+        };
+        let previous = State::from([(0, range(5, 10)), (1, range(5, 10)), (2, range(0, 1))]);
+        let mut next = State::from([(0, range(5, 11)), (1, range(4, 10)), (2, range(0, 1))]);
+        analysis.widen(&previous, &mut next);
+        // 增长的上界推到类型上界，减小的下界推到 0，bool 不参与
+        assert_eq!(next[&0], Interval::new(U256::from(5u64), u64_max()));
+        assert_eq!(next[&1], range(0, 10));
+        assert_eq!(next[&2], range(0, 1));
+    }
+
+    #[test]
+    fn test_refine_edge() {
+        // if ($t0 < 100) return $t0 else return $t0
+        let code = vec![
+            Bytecode::Load(attr(), 1, Constant::U64(100)),
+            Bytecode::Call(attr(), vec![2], Operation::Lt, vec![0, 1], None),
+            Bytecode::Branch(attr(), Label::new(0), Label::new(1), 2),
+            Bytecode::Label(attr(), Label::new(0)),
+            Bytecode::Ret(attr(), vec![0]),
+            Bytecode::Label(attr(), Label::new(1)),
+            Bytecode::Ret(attr(), vec![0]),
+        ];
+        let function = make_function(code, vec![u64_ty(), u64_ty(), bool_ty()]);
+        let analysis = IntervalAnalysis {
+            function: &function,
+            param_count: 1,
+        };
+        let result = dataflow::solve(&analysis, &function).unwrap();
+        assert_eq!(result.before[&1][&0], Interval::full(u64_max()));
+        assert_eq!(result.before[&4][&0], range(0, 99));
+        assert_eq!(
+            result.before[&6][&0],
+            Interval::new(U256::from(100u64), u64_max())
+        );
+    }
+
+    #[test]
+    fn test_loop_converges() {
+        // $t0 = 0; while ($t0 < 10) $t0 = $t0 + 1; return $t0
+        let code = vec![
+            Bytecode::Load(attr(), 0, Constant::U64(0)),
+            Bytecode::Label(attr(), Label::new(0)),
+            Bytecode::Load(attr(), 1, Constant::U64(10)),
+            Bytecode::Call(attr(), vec![2], Operation::Lt, vec![0, 1], None),
+            Bytecode::Branch(attr(), Label::new(1), Label::new(2), 2),
+            Bytecode::Label(attr(), Label::new(1)),
+            Bytecode::Load(attr(), 3, Constant::U64(1)),
+            Bytecode::Call(attr(), vec![0], Operation::Add, vec![0, 3], None),
+            Bytecode::Jump(attr(), Label::new(0)),
+            Bytecode::Label(attr(), Label::new(2)),
+            Bytecode::Ret(attr(), vec![0]),
+        ];
+        let local_types: Vec<Type> = vec![u64_ty(), u64_ty(), bool_ty(), u64_ty()];
+        let function = make_function(code, local_types);
+        let analysis = IntervalAnalysis {
+            function: &function,
+            param_count: 0,
+        };
+        // 加宽后循环头的区间不再增长，分析终止
+        let result = dataflow::solve(&analysis, &function).unwrap();
+        assert_eq!(result.before[&7][&0], range(0, 9));
+        assert_eq!(result.after[&7][&0], range(1, 10));
+        // 退出循环时 $t0 >= 10
+        assert_eq!(result.before[&10][&0].lo, U256::from(10u64));
+    }
+}
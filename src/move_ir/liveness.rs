@@ -0,0 +1,243 @@
+// 活跃变量分析与死存储
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use itertools::Itertools;
+use move_binary_format::file_format::{CodeOffset, FunctionDefinitionIndex};
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+
+use super::{
+    bytecode_display,
+    dataflow::{self, get_defs, get_uses, DataflowAnalysis, DataflowResult, Direction},
+    def_use::DefUse,
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    summary::FunctionSummary,
+};
+
+/// Backward analysis of the temps whose current value may still be read.
+pub struct LiveVariables;
+
+impl DataflowAnalysis for LiveVariables {
+    type State = BTreeSet<TempIndex>;
+    const DIRECTION: Direction = Direction::Backward;
+
+    fn boundary_state(&self, _function: &FunctionInfo) -> Self::State {
+        BTreeSet::new()
+    }
+
+    fn bottom(&self, _function: &FunctionInfo) -> Self::State {
+        BTreeSet::new()
+    }
+
+    fn transfer(&self, _offset: CodeOffset, bytecode: &Bytecode, state: &mut Self::State) {
+        for dst in get_defs(bytecode) {
+            state.remove(&dst);
+        }
+        state.extend(get_uses(bytecode));
+    }
+}
+
+/// Live temps before and after every instruction, None for native functions.
+pub fn get_liveness(function: &FunctionInfo) -> Option<DataflowResult<BTreeSet<TempIndex>>> {
+    dataflow::solve(&LiveVariables, function)
+}
+
+#[derive(Debug, Clone)]
+pub struct DeadStore {
+    pub code_offset: CodeOffset,
+    pub temp: TempIndex,
+    pub discarded: bool, // 值只被 Destroy，即 `let _ = x + 1` 或 `x + 1;`
+}
+
+/// Values assigned or computed but never read: the temp is dead right after the
+/// instruction, or its only uses drop it. Function calls are skipped since their side
+/// effects are usually the point, unless the callee is a package function whose summary
+/// shows it is pure; references and locals named `_x` are skipped as well.
+pub fn get_dead_stores(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    summaries: &BTreeMap<String, FunctionSummary>,
+) -> Vec<DeadStore> {
+    let function = &stbgr.functions[idx];
+    let (liveness, def_use) = match (get_liveness(function), DefUse::new(idx, stbgr)) {
+        (Some(liveness), Some(def_use)) => (liveness, def_use),
+        _ => return vec![],
+    };
+    let mut dead_stores = vec![];
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        let offset = offset as CodeOffset;
+        let is_candidate = match bytecode {
+            Bytecode::Assign(..) | Bytecode::Load(..) => true,
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) => summaries
+                .get(&stbgr.get_full_fname(*mid, *fid))
+                .map_or(false, |summary| summary.is_pure()),
+            Bytecode::Call(_, _, oper, _, _) => {
+                !matches!(oper, Operation::Destroy | Operation::Unpack(..))
+            }
+            _ => false,
+        };
+        let live_after = match (is_candidate, liveness.after.get(&offset)) {
+            (true, Some(live_after)) => live_after,
+            _ => continue,
+        };
+        let uses = def_use.uses_of(offset);
+        for dst in get_defs(bytecode) {
+            if matches!(function.local_types[dst], Type::Reference(..)) {
+                continue;
+            }
+            let name = stbgr.get_local_name(FunctionDefinitionIndex::new(idx as u16), dst);
+            if name.display(&stbgr.symbol_pool).to_string().starts_with('_') {
+                continue;
+            }
+            let discarded = uses.get(&dst).map_or(false, |uses| {
+                !uses.is_empty()
+                    && uses.iter().all(|use_offset| {
+                        matches!(
+                            function.code[*use_offset as usize],
+                            Bytecode::Call(_, _, Operation::Destroy, _, _)
+                        )
+                    })
+            });
+            if !live_after.contains(&dst) || discarded {
+                dead_stores.push(DeadStore {
+                    code_offset: offset,
+                    temp: dst,
+                    discarded,
+                });
+            }
+        }
+    }
+    dead_stores
+}
+
+/// Stackless bytecode annotated with the temps live after each instruction.
+pub fn display(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Option<String> {
+    let function = &stbgr.functions[idx];
+    let liveness = get_liveness(function)?;
+    let label_offsets = Bytecode::label_offsets(&function.code);
+    let mut f = String::new();
+    writeln!(f, "fun {} {{", function.name).unwrap();
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        let code = bytecode_display::display(bytecode, &label_offsets, stbgr).to_string();
+        let live = liveness
+            .after
+            .get(&(offset as CodeOffset))
+            .map(|live| live.iter().map(|temp| format!("$t{}", temp)).join(", "))
+            .unwrap_or_else(|| "unreachable".to_string());
+        writeln!(f, "{:>3}: {:<40} // live: {{{}}}", offset, code, live).unwrap();
+    }
+    writeln!(f, "}}").unwrap();
+    Some(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+    use move_stackless_bytecode::stackless_bytecode::{AssignKind, Constant, Label};
+
+    use super::*;
+    use crate::move_ir::{
+        dataflow::tests::{attr, bool_ty, make_function, u64_ty},
+        packages::Packages,
+        test_utils::{full_name, with_paths, ModuleBuilder},
+    };
+
+    #[test]
+    fn test_liveness() {
+        // $t1 = 1; if ($t0) $t2 = $t1 else $t1 = 2; return $t1
+        let code = vec![
+            Bytecode::Load(attr(), 1, Constant::U64(1)),
+            Bytecode::Branch(attr(), Label::new(0), Label::new(1), 0),
+            Bytecode::Label(attr(), Label::new(0)),
+            Bytecode::Assign(attr(), 2, 1, AssignKind::Copy),
+            Bytecode::Jump(attr(), Label::new(2)),
+            Bytecode::Label(attr(), Label::new(1)),
+            Bytecode::Load(attr(), 1, Constant::U64(2)),
+            Bytecode::Jump(attr(), Label::new(2)),
+            Bytecode::Label(attr(), Label::new(2)),
+            Bytecode::Ret(attr(), vec![1]),
+        ];
+        let function = make_function(code, vec![bool_ty(), u64_ty(), u64_ty()]);
+        let liveness = get_liveness(&function).unwrap();
+        assert_eq!(liveness.before[&0], BTreeSet::from([0]));
+        assert_eq!(liveness.after[&0], BTreeSet::from([0, 1]));
+        // 两条路径在汇合点都读 $t1
+        assert_eq!(liveness.after[&3], BTreeSet::from([1]));
+        assert!(liveness.before[&6].is_empty());
+        assert_eq!(liveness.after[&6], BTreeSet::from([1]));
+        assert_eq!(liveness.before[&9], BTreeSet::from([1]));
+        assert!(liveness.after[&9].is_empty());
+    }
+
+    #[test]
+    fn test_dead_stores_of_calls() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_pair = vec![SignatureToken::U64, SignatureToken::U64];
+        let first = builder.declare("first", u64_pair.clone(), vec![SignatureToken::U64]);
+        let add = builder.declare("add", u64_pair.clone(), vec![SignatureToken::U64]);
+        let f = builder.declare("f", u64_pair, vec![]);
+        builder.define(
+            first,
+            Visibility::Private,
+            vec![],
+            vec![MoveBytecode::MoveLoc(0), MoveBytecode::Ret],
+        );
+        // 加法可能溢出 abort，不是纯函数
+        builder.define(
+            add,
+            Visibility::Private,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Add,
+                MoveBytecode::Ret,
+            ],
+        );
+        // f(x, y) { first(x, y); add(x, y); }
+        builder.define(
+            f,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::CopyLoc(1),
+                MoveBytecode::Call(first),
+                MoveBytecode::Pop,
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(add),
+                MoveBytecode::Pop,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        assert!(packages
+            .get_summary(&full_name(&packages, "first"))
+            .unwrap()
+            .is_pure());
+        assert!(!packages
+            .get_summary(&full_name(&packages, "add"))
+            .unwrap()
+            .is_pure());
+        let (stbgr, idx) = packages
+            .get_function_by_full_name(&full_name(&packages, "f"))
+            .unwrap();
+        let function = &stbgr.functions[idx];
+        let dead_stores = get_dead_stores(idx, stbgr, &packages.summaries);
+        assert_eq!(dead_stores.len(), 1);
+        let dead_store = &dead_stores[0];
+        assert!(dead_store.discarded);
+        match &function.code[dead_store.code_offset as usize] {
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) => {
+                assert!(stbgr.get_full_fname(*mid, *fid).ends_with("::first"));
+            }
+            bytecode => panic!("unexpected dead store at {:?}", bytecode),
+        }
+    }
+}
@@ -1,8 +1,20 @@
 pub mod sbir_generator;
+pub mod abort;
 pub mod generate_bytecode;
 pub mod  bytecode_display;
 pub mod control_flow_graph;
 pub mod fatloop;
 pub mod data_dependency;
+pub mod dataflow;
+pub mod def_use;
+pub mod liveness;
+pub mod interval;
+pub mod slice;
+pub mod summary;
+pub mod taint;
 pub mod utils;
-pub mod packages;
\ No newline at end of file
+pub mod packages;
+pub mod sui;
+pub mod coin_flow;
+#[cfg(test)]
+pub(crate) mod test_utils;
\ No newline at end of file
@@ -1,8 +1,27 @@
-use super::generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator};
-use crate::{utils::utils,scanner::result::ModuleName};
-use move_binary_format::CompiledModule;
+use super::{
+    coin_flow::{self, CoinFlow},
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    summary::{get_function_summaries, FunctionSummary},
+    utils as ir_utils,
+};
+use crate::{
+    scanner::{
+        error::{ScanError, ScanResult},
+        result::ModuleName,
+    },
+    utils::utils,
+};
+use move_binary_format::{file_format::Visibility, CompiledModule};
 use move_model::model::FunId;
-use std::{collections::BTreeMap, path::PathBuf};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use petgraph::{
+    graph::{DiGraph, Graph, NodeIndex},
+    visit::{Dfs, Reversed},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
 use std::{
     fs,
     io::{BufReader, Read},
@@ -10,29 +29,48 @@ use std::{
 
 pub struct Packages<'a> {
     packages: BTreeMap<String, StacklessBytecodeGenerator<'a>>,
+    // 整个 package 的调用图，节点为函数全名 module::function，只包含 package 内的函数
+    pub call_graph: Graph<String, ()>,
+    pub func_to_node: BTreeMap<String, NodeIndex>,
+    // 函数全名 -> 过程间摘要
+    pub summaries: BTreeMap<String, FunctionSummary>,
     // todo 新增 Status，其中维护构建失败和成功的数量
 }
 
 impl<'a> Packages<'a> {
     pub fn new(cms: &'a Vec<(PathBuf, CompiledModule)>) -> Self {
-        // 根据cms构建StacklessBytecodeGenerator，并进行IR转换、cfg构建、call_gragh构建、data_dependency分析
-        let mut stbgrs = Vec::new();
+        // 根据cms构建StacklessBytecodeGenerator，并进行IR转换、cfg构建、call_gragh构建
+        let mut packages = BTreeMap::new();
         for (path, cm) in cms.iter() {
             let mut stbgr = StacklessBytecodeGenerator::new(cm, path);
+            stbgr.load_source_map();
             stbgr.generate_function();
             stbgr.get_control_flow_graph();
             stbgr.build_call_graph();
-            stbgr.get_data_dependency(&mut stbgrs);
+            let mname = stbgr.module_data.name.clone();
+            let mname = mname.display(&stbgr.symbol_pool).to_string();
+            packages.insert(mname, stbgr);
+        }
+        let (call_graph, func_to_node) = build_call_graph(&packages);
+        let summaries = get_function_summaries(&packages, &call_graph);
+        // data_dependency 按模块依赖顺序分析，被依赖的模块在前，与 .mv 文件的读取顺序无关
+        let mut stbgrs = Vec::new();
+        for mname in get_module_order(&packages) {
+            let mut stbgr = packages.remove(&mname).unwrap();
+            stbgr.get_data_dependency(&mut stbgrs, &summaries);
             stbgrs.push(stbgr);
         }
-        // package构建
-        let mut packages = BTreeMap::new();
         for stbgr in stbgrs {
             let mname = stbgr.module_data.name.clone();
             let mname = mname.display(&stbgr.symbol_pool).to_string();
             packages.insert(mname, stbgr);
         }
-        Packages { packages: packages }
+        Packages {
+            packages: packages,
+            call_graph,
+            func_to_node,
+            summaries,
+        }
     }
 
     pub fn get_all_stbgr(&self) -> &BTreeMap<String, StacklessBytecodeGenerator<'a>> {
@@ -55,10 +93,130 @@ impl<'a> Packages<'a> {
         }
         &stbgr.functions[idx as usize]
     }
+
+    /// Look a function up by `module::function`, e.g. a callee name from `get_full_fname`.
+    /// FunIds are not comparable across modules since every module has its own symbol pool.
+    pub fn get_function_by_full_name(
+        &self,
+        full_name: &str,
+    ) -> Option<(&StacklessBytecodeGenerator<'a>, usize)> {
+        find_function(&self.packages, full_name)
+    }
+
+    /// Summary of a package function by `module::function`, None for functions outside the package.
+    pub fn get_summary(&self, full_name: &str) -> Option<&FunctionSummary> {
+        self.summaries.get(full_name)
+    }
+
+    /// Coin / Balance flows of a package function by `module::function`, in code order.
+    pub fn get_coin_flows(&self, full_name: &str) -> Vec<CoinFlow> {
+        match self.get_function_by_full_name(full_name) {
+            Some((stbgr, idx)) => coin_flow::get_coin_flows(&stbgr.functions[idx], stbgr),
+            None => vec![],
+        }
+    }
+
+    /// Public and entry functions (the function itself included) that reach `full_name`
+    /// on the package call graph, i.e. the transactions able to trigger it.
+    pub fn get_entry_points(&self, full_name: &str) -> Vec<String> {
+        let graph = Reversed(&self.call_graph);
+        let mut dfs = Dfs::new(graph, self.func_to_node[full_name]);
+        let mut entry_points = vec![];
+        while let Some(node) = dfs.next(graph) {
+            let caller = &self.call_graph[node];
+            if let Some((stbgr, idx)) = self.get_function_by_full_name(caller) {
+                if (ir_utils::get_visibility(idx, stbgr) == Visibility::Public
+                    || ir_utils::is_entry(idx, stbgr))
+                    && !ir_utils::is_test_only(idx, stbgr)
+                {
+                    entry_points.push(caller.clone());
+                }
+            }
+        }
+        entry_points.sort();
+        entry_points
+    }
+
     pub fn get_module_names(&self) -> Vec<ModuleName>{
          self.packages.keys().cloned().collect()
     }
+
+    pub fn load_source_code(&mut self, mname: &str, source_path: &Path) {
+        if let Some(stbgr) = self.packages.get_mut(mname) {
+            stbgr.source_code = fs::read_to_string(source_path).ok();
+            stbgr.load_attributes();
+        }
+    }
+}
+pub(crate) fn find_function<'b, 'a>(
+    packages: &'b BTreeMap<String, StacklessBytecodeGenerator<'a>>,
+    full_name: &str,
+) -> Option<(&'b StacklessBytecodeGenerator<'a>, usize)> {
+    let (mname, fname) = full_name.rsplit_once("::")?;
+    let stbgr = packages.get(mname)?;
+    let idx = stbgr.functions.iter().position(|f| f.name == fname)?;
+    Some((stbgr, idx))
 }
+
+/// Module names with every module after the package modules it uses. Move forbids
+/// cyclic module dependencies, so this is a topological order.
+fn get_module_order(packages: &BTreeMap<String, StacklessBytecodeGenerator>) -> Vec<String> {
+    fn visit(
+        mname: &str,
+        packages: &BTreeMap<String, StacklessBytecodeGenerator>,
+        visited: &mut BTreeSet<String>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(mname.to_string()) {
+            return;
+        }
+        let stbgr = &packages[mname];
+        for dep in stbgr.module_names.iter().skip(1) {
+            let dep = dep.display(&stbgr.symbol_pool).to_string();
+            if packages.contains_key(&dep) {
+                visit(&dep, packages, visited, order);
+            }
+        }
+        order.push(mname.to_string());
+    }
+    let mut visited = BTreeSet::new();
+    let mut order = vec![];
+    for mname in packages.keys() {
+        visit(mname, packages, &mut visited, &mut order);
+    }
+    order
+}
+
+fn build_call_graph(
+    packages: &BTreeMap<String, StacklessBytecodeGenerator>,
+) -> (Graph<String, ()>, BTreeMap<String, NodeIndex>) {
+    let mut graph: Graph<String, ()> = DiGraph::new();
+    let mut nodes: BTreeMap<String, NodeIndex> = BTreeMap::new();
+    for (mname, stbgr) in packages.iter() {
+        for function in stbgr.functions.iter() {
+            let fname = format!("{}::{}", mname, function.name);
+            let node_idx = graph.add_node(fname.clone());
+            nodes.insert(fname, node_idx);
+        }
+    }
+    for (mname, stbgr) in packages.iter() {
+        for function in stbgr.functions.iter() {
+            let src_idx = nodes[&format!("{}::{}", mname, function.name)];
+            for code in function.code.iter() {
+                if let Bytecode::Call(_, _, Operation::Function(mid, fid, _), _, _) = code {
+                    // 依赖中的函数不在图中
+                    if let Some(dst_idx) = nodes.get(&stbgr.get_full_fname(*mid, *fid)) {
+                        if graph.find_edge(src_idx, *dst_idx).is_none() {
+                            graph.add_edge(src_idx, *dst_idx, ());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    (graph, nodes)
+}
+
 pub fn compile_module(filename: PathBuf) -> Option<CompiledModule> {
     let f = fs::File::open(filename).unwrap();
     let mut reader = BufReader::new(f);
@@ -68,10 +226,19 @@ pub fn compile_module(filename: PathBuf) -> Option<CompiledModule> {
     cm.ok()
 }
 
-pub fn build_compiled_modules(bytecode_path: &PathBuf) -> Vec<(PathBuf, CompiledModule)> {
+/// Deserialize all .mv files; a file that fails is skipped, but nothing deserialized is an error.
+pub fn build_compiled_modules(
+    bytecode_path: &PathBuf,
+) -> ScanResult<Vec<(PathBuf, CompiledModule)>> {
     // 输入路径遍历
     let mut paths = Vec::new();
     utils::visit_dirs(&bytecode_path, &mut paths, true);
+    if paths.is_empty() {
+        return Err(ScanError::InvalidInput(format!(
+            "no bytecode file found in {}",
+            bytecode_path.display()
+        )));
+    }
     // 输入文件解析(反序列化成CompiledModule)
     let mut cms = Vec::new();
     for filename in paths {
@@ -82,5 +249,11 @@ pub fn build_compiled_modules(bytecode_path: &PathBuf) -> Vec<(PathBuf, Compiled
             println!("\x1B[31mFail to deserialize {:?}, Skip.\x1B[0m", filename);
         }
     }
-    cms
+    if cms.is_empty() {
+        return Err(ScanError::DeserializationFailure(format!(
+            "no module in {} could be deserialized",
+            bytecode_path.display()
+        )));
+    }
+    Ok(cms)
 }
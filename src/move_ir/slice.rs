@@ -0,0 +1,515 @@
+// 程序切片：基于数据依赖与控制依赖的后向/前向切片
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::Write,
+    str::FromStr,
+};
+
+use move_binary_format::file_format::CodeOffset;
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::{
+    stackless_bytecode::{Bytecode, Operation},
+    stackless_control_flow_graph::BlockContent,
+};
+
+use super::{
+    bytecode_display, control_flow_graph,
+    dataflow::{get_uses, DefSite},
+    def_use::DefUse,
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    utils,
+};
+
+/// An instruction offset, or the last instruction on a 1-based source line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlicePoint {
+    Offset(CodeOffset),
+    Line(usize),
+}
+
+/// Slicing criterion `module::function@offset` or `module::function@Lline`,
+/// e.g. `0x0::pool::swap@12` or `0x0::pool::swap@L42`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceCriterion {
+    pub function: String,
+    pub point: SlicePoint,
+}
+
+impl FromStr for SliceCriterion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!("invalid slice criterion `{}`, expected module::function@offset or module::function@Lline", s)
+        };
+        let (function, point) = s.rsplit_once('@').ok_or_else(invalid)?;
+        if !function.contains("::") {
+            return Err(invalid());
+        }
+        let point = match point.strip_prefix('L') {
+            Some(line) => SlicePoint::Line(line.parse().map_err(|_| invalid())?),
+            None => SlicePoint::Offset(point.parse().map_err(|_| invalid())?),
+        };
+        Ok(Self {
+            function: function.to_string(),
+            point,
+        })
+    }
+}
+
+/// Program dependence graph of a function at instruction level: every instruction and the
+/// instructions it directly depends on, through a value (reaching definitions, and writes
+/// through a reference to the same location) or through a branch deciding whether it runs.
+#[derive(Debug, Clone)]
+pub struct DependenceGraph {
+    deps: BTreeMap<CodeOffset, BTreeSet<CodeOffset>>,
+}
+
+impl DependenceGraph {
+    /// None for native functions.
+    pub fn new(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Option<Self> {
+        let function = &stbgr.functions[idx];
+        let def_use = DefUse::new(idx, stbgr)?;
+        let mut deps: BTreeMap<CodeOffset, BTreeSet<CodeOffset>> = BTreeMap::new();
+        // 数据依赖
+        for (offset, bytecode) in function.code.iter().enumerate() {
+            let offset = offset as CodeOffset;
+            let entry = deps.entry(offset).or_default();
+            for src in get_uses(bytecode) {
+                for def in def_use.defs_reaching(offset, src) {
+                    if let DefSite::Code(def) = def {
+                        entry.insert(def);
+                    }
+                }
+            }
+        }
+        // 通过引用的读依赖于对同一位置的写
+        for (offset, writes) in get_memory_dependence(idx, stbgr) {
+            deps.entry(offset).or_default().extend(writes);
+        }
+        // 控制依赖
+        for (offset, branches) in get_control_dependence(function) {
+            deps.entry(offset).or_default().extend(branches);
+        }
+        Some(Self { deps })
+    }
+
+    /// Instructions `offset` directly depends on.
+    pub fn get_deps(&self, offset: CodeOffset) -> BTreeSet<CodeOffset> {
+        self.deps.get(&offset).cloned().unwrap_or_default()
+    }
+
+    /// Every instruction that may affect `offset`, `offset` included.
+    pub fn backward_slice(&self, offset: CodeOffset) -> BTreeSet<CodeOffset> {
+        reach(offset, |node| self.get_deps(node))
+    }
+
+    /// Every instruction `offset` may affect, `offset` included.
+    pub fn forward_slice(&self, offset: CodeOffset) -> BTreeSet<CodeOffset> {
+        let mut dependents: BTreeMap<CodeOffset, BTreeSet<CodeOffset>> = BTreeMap::new();
+        for (node, deps) in self.deps.iter() {
+            for dep in deps {
+                dependents.entry(*dep).or_default().insert(*node);
+            }
+        }
+        reach(offset, |node| {
+            dependents.get(&node).cloned().unwrap_or_default()
+        })
+    }
+}
+
+fn reach(
+    start: CodeOffset,
+    next: impl Fn(CodeOffset) -> BTreeSet<CodeOffset>,
+) -> BTreeSet<CodeOffset> {
+    let mut visited = BTreeSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for succ in next(node) {
+            if visited.insert(succ) {
+                queue.push_back(succ);
+            }
+        }
+    }
+    visited
+}
+
+/// Branches each instruction is control dependent on, from the control dependence of its
+/// block.
+pub fn get_control_dependence(
+    function: &FunctionInfo,
+) -> BTreeMap<CodeOffset, BTreeSet<CodeOffset>> {
+    let cfg = match function.cfg.as_ref() {
+        Some(cfg) => cfg,
+        None => return BTreeMap::new(),
+    };
+    let mut control_dependence = BTreeMap::new();
+    for (block, controllers) in control_flow_graph::get_control_dependence(function) {
+        // 控制块的最后一条指令即分支
+        let branches: BTreeSet<CodeOffset> = controllers
+            .iter()
+            .filter_map(|(controller, _)| match cfg.content(*controller) {
+                BlockContent::Basic { upper, .. } => Some(*upper),
+                BlockContent::Dummy => None,
+            })
+            .collect();
+        for offset in cfg.instr_indexes(block).into_iter().flatten() {
+            control_dependence.insert(offset, branches.clone());
+        }
+    }
+    control_dependence
+}
+
+/// Reads through a reference and the writes through any reference to the same location,
+/// grouping references by the locals or parameters they may be borrowed from with
+/// `def_attrid`, and finding their readers and writers with `use_attrid`. A direct use of
+/// a borrowed local is a read as well, so `x` read after `*(&mut x) = v` depends on the write.
+fn get_memory_dependence(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+) -> BTreeMap<CodeOffset, BTreeSet<CodeOffset>> {
+    let function = &stbgr.functions[idx];
+    let param_count = utils::get_param_count(idx, stbgr);
+    let mut locations: BTreeMap<TempIndex, Vec<TempIndex>> = BTreeMap::new();
+    for temp in 0..function.local_types.len() {
+        if matches!(function.local_types[temp], Type::Reference(..)) {
+            for origin in get_ref_origins(function, param_count, temp, &mut BTreeSet::new()) {
+                locations.entry(origin).or_default().push(temp);
+            }
+        }
+    }
+    let mut memory_dependence: BTreeMap<CodeOffset, BTreeSet<CodeOffset>> = BTreeMap::new();
+    for (origin, refs) in locations.iter() {
+        let mut reads = BTreeSet::new();
+        let mut writes = BTreeSet::new();
+        for temp in refs {
+            let is_mut = matches!(function.local_types[*temp], Type::Reference(true, _));
+            for offset in function.use_attrid[*temp].iter() {
+                let offset = *offset as CodeOffset;
+                match &function.code[offset as usize] {
+                    Bytecode::Call(_, _, Operation::WriteRef, srcs, _) if srcs[0] == *temp => {
+                        writes.insert(offset);
+                    }
+                    Bytecode::Call(_, _, Operation::Function(..), _, _) => {
+                        reads.insert(offset);
+                        if is_mut {
+                            writes.insert(offset);
+                        }
+                    }
+                    Bytecode::Call(_, _, Operation::ReadRef, _, _) => {
+                        reads.insert(offset);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        // 被借用的局部变量本身的读，借用指令除外
+        if !matches!(function.local_types[*origin], Type::Reference(..)) {
+            for offset in function.use_attrid[*origin].iter() {
+                let offset = *offset as CodeOffset;
+                if !matches!(
+                    &function.code[offset as usize],
+                    Bytecode::Call(_, _, Operation::BorrowLoc, ..)
+                ) {
+                    reads.insert(offset);
+                }
+            }
+        }
+        for read in reads {
+            let others: BTreeSet<CodeOffset> = writes
+                .iter()
+                .filter(|write| **write != read)
+                .copied()
+                .collect();
+            if !others.is_empty() {
+                memory_dependence.entry(read).or_default().extend(others);
+            }
+        }
+    }
+    memory_dependence
+}
+
+/// The locals a reference may be borrowed from, the parameters it may come in as, or the
+/// reference itself when it was returned by a call or borrowed from global storage. A
+/// reference defined on several paths, e.g. `if (c) &mut a else &mut b`, has the origins
+/// of every definition.
+fn get_ref_origins(
+    function: &FunctionInfo,
+    param_count: usize,
+    temp: TempIndex,
+    visited: &mut BTreeSet<TempIndex>,
+) -> BTreeSet<TempIndex> {
+    if temp < param_count {
+        return BTreeSet::from([temp]);
+    }
+    // 已经沿另一条定义走过
+    if !visited.insert(temp) {
+        return BTreeSet::new();
+    }
+    let mut origins = BTreeSet::new();
+    for offset in function.def_attrid[temp].iter() {
+        match &function.code[*offset] {
+            Bytecode::Assign(_, _, src, _) => {
+                origins.extend(get_ref_origins(function, param_count, *src, visited));
+            }
+            Bytecode::Call(_, _, Operation::BorrowField(..) | Operation::FreezeRef, srcs, _) => {
+                origins.extend(get_ref_origins(function, param_count, srcs[0], visited));
+            }
+            Bytecode::Call(_, _, Operation::BorrowLoc, srcs, _) => {
+                origins.insert(srcs[0]);
+            }
+            _ => {
+                origins.insert(temp);
+            }
+        }
+    }
+    if origins.is_empty() {
+        origins.insert(temp);
+    }
+    origins
+}
+
+/// Offset of the slicing point in `function`: the offset itself if it is in range, or the
+/// last instruction on the line, which usually computes the statement's result.
+pub fn get_criterion_offset(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+    point: SlicePoint,
+) -> Option<CodeOffset> {
+    match point {
+        SlicePoint::Offset(offset) => {
+            Some(offset).filter(|offset| (*offset as usize) < function.code.len())
+        }
+        SlicePoint::Line(line) => (0..function.code.len())
+            .rev()
+            .filter(|offset| {
+                !matches!(
+                    function.code[*offset],
+                    Bytecode::Label(..) | Bytecode::Jump(..)
+                )
+            })
+            .find(|offset| stbgr.get_source_line(function, Some(*offset)) == Some(line))
+            .map(|offset| offset as CodeOffset),
+    }
+}
+
+/// Stackless bytecode of the function trimmed to the instructions in `slice`, with the
+/// criterion marked.
+pub fn display(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    slice: &BTreeSet<CodeOffset>,
+    criterion: CodeOffset,
+) -> String {
+    let function = &stbgr.functions[idx];
+    let label_offsets = Bytecode::label_offsets(&function.code);
+    let mut f = String::new();
+    writeln!(f, "fun {} {{", function.name).unwrap();
+    for offset in slice.iter() {
+        let bytecode = &function.code[*offset as usize];
+        let code = bytecode_display::display(bytecode, &label_offsets, stbgr).to_string();
+        if *offset == criterion {
+            writeln!(f, "{:>3}: {:<40} // criterion", offset, code).unwrap();
+        } else {
+            writeln!(f, "{:>3}: {}", offset, code).unwrap();
+        }
+    }
+    writeln!(f, "}}").unwrap();
+    f
+}
+
+/// Source of the function with the lines in `slice` marked `>` and the criterion line `*`.
+/// None without the source map or the source code.
+pub fn display_source(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    slice: &BTreeSet<CodeOffset>,
+    criterion: CodeOffset,
+) -> Option<String> {
+    let function = &stbgr.functions[idx];
+    let source_code = stbgr.source_code.as_ref()?;
+    let first = stbgr.get_source_line(function, None)?;
+    let last = (0..function.code.len())
+        .filter_map(|offset| stbgr.get_source_line(function, Some(offset)))
+        .max()
+        .unwrap_or(first);
+    let line_of = |offset: &CodeOffset| stbgr.get_source_line(function, Some(*offset as usize));
+    let lines: BTreeSet<usize> = slice.iter().filter_map(line_of).collect();
+    let criterion_line = line_of(&criterion);
+    let mut f = String::new();
+    for (i, text) in source_code.lines().enumerate().take(last).skip(first - 1) {
+        let line = i + 1;
+        let mark = if Some(line) == criterion_line {
+            '*'
+        } else if lines.contains(&line) {
+            '>'
+        } else {
+            ' '
+        };
+        writeln!(f, "{} {:>4} | {}", mark, line, text).unwrap();
+    }
+    Some(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Bytecode as MoveBytecode, SignatureToken, Visibility};
+
+    use super::*;
+    use crate::move_ir::{
+        packages::Packages,
+        test_utils::{full_name, with_paths, ModuleBuilder},
+    };
+
+    fn offsets_of(function: &FunctionInfo, pred: impl Fn(&Bytecode) -> bool) -> Vec<CodeOffset> {
+        (0..function.code.len())
+            .filter(|offset| pred(&function.code[*offset]))
+            .map(|offset| offset as CodeOffset)
+            .collect()
+    }
+
+    #[test]
+    fn test_slice_criterion() {
+        assert_eq!(
+            "0x0::pool::swap@12".parse::<SliceCriterion>(),
+            Ok(SliceCriterion {
+                function: "0x0::pool::swap".to_string(),
+                point: SlicePoint::Offset(12),
+            })
+        );
+        assert_eq!(
+            "0x0::pool::swap@L42"
+                .parse::<SliceCriterion>()
+                .unwrap()
+                .point,
+            SlicePoint::Line(42)
+        );
+        assert!("swap@12".parse::<SliceCriterion>().is_err());
+        assert!("0x0::pool::swap@x".parse::<SliceCriterion>().is_err());
+    }
+
+    #[test]
+    fn test_read_through_borrowed_local() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_ty = SignatureToken::U64;
+        let f = builder.declare("f", vec![u64_ty.clone()], vec![u64_ty.clone()]);
+        // f(x) { let y = 0; *(&mut y) = x; y }
+        builder.define(
+            f,
+            Visibility::Public,
+            vec![u64_ty],
+            vec![
+                MoveBytecode::LdU64(0),
+                MoveBytecode::StLoc(1),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MutBorrowLoc(1),
+                MoveBytecode::WriteRef,
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let (stbgr, idx) = packages
+            .get_function_by_full_name(&full_name(&packages, "f"))
+            .unwrap();
+        let function = &stbgr.functions[idx];
+        let writes = offsets_of(function, |bytecode| {
+            matches!(bytecode, Bytecode::Call(_, _, Operation::WriteRef, ..))
+        });
+        let rets = offsets_of(function, |bytecode| matches!(bytecode, Bytecode::Ret(..)));
+        assert_eq!(writes.len(), 1);
+        let write = writes[0];
+        // 写之后对 y 的直接读依赖于通过引用的写
+        let memory_dependence = get_memory_dependence(idx, stbgr);
+        assert!(!memory_dependence.is_empty());
+        assert!(memory_dependence
+            .values()
+            .all(|deps| *deps == BTreeSet::from([write])));
+
+        let graph = DependenceGraph::new(idx, stbgr).unwrap();
+        let ret = rets[0];
+        let backward = graph.backward_slice(ret);
+        assert!(backward.contains(&write));
+        assert!(graph.forward_slice(write).contains(&ret));
+
+        assert_eq!(
+            get_criterion_offset(function, stbgr, SlicePoint::Offset(ret)),
+            Some(ret)
+        );
+        let len = function.code.len() as CodeOffset;
+        assert_eq!(
+            get_criterion_offset(function, stbgr, SlicePoint::Offset(len)),
+            None
+        );
+        // 没有源码映射时无法按行定位
+        assert_eq!(
+            get_criterion_offset(function, stbgr, SlicePoint::Line(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_reference_with_several_definitions() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_ty = SignatureToken::U64;
+        let u64_mut = SignatureToken::MutableReference(Box::new(u64_ty.clone()));
+        let g = builder.declare(
+            "g",
+            vec![SignatureToken::Bool, u64_ty.clone(), u64_ty],
+            vec![],
+        );
+        // g(c, a, b) { let r = if (c) &mut a else &mut b; *r = 0; }
+        builder.define(
+            g,
+            Visibility::Public,
+            vec![u64_mut],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::BrFalse(5),
+                MoveBytecode::MutBorrowLoc(1),
+                MoveBytecode::StLoc(3),
+                MoveBytecode::Branch(7),
+                MoveBytecode::MutBorrowLoc(2),
+                MoveBytecode::StLoc(3),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::MoveLoc(3),
+                MoveBytecode::WriteRef,
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let (stbgr, idx) = packages
+            .get_function_by_full_name(&full_name(&packages, "g"))
+            .unwrap();
+        let function = &stbgr.functions[idx];
+        let param_count = utils::get_param_count(idx, stbgr);
+        assert_eq!(
+            get_ref_origins(function, param_count, 3, &mut BTreeSet::new()),
+            BTreeSet::from([1, 2])
+        );
+
+        let borrows = offsets_of(function, |bytecode| {
+            matches!(bytecode, Bytecode::Call(_, _, Operation::BorrowLoc, ..))
+        });
+        let branches = offsets_of(function, |bytecode| {
+            matches!(bytecode, Bytecode::Branch(..))
+        });
+        let write = offsets_of(function, |bytecode| {
+            matches!(bytecode, Bytecode::Call(_, _, Operation::WriteRef, ..))
+        })[0];
+        assert_eq!(borrows.len(), 2);
+        assert_eq!(branches.len(), 1);
+        // 写的目标取决于走哪条分支
+        let graph = DependenceGraph::new(idx, stbgr).unwrap();
+        let backward = graph.backward_slice(write);
+        assert!(borrows.iter().all(|borrow| backward.contains(borrow)));
+        assert!(backward.contains(&branches[0]));
+        for borrow in borrows {
+            assert!(graph.get_deps(borrow).contains(&branches[0]));
+        }
+    }
+}
@@ -0,0 +1,397 @@
+// Move 标准库 (0x1) 与 Sui framework (0x2) 相关的识别
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Ability, AbilitySet, FunctionDefinitionIndex},
+    internals::ModuleIndex,
+};
+use move_model::{
+    ast::TempIndex,
+    model::{FunId, ModuleId, StructId},
+    symbol::Symbol,
+    ty::{PrimitiveType, Type},
+};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use num::BigUint;
+
+use super::{
+    abort,
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    packages::Packages,
+    utils,
+};
+
+pub const SHARE_OBJECT_FUNCTIONS: [&str; 2] =
+    ["0x2::transfer::share_object", "0x2::transfer::public_share_object"];
+pub const TX_CONTEXT_SENDER: &str = "0x2::tx_context::sender";
+pub const TX_CONTEXT: &str = "0x2::tx_context::TxContext";
+pub const UID: &str = "0x2::object::UID";
+pub const IS_ONE_TIME_WITNESS: &str = "0x2::types::is_one_time_witness";
+pub const RANDOM: &str = "0x2::random::Random";
+pub const RANDOM_NEW_GENERATOR: &str = "0x2::random::new_generator";
+pub const CLOCK_TIMESTAMP_MS: &str = "0x2::clock::timestamp_ms";
+// 框架中只有持有者才能使用的对象
+pub const FRAMEWORK_CAPABILITIES: [&str; 5] = [
+    "0x2::coin::TreasuryCap",
+    "0x2::coin::DenyCap",
+    "0x2::coin::DenyCapV2",
+    "0x2::package::UpgradeCap",
+    "0x2::package::Publisher",
+];
+
+// 遍历次数由集合大小决定的函数
+pub const COLLECTION_SIZE_FUNCTIONS: [&str; 9] = [
+    "0x1::vector::length",
+    "0x2::table::length",
+    "0x2::table_vec::length",
+    "0x2::object_table::length",
+    "0x2::linked_table::length",
+    "0x2::bag::length",
+    "0x2::object_bag::length",
+    "0x2::vec_map::size",
+    "0x2::vec_set::size",
+];
+
+// Sui clever error: |tag(1)|reserved(15)|line(16)|identifier index(16)|constant index(16)|
+const CLEVER_ERROR_TAG: u64 = 0x8000_0000_0000_0000;
+const CLEVER_ERROR_NONE: u64 = 0xffff;
+
+/// Constant pool index of the error constant a Sui clever abort code names, None for a
+/// plain abort code or a clever error without a constant, e.g. `assert!(cond)`.
+pub fn get_clever_error_constant(abort_code: u64) -> Option<usize> {
+    let const_idx = abort_code & 0xffff;
+    if abort_code & CLEVER_ERROR_TAG == 0 || const_idx == CLEVER_ERROR_NONE {
+        return None;
+    }
+    Some(const_idx as usize)
+}
+
+// std 与 sui 有同名模块（bcs、hash、address），框架名带上地址以区分
+const FRAMEWORK_ADDRESSES: [u8; 2] = [1, 2];
+
+fn get_framework_name(
+    stbgr: &StacklessBytecodeGenerator,
+    mid: ModuleId,
+    symbol: Symbol,
+) -> Option<String> {
+    let module_name = &stbgr.module_names[mid.to_usize()];
+    if !FRAMEWORK_ADDRESSES
+        .iter()
+        .any(|addr| *module_name.addr() == BigUint::from(*addr))
+    {
+        return None;
+    }
+    Some(format!(
+        "0x{:x}::{}::{}",
+        module_name.addr(),
+        module_name.name().display(&stbgr.symbol_pool),
+        symbol.display(&stbgr.symbol_pool)
+    ))
+}
+
+/// `0x2::module::function` of a Move stdlib or Sui framework callee, None for any other callee.
+pub fn get_framework_fname(
+    stbgr: &StacklessBytecodeGenerator,
+    mid: ModuleId,
+    fid: FunId,
+) -> Option<String> {
+    get_framework_name(stbgr, mid, fid.symbol())
+}
+
+/// `0x2::module::Struct` of a Move stdlib or Sui framework struct, None for any other struct.
+pub fn get_framework_struct_name(
+    stbgr: &StacklessBytecodeGenerator,
+    mid: ModuleId,
+    sid: StructId,
+) -> Option<String> {
+    get_framework_name(stbgr, mid, sid.symbol())
+}
+
+/// Structs passed to `transfer::share_object` anywhere in the package.
+pub fn get_shared_structs(packages: &Packages) -> BTreeSet<String> {
+    let mut shared = BTreeSet::new();
+    for stbgr in packages.get_all_stbgr().values() {
+        for function in stbgr.functions.iter() {
+            for bytecode in function.code.iter() {
+                if let Bytecode::Call(_, _, Operation::Function(mid, fid, targs), _, _) = bytecode
+                {
+                    let is_share = get_framework_fname(stbgr, *mid, *fid)
+                        .map_or(false, |fname| SHARE_OBJECT_FUNCTIONS.contains(&fname.as_str()));
+                    if let (true, Some(Type::Struct(smid, sid, _))) = (is_share, targs.first()) {
+                        shared.insert(stbgr.get_struct_full_name(*smid, *sid));
+                    }
+                }
+            }
+        }
+    }
+    shared
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Origin {
+    Sender,
+    Field, // 从结构体字段读出的值
+}
+
+/// Whether the function compares `tx_context::sender` with an address read from a field
+/// and aborts on the outcome, e.g. `assert!(tx_context::sender(ctx) == pool.admin, E_NOT_ADMIN)`.
+pub fn checks_sender(function: &FunctionInfo, stbgr: &StacklessBytecodeGenerator) -> bool {
+    let mut origins: BTreeMap<TempIndex, Origin> = BTreeMap::new();
+    let mut field_refs = BTreeSet::new();
+    let mut comparisons = BTreeSet::new();
+    for bytecode in function.code.iter() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) => {
+                if let Some(origin) = origins.get(src).copied() {
+                    origins.insert(*dst, origin);
+                }
+                if field_refs.contains(src) {
+                    field_refs.insert(*dst);
+                }
+            }
+            Bytecode::Call(_, dsts, Operation::Function(mid, fid, _), _, _) => {
+                let is_sender =
+                    get_framework_fname(stbgr, *mid, *fid).as_deref() == Some(TX_CONTEXT_SENDER);
+                if let (true, Some(dst)) = (is_sender, dsts.first()) {
+                    origins.insert(*dst, Origin::Sender);
+                }
+            }
+            Bytecode::Call(_, dsts, Operation::BorrowField(..), _, _) => {
+                field_refs.insert(dsts[0]);
+            }
+            Bytecode::Call(_, dsts, Operation::ReadRef, srcs, _) => {
+                if field_refs.contains(&srcs[0]) {
+                    origins.insert(dsts[0], Origin::Field);
+                }
+            }
+            Bytecode::Call(_, dsts, Operation::GetField(..), _, _) => {
+                origins.insert(dsts[0], Origin::Field);
+            }
+            Bytecode::Call(_, dsts, Operation::Eq | Operation::Neq, srcs, _) => {
+                let operands: BTreeSet<Origin> = srcs
+                    .iter()
+                    .filter_map(|src| origins.get(src).copied())
+                    .collect();
+                if operands.len() == 2 {
+                    comparisons.extend(dsts.iter().copied());
+                }
+            }
+            _ => {}
+        }
+    }
+    abort::controls_abort(function, &comparisons)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapabilityKind {
+    Capability, // 只能在 init 或受 capability 保护的函数中创建的 key 对象
+    Witness,    // 没有字段的 drop 结构体
+}
+
+/// Classify the package structs that authorize their holder.
+/// Candidates are key-only objects that are never shared, since anyone can pass a shared
+/// object; a candidate packed outside `init` by a function that does not itself take a
+/// capability is dropped, until no more candidates are dropped.
+pub fn get_capabilities(packages: &Packages) -> BTreeMap<String, CapabilityKind> {
+    let shared = get_shared_structs(packages);
+    let mut capabilities = BTreeMap::new();
+    for stbgr in packages.get_all_stbgr().values() {
+        let self_mid = ModuleId::new(stbgr.module.self_handle_idx().into_index());
+        for (def_idx, sid) in stbgr.module_data.struct_idx_to_id.iter() {
+            let abilities = stbgr
+                .module
+                .struct_handle_at(stbgr.module.struct_def_at(*def_idx).struct_handle)
+                .abilities;
+            let name = stbgr.get_struct_full_name(self_mid, *sid);
+            if shared.contains(&name) {
+                continue;
+            }
+            if abilities.has_key() && !abilities.has_store() {
+                capabilities.insert(name, CapabilityKind::Capability);
+            } else if abilities == AbilitySet::EMPTY | Ability::Drop && is_fieldless(stbgr, *sid) {
+                capabilities.insert(name, CapabilityKind::Witness);
+            }
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for stbgr in packages.get_all_stbgr().values() {
+            for (idx, function) in stbgr.functions.iter().enumerate() {
+                if function.name == "init"
+                    || !get_guards(idx, stbgr, &capabilities, &shared).is_empty()
+                {
+                    continue;
+                }
+                for bytecode in function.code.iter() {
+                    if let Bytecode::Call(_, _, Operation::Pack(mid, sid, _), _, _) = bytecode {
+                        let name = stbgr.get_struct_full_name(*mid, *sid);
+                        if capabilities.get(&name) == Some(&CapabilityKind::Capability) {
+                            capabilities.remove(&name);
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    capabilities
+}
+
+// 空结构体编译后会带一个 bool 类型的 dummy_field
+fn is_fieldless(stbgr: &StacklessBytecodeGenerator, sid: StructId) -> bool {
+    match stbgr.module_data.struct_data.get(&sid) {
+        Some(struct_data) => {
+            struct_data.field_data.is_empty()
+                || (struct_data.field_data.len() == 1
+                    && stbgr.get_field_name(sid, 0) == "dummy_field")
+        }
+        None => false,
+    }
+}
+
+/// Capability and witness types among the parameters of a function, including framework
+/// capabilities. Shared objects never guard: any transaction can pass them, even one holding
+/// a `TreasuryCap` (e.g. a shared `TreasuryCap` itself).
+pub fn get_guards(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    capabilities: &BTreeMap<String, CapabilityKind>,
+    shared: &BTreeSet<String>,
+) -> Vec<String> {
+    let function = &stbgr.functions[idx];
+    let mut guards = vec![];
+    for ty in function.local_types[..utils::get_param_count(idx, stbgr)].iter() {
+        let (mid, sid) = match ty.skip_reference() {
+            Type::Struct(mid, sid, _) => (*mid, *sid),
+            _ => continue,
+        };
+        let name = stbgr.get_struct_full_name(mid, sid);
+        if shared.contains(&name) {
+            continue;
+        }
+        let is_framework_capability = get_framework_struct_name(stbgr, mid, sid)
+            .map_or(false, |name| FRAMEWORK_CAPABILITIES.contains(&name.as_str()));
+        if (capabilities.contains_key(&name) || is_framework_capability) && !guards.contains(&name) {
+            guards.push(name);
+        }
+    }
+    guards
+}
+
+/// The struct named after the module in uppercase, which Sui treats as the one-time witness.
+pub fn get_otw_candidate(stbgr: &StacklessBytecodeGenerator) -> Option<StructId> {
+    let otw_name = stbgr.module.self_id().name().as_str().to_uppercase();
+    stbgr
+        .module_data
+        .struct_data
+        .iter()
+        .find(|(_, data)| data.name.display(&stbgr.symbol_pool).to_string() == otw_name)
+        .map(|(sid, _)| *sid)
+}
+
+/// Problems with the shape of a one-time witness: only `drop`, no type parameters,
+/// no fields or a single bool field.
+pub fn check_otw_shape(stbgr: &StacklessBytecodeGenerator, sid: StructId) -> Vec<String> {
+    let mut problems = vec![];
+    let self_mid = ModuleId::new(stbgr.module.self_handle_idx().into_index());
+    if stbgr.get_struct_abilities(self_mid, sid) != Some(AbilitySet::EMPTY | Ability::Drop) {
+        problems.push("abilities must be exactly drop".to_string());
+    }
+    if let Some((def, _)) = stbgr
+        .module_data
+        .struct_idx_to_id
+        .iter()
+        .find(|(_, id)| **id == sid)
+    {
+        let handle = stbgr
+            .module
+            .struct_handle_at(stbgr.module.struct_def_at(*def).struct_handle);
+        if !handle.type_parameters.is_empty() {
+            problems.push("must not have type parameters".to_string());
+        }
+    }
+    let fields = &stbgr.module_data.struct_data[&sid].field_data;
+    let single_bool = fields.len() == 1
+        && fields
+            .values()
+            .all(|data| stbgr.get_type(data) == Type::Primitive(PrimitiveType::Bool));
+    if !fields.is_empty() && !single_bool {
+        problems.push("must have no fields or a single bool field".to_string());
+    }
+    problems
+}
+
+pub fn get_init(stbgr: &StacklessBytecodeGenerator) -> Option<usize> {
+    stbgr.functions.iter().position(|f| f.name == "init")
+}
+
+/// The module's one-time witness: a well-formed candidate consumed by `init`.
+pub fn get_one_time_witness(stbgr: &StacklessBytecodeGenerator) -> Option<StructId> {
+    let sid = get_otw_candidate(stbgr)?;
+    let init = get_init(stbgr)?;
+    let self_mid = ModuleId::new(stbgr.module.self_handle_idx().into_index());
+    let consumed = utils::get_param_count(init, stbgr) == 2
+        && matches!(
+            stbgr.functions[init].local_types.first(),
+            Some(Type::Struct(mid, id, _)) if *mid == self_mid && *id == sid
+        );
+    (consumed && check_otw_shape(stbgr, sid).is_empty()).then_some(sid)
+}
+
+/// Package structs without any ability, which must be unpacked in the transaction
+/// that created them (flash-loan receipts and similar hot potatoes).
+pub fn get_hot_potatoes(packages: &Packages) -> BTreeSet<String> {
+    let mut potatoes = BTreeSet::new();
+    for stbgr in packages.get_all_stbgr().values() {
+        let self_mid = ModuleId::new(stbgr.module.self_handle_idx().into_index());
+        for (def_idx, sid) in stbgr.module_data.struct_idx_to_id.iter() {
+            if utils::get_struct_abilities_strs(stbgr.module, *def_idx).is_empty() {
+                potatoes.insert(stbgr.get_struct_full_name(self_mid, *sid));
+            }
+        }
+    }
+    potatoes
+}
+
+/// Hot potatoes among the return values of a function.
+pub fn get_produced_potatoes(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    potatoes: &BTreeSet<String>,
+) -> Vec<String> {
+    let func_def = stbgr
+        .module
+        .function_def_at(FunctionDefinitionIndex::new(idx as u16));
+    let handle = stbgr.module.function_handle_at(func_def.function);
+    let mut produced = vec![];
+    for ty in stbgr.globalize_signatures(&stbgr.module.signature_at(handle.return_).0) {
+        if let Type::Struct(mid, sid, _) = ty {
+            let name = stbgr.get_struct_full_name(mid, sid);
+            if potatoes.contains(&name) && !produced.contains(&name) {
+                produced.push(name);
+            }
+        }
+    }
+    produced
+}
+
+/// Hot potatoes a function unpacks, with the offset of each unpack.
+pub fn get_consumed_potatoes(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+    potatoes: &BTreeSet<String>,
+) -> Vec<(usize, String)> {
+    let mut consumed = vec![];
+    for (offset, bytecode) in function.code.iter().enumerate() {
+        if let Bytecode::Call(_, _, Operation::Unpack(mid, sid, _), _, _) = bytecode {
+            let name = stbgr.get_struct_full_name(*mid, *sid);
+            if potatoes.contains(&name) {
+                consumed.push((offset, name));
+            }
+        }
+    }
+    consumed
+}
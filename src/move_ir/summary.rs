@@ -0,0 +1,410 @@
+// 过程间函数摘要：返回值依赖的参数、被修改的 &mut 参数、是否 abort
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{CodeOffset, FunctionDefinitionIndex},
+    views::FunctionDefinitionView,
+};
+use move_model::{ast::TempIndex, ty::Type};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use petgraph::{algo::tarjan_scc, graph::Graph};
+
+use super::{
+    dataflow::{self, get_defs, DataflowAnalysis, Direction},
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    packages::find_function,
+    utils,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionSummary {
+    // 每个返回值（按位置）依赖的参数
+    pub return_deps: Vec<BTreeSet<usize>>,
+    // 被写入或交给可能写入的函数的 &mut 参数
+    pub modified_params: BTreeSet<usize>,
+    // 函数自身可能 abort（abort、算术、cast），或调用了可能 abort 的函数
+    pub aborts: bool,
+}
+
+impl FunctionSummary {
+    /// Whether calling the function has no effect but its return values: it modifies no
+    /// `&mut` parameter and cannot abort, which also rules out calls outside the package
+    /// (events, transfers) and global storage.
+    pub fn is_pure(&self) -> bool {
+        self.modified_params.is_empty() && !self.aborts
+    }
+
+    /// Parameters the `i`-th return value depends on, empty while a recursive callee
+    /// has not been summarized yet.
+    pub fn get_return_deps(&self, i: usize) -> BTreeSet<usize> {
+        self.return_deps.get(i).cloned().unwrap_or_default()
+    }
+
+    /// What is assumed of a function without code: every return value depends on every
+    /// parameter, every `&mut` parameter is modified, and it may abort.
+    fn conservative(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Self {
+        let function = &stbgr.functions[idx];
+        let param_count = utils::get_param_count(idx, stbgr);
+        let func_def = stbgr
+            .module
+            .function_def_at(FunctionDefinitionIndex::new(idx as u16));
+        let return_count = FunctionDefinitionView::new(stbgr.module, func_def)
+            .return_()
+            .len();
+        Self {
+            return_deps: vec![(0..param_count).collect(); return_count],
+            modified_params: (0..param_count)
+                .filter(|param| is_mut_ref(&function.local_types[*param]))
+                .collect(),
+            aborts: true,
+        }
+    }
+}
+
+fn is_mut_ref(ty: &Type) -> bool {
+    matches!(ty, Type::Reference(true, _))
+}
+
+/// Operations aborting on some operands: arithmetic overflow and underflow, division by
+/// zero, shifts by the bit width or more, casts out of range, and global storage accesses
+/// to missing or existing resources.
+pub fn may_abort(oper: &Operation) -> bool {
+    matches!(
+        oper,
+        Operation::Add
+            | Operation::Sub
+            | Operation::Mul
+            | Operation::Div
+            | Operation::Mod
+            | Operation::Shl
+            | Operation::Shr
+            | Operation::CastU8
+            | Operation::CastU16
+            | Operation::CastU32
+            | Operation::CastU64
+            | Operation::CastU128
+            | Operation::BorrowGlobal(..)
+            | Operation::MoveFrom(..)
+            | Operation::MoveTo(..)
+    )
+}
+
+/// Whether calling `callee` may abort: from its summary for package functions, and
+/// always for functions outside the package, e.g. `coin::split` or `vector::borrow`.
+pub fn callee_may_abort(summaries: &BTreeMap<String, FunctionSummary>, callee: &str) -> bool {
+    summaries.get(callee).map_or(true, |summary| summary.aborts)
+}
+
+/// Forward analysis of the parameters each temp is computed from, looking through
+/// package calls with the callee summaries found so far.
+struct ParamDependency<'a, 'b> {
+    stbgr: &'b StacklessBytecodeGenerator<'a>,
+    summaries: &'b BTreeMap<String, FunctionSummary>,
+    param_count: usize,
+}
+
+impl<'a, 'b> DataflowAnalysis for ParamDependency<'a, 'b> {
+    type State = BTreeMap<TempIndex, BTreeSet<usize>>;
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn boundary_state(&self, _function: &FunctionInfo) -> Self::State {
+        (0..self.param_count)
+            .map(|param| (param, BTreeSet::from([param])))
+            .collect()
+    }
+
+    fn bottom(&self, _function: &FunctionInfo) -> Self::State {
+        BTreeMap::new()
+    }
+
+    fn transfer(&self, _offset: CodeOffset, bytecode: &Bytecode, state: &mut Self::State) {
+        let deps_of = |srcs: &[TempIndex]| -> BTreeSet<usize> {
+            srcs.iter()
+                .filter_map(|src| state.get(src))
+                .flatten()
+                .copied()
+                .collect()
+        };
+        let deps: Vec<(TempIndex, BTreeSet<usize>)> = match bytecode {
+            Bytecode::Call(_, dsts, Operation::Function(mid, fid, _), srcs, _) => {
+                let callee = self.stbgr.get_full_fname(*mid, *fid);
+                match self.summaries.get(&callee) {
+                    Some(summary) => dsts
+                        .iter()
+                        .enumerate()
+                        .map(|(i, dst)| {
+                            let args: Vec<TempIndex> = summary
+                                .get_return_deps(i)
+                                .iter()
+                                .filter_map(|param| srcs.get(*param).copied())
+                                .collect();
+                            (*dst, deps_of(&args))
+                        })
+                        .collect(),
+                    // 包外函数：返回值依赖所有参数
+                    None => dsts.iter().map(|dst| (*dst, deps_of(srcs))).collect(),
+                }
+            }
+            Bytecode::Assign(_, dst, src, _) => vec![(*dst, deps_of(&[*src]))],
+            Bytecode::Call(_, dsts, _, srcs, _) => {
+                dsts.iter().map(|dst| (*dst, deps_of(srcs))).collect()
+            }
+            _ => get_defs(bytecode)
+                .into_iter()
+                .map(|dst| (dst, BTreeSet::new()))
+                .collect(),
+        };
+        for (dst, deps) in deps {
+            if deps.is_empty() {
+                state.remove(&dst);
+            } else {
+                state.insert(dst, deps);
+            }
+        }
+    }
+}
+
+/// Summary of the function at `idx` given the summaries computed so far for its callees.
+fn summarize(
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    summaries: &BTreeMap<String, FunctionSummary>,
+) -> FunctionSummary {
+    let function = &stbgr.functions[idx];
+    let param_count = utils::get_param_count(idx, stbgr);
+    let analysis = ParamDependency {
+        stbgr,
+        summaries,
+        param_count,
+    };
+    let deps = match dataflow::solve(&analysis, function) {
+        Some(deps) => deps,
+        None => return FunctionSummary::conservative(idx, stbgr),
+    };
+    let roots = utils::get_ref_roots(function, param_count);
+    let mut summary = FunctionSummary::default();
+    // 只看可达的指令
+    for (offset, state) in deps.before.iter() {
+        match &function.code[*offset as usize] {
+            Bytecode::Ret(_, srcs) => {
+                summary
+                    .return_deps
+                    .resize(summary.return_deps.len().max(srcs.len()), BTreeSet::new());
+                for (i, src) in srcs.iter().enumerate() {
+                    summary.return_deps[i].extend(state.get(src).into_iter().flatten());
+                }
+            }
+            Bytecode::Abort(..) => summary.aborts = true,
+            Bytecode::Call(_, _, Operation::WriteRef, srcs, _) => {
+                summary
+                    .modified_params
+                    .extend(roots.get(&srcs[0]).map(|root| root.param));
+            }
+            Bytecode::Call(_, _, oper, _, _) if may_abort(oper) => summary.aborts = true,
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) => {
+                let callee_name = stbgr.get_full_fname(*mid, *fid);
+                summary.aborts |= callee_may_abort(summaries, &callee_name);
+                let callee = summaries.get(&callee_name);
+                for (i, src) in srcs.iter().enumerate() {
+                    // 包外函数拿到 &mut 即视为会修改
+                    let modified =
+                        callee.map_or(true, |callee| callee.modified_params.contains(&i));
+                    if modified && is_mut_ref(&function.local_types[*src]) {
+                        summary
+                            .modified_params
+                            .extend(roots.get(src).map(|root| root.param));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    summary
+}
+
+/// Summaries of every function in the package keyed by `module::function`, computed
+/// bottom-up over the SCCs of the package call graph. Mutually recursive functions start
+/// from an empty summary and are recomputed until none of them changes; the summaries only
+/// grow, so this terminates.
+pub fn get_function_summaries(
+    packages: &BTreeMap<String, StacklessBytecodeGenerator>,
+    call_graph: &Graph<String, ()>,
+) -> BTreeMap<String, FunctionSummary> {
+    let mut summaries = BTreeMap::new();
+    // tarjan_scc 按逆拓扑序返回，被调函数所在的 SCC 在前
+    for scc in tarjan_scc(call_graph) {
+        let functions: Vec<(&String, &StacklessBytecodeGenerator, usize)> = scc
+            .iter()
+            .filter_map(|node| {
+                let full_name = &call_graph[*node];
+                let (stbgr, idx) = find_function(packages, full_name)?;
+                Some((full_name, stbgr, idx))
+            })
+            .collect();
+        for (full_name, _, _) in functions.iter() {
+            summaries.insert(full_name.to_string(), FunctionSummary::default());
+        }
+        loop {
+            let mut changed = false;
+            for (full_name, stbgr, idx) in functions.iter() {
+                let summary = summarize(*idx, stbgr, &summaries);
+                if summaries.get(*full_name) != Some(&summary) {
+                    summaries.insert(full_name.to_string(), summary);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::{
+        packages::Packages,
+        test_utils::{full_name, with_paths, ModuleBuilder},
+    };
+
+    fn mut_u64() -> SignatureToken {
+        SignatureToken::MutableReference(Box::new(SignatureToken::U64))
+    }
+
+    fn summary<'a>(packages: &'a Packages, fname: &str) -> &'a FunctionSummary {
+        packages.get_summary(&full_name(packages, fname)).unwrap()
+    }
+
+    #[test]
+    fn test_function_summaries() {
+        let mut builder = ModuleBuilder::new("m");
+        let u64_ty = || SignatureToken::U64;
+        let tx_context = builder.module_handle(2, "tx_context");
+        let ctx = builder.struct_handle(tx_context, "TxContext", AbilitySet::EMPTY);
+        let ctx_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(ctx)));
+        let first = builder.declare("first", vec![u64_ty(), u64_ty()], vec![u64_ty()]);
+        let set = builder.declare("set", vec![mut_u64(), u64_ty()], vec![]);
+        let set_first = builder.declare("set_first", vec![mut_u64(), mut_u64()], vec![]);
+        let even = builder.declare("even", vec![u64_ty()], vec![u64_ty()]);
+        let odd = builder.declare("odd", vec![u64_ty()], vec![u64_ty()]);
+        let sender = builder.declare(
+            "sender",
+            vec![ctx_ref.clone()],
+            vec![SignatureToken::Address],
+        );
+        let tx_sender = builder.function_handle(
+            tx_context,
+            "sender",
+            vec![ctx_ref],
+            vec![SignatureToken::Address],
+        );
+
+        // first(x, y) = x
+        builder.define(
+            first,
+            Visibility::Public,
+            vec![],
+            vec![MoveBytecode::MoveLoc(0), MoveBytecode::Ret],
+        );
+        // set(r, v) { *r = v }
+        builder.define(
+            set,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::WriteRef,
+                MoveBytecode::Ret,
+            ],
+        );
+        // set_first(a, b) { set(a, 1) }
+        builder.define(
+            set_first,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Call(set),
+                MoveBytecode::Ret,
+            ],
+        );
+        // even(n) { if (n == 0) n else odd(n - 1) }
+        builder.define(
+            even,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::CopyLoc(0),
+                MoveBytecode::LdU64(0),
+                MoveBytecode::Eq,
+                MoveBytecode::BrFalse(6),
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Ret,
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::LdU64(1),
+                MoveBytecode::Sub,
+                MoveBytecode::Call(odd),
+                MoveBytecode::Ret,
+            ],
+        );
+        // odd(n) { even(n) }
+        builder.define(
+            odd,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(even),
+                MoveBytecode::Ret,
+            ],
+        );
+        // sender(ctx) = tx_context::sender(ctx)
+        builder.define(
+            sender,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(tx_sender),
+                MoveBytecode::Ret,
+            ],
+        );
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+
+        let first = summary(&packages, "first");
+        assert_eq!(first.return_deps, vec![BTreeSet::from([0])]);
+        assert!(first.modified_params.is_empty());
+        assert!(!first.aborts);
+
+        assert_eq!(
+            summary(&packages, "set").modified_params,
+            BTreeSet::from([0])
+        );
+        // 只有传给 set 的 &mut 参数被修改
+        let set_first = summary(&packages, "set_first");
+        assert_eq!(set_first.modified_params, BTreeSet::from([0]));
+        assert!(set_first.return_deps.is_empty());
+
+        // even 与 odd 相互递归，在同一个 SCC 中迭代到不动点
+        for fname in ["even", "odd"] {
+            let summary = summary(&packages, fname);
+            assert_eq!(summary.return_deps, vec![BTreeSet::from([0])]);
+            assert!(summary.aborts, "{} may underflow through even", fname);
+        }
+
+        // 包外函数视为会 abort，返回值依赖所有实参
+        let sender = summary(&packages, "sender");
+        assert_eq!(sender.return_deps, vec![BTreeSet::from([0])]);
+        assert!(sender.aborts);
+    }
+}
@@ -0,0 +1,536 @@
+// 污点分析：public/entry 函数的参数与 tx_context::sender 等来源流向敏感操作
+use std::collections::{BTreeMap, BTreeSet};
+
+use move_binary_format::file_format::{CodeOffset, Visibility};
+use move_model::{
+    ast::TempIndex,
+    model::{FunId, ModuleId},
+    ty::Type,
+};
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
+use petgraph::algo::tarjan_scc;
+use serde::Deserialize;
+
+use super::{
+    coin_flow,
+    dataflow::{self, get_defs, DataflowAnalysis, Direction},
+    generate_bytecode::{FunctionInfo, StacklessBytecodeGenerator},
+    packages::Packages,
+    sui,
+    utils::{self, RefRoot},
+};
+
+/// Extra sources and sinks from the `[taint]` table of MoveScanner.toml, on top of the
+/// built-in ones. Functions are full names, `address::module::function`, framework ones
+/// included, e.g. `0x2::coin::split`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TaintConfig {
+    pub sources: Vec<String>, // 返回值视为污点的函数
+    pub sinks: Vec<SinkConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SinkConfig {
+    pub function: String,
+    pub arg: usize, // 参数位置，从 0 开始
+}
+
+// 返回值由交易发送者决定的函数
+const SOURCE_FUNCTIONS: [&str; 1] = [sui::TX_CONTEXT_SENDER];
+
+// (函数, 参数位置, sink 描述)
+const SINK_FUNCTIONS: [(&str, usize, &str); 9] = [
+    (coin_flow::TRANSFER_FUNCTIONS[0], 1, "transfer recipient"),
+    (coin_flow::TRANSFER_FUNCTIONS[1], 1, "transfer recipient"),
+    ("0x2::balance::split", 1, "balance::split amount"),
+    ("0x2::coin::split", 1, "coin::split amount"),
+    ("0x2::coin::take", 1, "coin::take amount"),
+    ("0x1::vector::borrow", 1, "vector index"),
+    ("0x1::vector::borrow_mut", 1, "vector index"),
+    ("0x1::vector::remove", 1, "vector index"),
+    ("0x1::vector::swap_remove", 1, "vector index"),
+];
+
+impl TaintConfig {
+    fn get_source(
+        &self,
+        stbgr: &StacklessBytecodeGenerator,
+        mid: ModuleId,
+        fid: FunId,
+    ) -> Option<String> {
+        let names = get_callee_names(stbgr, mid, fid);
+        SOURCE_FUNCTIONS
+            .iter()
+            .map(|source| source.to_string())
+            .chain(self.sources.iter().cloned())
+            .find(|source| names.contains(source))
+    }
+
+    /// Arguments of a call that are sinks, with the sink description.
+    fn get_sinks(
+        &self,
+        stbgr: &StacklessBytecodeGenerator,
+        mid: ModuleId,
+        fid: FunId,
+    ) -> Vec<(usize, String)> {
+        let names = get_callee_names(stbgr, mid, fid);
+        let builtin = SINK_FUNCTIONS
+            .iter()
+            .filter(|(function, _, _)| names.iter().any(|name| name == *function))
+            .map(|(_, arg, kind)| (*arg, kind.to_string()));
+        let configured = self
+            .sinks
+            .iter()
+            .filter(|sink| names.contains(&sink.function))
+            .map(|sink| (sink.arg, format!("{} arg {}", sink.function, sink.arg)));
+        builtin.chain(configured).collect()
+    }
+}
+
+/// Full name of a callee, and its `0x2::module::function` name when it is in the framework.
+fn get_callee_names(stbgr: &StacklessBytecodeGenerator, mid: ModuleId, fid: FunId) -> Vec<String> {
+    let mut names = vec![stbgr.get_full_fname(mid, fid)];
+    names.extend(sui::get_framework_fname(stbgr, mid, fid));
+    names
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TaintSource {
+    Param(TempIndex),
+    Call(String), // 来源函数，例如 0x2::tx_context::sender
+}
+
+/// A sensitive operand: the instruction at `code_offset` of `function` (`module::function`).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SinkSite {
+    pub function: String,
+    pub code_offset: CodeOffset,
+    pub kind: String, // 例如 transfer recipient、denominator、Pool.fee write
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TaintSummary {
+    // 每个返回值携带的来源函数
+    pub returns: Vec<BTreeSet<String>>,
+    // (来源, sink) -> 从本函数到 sink 所在函数经过的被调函数，不含本函数
+    pub flows: BTreeMap<(TaintSource, SinkSite), Vec<String>>,
+}
+
+/// A flow from a parameter of a public/entry function, or from a source called on the
+/// way, to a sink. `path` runs from `entry` to the function containing the sink.
+#[derive(Debug, Clone)]
+pub struct TaintFlow {
+    pub entry: String,
+    pub source: TaintSource,
+    pub sink: SinkSite,
+    pub path: Vec<String>,
+}
+
+type Labels = BTreeMap<TempIndex, BTreeSet<TaintSource>>;
+
+/// Forward analysis of the taint sources of every temp. Package callees are looked through
+/// with their function summaries, other callees taint their results with all arguments.
+struct TaintPropagation<'a, 'b> {
+    stbgr: &'b StacklessBytecodeGenerator<'a>,
+    packages: &'b Packages<'a>,
+    config: &'b TaintConfig,
+    summaries: &'b BTreeMap<String, TaintSummary>,
+    param_count: usize,
+}
+
+impl<'a, 'b> DataflowAnalysis for TaintPropagation<'a, 'b> {
+    type State = Labels;
+    const DIRECTION: Direction = Direction::Forward;
+
+    fn boundary_state(&self, _function: &FunctionInfo) -> Self::State {
+        (0..self.param_count)
+            .map(|param| (param, BTreeSet::from([TaintSource::Param(param)])))
+            .collect()
+    }
+
+    fn bottom(&self, _function: &FunctionInfo) -> Self::State {
+        BTreeMap::new()
+    }
+
+    fn transfer(&self, _offset: CodeOffset, bytecode: &Bytecode, state: &mut Self::State) {
+        let labels_of = |srcs: &[TempIndex]| -> BTreeSet<TaintSource> {
+            srcs.iter()
+                .filter_map(|src| state.get(src))
+                .flatten()
+                .cloned()
+                .collect()
+        };
+        let labels: Vec<(TempIndex, BTreeSet<TaintSource>)> = match bytecode {
+            Bytecode::Call(_, dsts, Operation::Function(mid, fid, _), srcs, _) => {
+                let callee = self.stbgr.get_full_fname(*mid, *fid);
+                let source = self.config.get_source(self.stbgr, *mid, *fid);
+                match (
+                    source,
+                    self.packages.get_summary(&callee),
+                    self.summaries.get(&callee),
+                ) {
+                    (Some(source), _, _) => dsts
+                        .iter()
+                        .map(|dst| (*dst, BTreeSet::from([TaintSource::Call(source.clone())])))
+                        .collect(),
+                    (None, Some(summary), Some(taint)) => dsts
+                        .iter()
+                        .enumerate()
+                        .map(|(i, dst)| {
+                            let args: Vec<TempIndex> = summary
+                                .get_return_deps(i)
+                                .iter()
+                                .filter_map(|param| srcs.get(*param).copied())
+                                .collect();
+                            let mut labels = labels_of(&args);
+                            let returned = taint.returns.get(i).into_iter().flatten();
+                            labels.extend(returned.map(|source| TaintSource::Call(source.clone())));
+                            (*dst, labels)
+                        })
+                        .collect(),
+                    _ => dsts.iter().map(|dst| (*dst, labels_of(srcs))).collect(),
+                }
+            }
+            Bytecode::Assign(_, dst, src, _) => vec![(*dst, labels_of(&[*src]))],
+            Bytecode::Call(_, dsts, _, srcs, _) => {
+                dsts.iter().map(|dst| (*dst, labels_of(srcs))).collect()
+            }
+            _ => get_defs(bytecode)
+                .into_iter()
+                .map(|dst| (dst, BTreeSet::new()))
+                .collect(),
+        };
+        for (dst, labels) in labels {
+            if labels.is_empty() {
+                state.remove(&dst);
+            } else {
+                state.insert(dst, labels);
+            }
+        }
+    }
+}
+
+// 同一 (来源, sink) 只保留最短的路径，保证不动点迭代收敛
+fn insert_flow(
+    flows: &mut BTreeMap<(TaintSource, SinkSite), Vec<String>>,
+    key: (TaintSource, SinkSite),
+    path: Vec<String>,
+) {
+    match flows.get(&key) {
+        Some(existing) if (existing.len(), existing) <= (path.len(), &path) => {}
+        _ => {
+            flows.insert(key, path);
+        }
+    }
+}
+
+/// Taint summary of `full_name` given the summaries computed so far for its callees.
+fn summarize(
+    full_name: &str,
+    idx: usize,
+    stbgr: &StacklessBytecodeGenerator,
+    packages: &Packages,
+    config: &TaintConfig,
+    summaries: &BTreeMap<String, TaintSummary>,
+) -> TaintSummary {
+    let function = &stbgr.functions[idx];
+    let param_count = utils::get_param_count(idx, stbgr);
+    let analysis = TaintPropagation {
+        stbgr,
+        packages,
+        config,
+        summaries,
+        param_count,
+    };
+    let mut summary = TaintSummary::default();
+    let labels = match dataflow::solve(&analysis, function) {
+        Some(labels) => labels,
+        None => return summary,
+    };
+    let roots = utils::get_ref_roots(function, param_count);
+    for (offset, state) in labels.before.iter() {
+        let labels_of = |temp: &TempIndex| state.get(temp).cloned().unwrap_or_default();
+        let mut sinks: Vec<(TempIndex, String)> = vec![];
+        match &function.code[*offset as usize] {
+            Bytecode::Ret(_, srcs) => {
+                summary
+                    .returns
+                    .resize(summary.returns.len().max(srcs.len()), BTreeSet::new());
+                for (i, src) in srcs.iter().enumerate() {
+                    for source in labels_of(src) {
+                        if let TaintSource::Call(source) = source {
+                            summary.returns[i].insert(source);
+                        }
+                    }
+                }
+            }
+            Bytecode::Call(_, _, Operation::Div | Operation::Mod, srcs, _) => {
+                sinks.push((srcs[1], "denominator".to_string()));
+            }
+            Bytecode::Call(_, _, Operation::WriteRef, srcs, _) => {
+                if let Some(RefRoot {
+                    field: Some((mid, sid, field)),
+                    ..
+                }) = roots.get(&srcs[0])
+                {
+                    let struct_name = stbgr.get_struct_full_name(*mid, *sid);
+                    let kind = format!(
+                        "{}.{} write",
+                        struct_name.rsplit("::").next().unwrap(),
+                        stbgr.get_field_name(*sid, *field)
+                    );
+                    sinks.push((srcs[1], kind));
+                }
+            }
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) => {
+                for (arg, kind) in config.get_sinks(stbgr, *mid, *fid) {
+                    sinks.extend(srcs.get(arg).map(|src| (*src, kind)));
+                }
+                // 被调函数内部的 flow 映射到本函数的实参上，跳过递归形成的环
+                let callee = stbgr.get_full_fname(*mid, *fid);
+                let flows = summaries
+                    .get(&callee)
+                    .filter(|_| callee != full_name)
+                    .map(|taint| &taint.flows);
+                for ((source, sink), path) in flows.into_iter().flatten() {
+                    if path.iter().any(|f| f == full_name) {
+                        continue;
+                    }
+                    let sources = match source {
+                        TaintSource::Param(param) => {
+                            srcs.get(*param).map(labels_of).unwrap_or_default()
+                        }
+                        TaintSource::Call(_) => BTreeSet::from([source.clone()]),
+                    };
+                    let mut path = path.clone();
+                    path.insert(0, callee.clone());
+                    for source in sources {
+                        insert_flow(&mut summary.flows, (source, sink.clone()), path.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+        for (temp, kind) in sinks {
+            let sink = SinkSite {
+                function: full_name.to_string(),
+                code_offset: *offset,
+                kind,
+            };
+            for source in labels_of(&temp) {
+                insert_flow(&mut summary.flows, (source, sink.clone()), vec![]);
+            }
+        }
+    }
+    summary
+}
+
+/// Taint summaries of every package function, bottom-up over the SCCs of the call graph
+/// like the function summaries, iterating recursive functions to a fixed point.
+pub fn get_taint_summaries(
+    packages: &Packages,
+    config: &TaintConfig,
+) -> BTreeMap<String, TaintSummary> {
+    let call_graph = &packages.call_graph;
+    let mut summaries = BTreeMap::new();
+    for scc in tarjan_scc(call_graph) {
+        let functions: Vec<(&String, &StacklessBytecodeGenerator, usize)> = scc
+            .iter()
+            .filter_map(|node| {
+                let full_name = &call_graph[*node];
+                let (stbgr, idx) = packages.get_function_by_full_name(full_name)?;
+                Some((full_name, stbgr, idx))
+            })
+            .collect();
+        for (full_name, _, _) in functions.iter() {
+            summaries.insert(full_name.to_string(), TaintSummary::default());
+        }
+        loop {
+            let mut changed = false;
+            for (full_name, stbgr, idx) in functions.iter() {
+                let summary = summarize(full_name, *idx, stbgr, packages, config, &summaries);
+                if summaries.get(*full_name) != Some(&summary) {
+                    summaries.insert(full_name.to_string(), summary);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+    summaries
+}
+
+/// Flows into sinks from the value parameters of public and entry functions, and from
+/// source calls. Reference parameters are objects passed in, not values the caller picks.
+pub fn get_taint_flows(packages: &Packages, config: &TaintConfig) -> Vec<TaintFlow> {
+    let mut flows = vec![];
+    for (full_name, summary) in get_taint_summaries(packages, config) {
+        let (stbgr, idx) = match packages.get_function_by_full_name(&full_name) {
+            Some(function) => function,
+            None => continue,
+        };
+        let is_entry_point =
+            utils::get_visibility(idx, stbgr) == Visibility::Public || utils::is_entry(idx, stbgr);
+        if !is_entry_point || utils::is_test_only(idx, stbgr) {
+            continue;
+        }
+        let local_types = &stbgr.functions[idx].local_types;
+        for ((source, sink), path) in summary.flows {
+            if let TaintSource::Param(param) = source {
+                if matches!(local_types[param], Type::Reference(..)) {
+                    continue;
+                }
+            }
+            let mut path = path;
+            path.insert(0, full_name.clone());
+            flows.push(TaintFlow {
+                entry: full_name.clone(),
+                source,
+                sink,
+                path,
+            });
+        }
+    }
+    flows
+}
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{
+        AbilitySet, Bytecode as MoveBytecode, SignatureToken, Visibility,
+    };
+
+    use super::*;
+    use crate::move_ir::test_utils::{full_name, with_paths, ModuleBuilder};
+
+    #[test]
+    fn test_taint_flows() {
+        let mut builder = ModuleBuilder::new("m");
+        let balance = builder.module_handle(2, "balance");
+        let tx_context = builder.module_handle(2, "tx_context");
+        let transfer = builder.module_handle(2, "transfer");
+        let balance_ty =
+            SignatureToken::Struct(builder.struct_handle(balance, "Balance", AbilitySet::EMPTY));
+        let balance_mut = SignatureToken::MutableReference(Box::new(balance_ty.clone()));
+        let ctx = builder.struct_handle(tx_context, "TxContext", AbilitySet::EMPTY);
+        let ctx_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(ctx)));
+        let withdraw = builder.declare(
+            "withdraw",
+            vec![balance_mut.clone(), SignatureToken::U64],
+            vec![balance_ty.clone()],
+        );
+        let withdraw_inner = builder.declare(
+            "withdraw_inner",
+            vec![balance_mut.clone(), SignatureToken::U64],
+            vec![balance_ty.clone()],
+        );
+        let recipient = builder.declare(
+            "recipient",
+            vec![ctx_ref.clone()],
+            vec![SignatureToken::Address],
+        );
+        let pay = builder.declare("pay", vec![balance_ty.clone(), ctx_ref.clone()], vec![]);
+        let split = builder.function_handle(
+            balance,
+            "split",
+            vec![balance_mut, SignatureToken::U64],
+            vec![balance_ty.clone()],
+        );
+        let sender = builder.function_handle(
+            tx_context,
+            "sender",
+            vec![ctx_ref],
+            vec![SignatureToken::Address],
+        );
+        let public_transfer = builder.function_handle(
+            transfer,
+            "public_transfer",
+            vec![balance_ty, SignatureToken::Address],
+            vec![],
+        );
+
+        // public withdraw(b, amount) { withdraw_inner(b, amount) }
+        builder.define(
+            withdraw,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(withdraw_inner),
+                MoveBytecode::Ret,
+            ],
+        );
+        // withdraw_inner(b, amount) { balance::split(b, amount) }
+        builder.define(
+            withdraw_inner,
+            Visibility::Private,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(split),
+                MoveBytecode::Ret,
+            ],
+        );
+        // recipient(ctx) { tx_context::sender(ctx) }
+        builder.define(
+            recipient,
+            Visibility::Private,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::Call(sender),
+                MoveBytecode::Ret,
+            ],
+        );
+        // public pay(b, ctx) { transfer::public_transfer(b, recipient(ctx)) }
+        builder.define(
+            pay,
+            Visibility::Public,
+            vec![],
+            vec![
+                MoveBytecode::MoveLoc(0),
+                MoveBytecode::MoveLoc(1),
+                MoveBytecode::Call(recipient),
+                MoveBytecode::Call(public_transfer),
+                MoveBytecode::Ret,
+            ],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let flows = get_taint_flows(&packages, &TaintConfig::default());
+        let (withdraw, withdraw_inner, pay) = (
+            full_name(&packages, "withdraw"),
+            full_name(&packages, "withdraw_inner"),
+            full_name(&packages, "pay"),
+        );
+
+        // 参数经包内调用到达 balance::split 的金额；引用参数 b 不是污点来源
+        let from_withdraw: Vec<&TaintFlow> =
+            flows.iter().filter(|flow| flow.entry == withdraw).collect();
+        assert_eq!(from_withdraw.len(), 1);
+        assert_eq!(from_withdraw[0].source, TaintSource::Param(1));
+        assert_eq!(from_withdraw[0].sink.function, withdraw_inner);
+        assert_eq!(from_withdraw[0].sink.kind, "balance::split amount");
+        assert_eq!(
+            from_withdraw[0].path,
+            vec![withdraw.clone(), withdraw_inner]
+        );
+
+        // 包内函数返回的 tx_context::sender 到达转账接收者
+        let from_pay: Vec<&TaintFlow> = flows.iter().filter(|flow| flow.entry == pay).collect();
+        assert_eq!(from_pay.len(), 1);
+        assert_eq!(
+            from_pay[0].source,
+            TaintSource::Call(sui::TX_CONTEXT_SENDER.to_string())
+        );
+        assert_eq!(from_pay[0].sink.function, pay);
+        assert_eq!(from_pay[0].sink.kind, "transfer recipient");
+        assert_eq!(from_pay[0].path, vec![pay.clone()]);
+    }
+}
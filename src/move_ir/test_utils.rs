@@ -0,0 +1,534 @@
+// 测试用：直接以 file_format 构造 CompiledModule，不依赖 Move 编译器
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{
+        empty_module, Ability, AbilitySet, AddressIdentifierIndex, Bytecode, CodeUnit,
+        CompiledModule, Constant, ConstantPoolIndex, FieldDefinition, FieldHandle,
+        FieldHandleIndex, FunctionDefinition, FunctionHandle, FunctionHandleIndex,
+        FunctionInstantiation, FunctionInstantiationIndex, IdentifierIndex, ModuleHandle,
+        ModuleHandleIndex, Signature, SignatureIndex, SignatureToken, StructDefinition,
+        StructDefinitionIndex, StructFieldInformation, StructHandle, StructHandleIndex,
+        TypeSignature, Visibility,
+    },
+};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier};
+
+use super::packages::Packages;
+use crate::scanner::result::DetectContent;
+
+/// A module at `0x0` whose functions are written directly in Move bytecode. Functions of
+/// the module itself must be declared before any other function handle, so that a
+/// definition index is also the index of its handle, as the generator assumes.
+pub(crate) struct ModuleBuilder {
+    module: CompiledModule,
+}
+
+impl ModuleBuilder {
+    pub(crate) fn new(name: &str) -> Self {
+        let mut module = empty_module();
+        module.identifiers[0] = Identifier::new(name).unwrap();
+        Self { module }
+    }
+
+    fn identifier(&mut self, name: &str) -> IdentifierIndex {
+        let identifiers = &mut self.module.identifiers;
+        let idx = match identifiers.iter().position(|ident| ident.as_str() == name) {
+            Some(idx) => idx,
+            None => {
+                identifiers.push(Identifier::new(name).unwrap());
+                identifiers.len() - 1
+            }
+        };
+        IdentifierIndex::new(idx as u16)
+    }
+
+    fn address(&mut self, address: AccountAddress) -> AddressIdentifierIndex {
+        let addresses = &mut self.module.address_identifiers;
+        let idx = match addresses.iter().position(|addr| *addr == address) {
+            Some(idx) => idx,
+            None => {
+                addresses.push(address);
+                addresses.len() - 1
+            }
+        };
+        AddressIdentifierIndex::new(idx as u16)
+    }
+
+    pub(crate) fn signature(&mut self, tokens: Vec<SignatureToken>) -> SignatureIndex {
+        let signature = Signature(tokens);
+        let signatures = &mut self.module.signatures;
+        let idx = match signatures.iter().position(|sig| *sig == signature) {
+            Some(idx) => idx,
+            None => {
+                signatures.push(signature);
+                signatures.len() - 1
+            }
+        };
+        SignatureIndex::new(idx as u16)
+    }
+
+    /// Handle of the module `address::name`, e.g. `(0x2, "balance")`.
+    pub(crate) fn module_handle(&mut self, address: u8, name: &str) -> ModuleHandleIndex {
+        let handle = ModuleHandle {
+            address: self
+                .address(AccountAddress::from_hex_literal(&format!("0x{:x}", address)).unwrap()),
+            name: self.identifier(name),
+        };
+        let handles = &mut self.module.module_handles;
+        let idx = match handles.iter().position(|h| *h == handle) {
+            Some(idx) => idx,
+            None => {
+                handles.push(handle);
+                handles.len() - 1
+            }
+        };
+        ModuleHandleIndex::new(idx as u16)
+    }
+
+    /// A struct of `module` without type parameters, e.g. `balance::Balance`.
+    pub(crate) fn struct_handle(
+        &mut self,
+        module: ModuleHandleIndex,
+        name: &str,
+        abilities: AbilitySet,
+    ) -> StructHandleIndex {
+        let handle = StructHandle {
+            module,
+            name: self.identifier(name),
+            abilities,
+            type_parameters: vec![],
+        };
+        let handles = &mut self.module.struct_handles;
+        let idx = match handles.iter().position(|h| *h == handle) {
+            Some(idx) => idx,
+            None => {
+                handles.push(handle);
+                handles.len() - 1
+            }
+        };
+        StructHandleIndex::new(idx as u16)
+    }
+
+    /// A struct of this module without type parameters, e.g.
+    /// `Pool has key { id: UID, balance: u64 }`.
+    pub(crate) fn define_struct(
+        &mut self,
+        name: &str,
+        abilities: AbilitySet,
+        fields: Vec<(&str, SignatureToken)>,
+    ) -> (StructHandleIndex, StructDefinitionIndex) {
+        let handle = self.struct_handle(ModuleHandleIndex::new(0), name, abilities);
+        let fields = fields
+            .into_iter()
+            .map(|(name, token)| FieldDefinition {
+                name: self.identifier(name),
+                signature: TypeSignature(token),
+            })
+            .collect();
+        self.module.struct_defs.push(StructDefinition {
+            struct_handle: handle,
+            field_information: StructFieldInformation::Declared(fields),
+        });
+        let def = StructDefinitionIndex::new((self.module.struct_defs.len() - 1) as u16);
+        (handle, def)
+    }
+
+    /// The `field`-th field of a struct of this module, for `ImmBorrowField`/`MutBorrowField`.
+    pub(crate) fn field_handle(
+        &mut self,
+        owner: StructDefinitionIndex,
+        field: u16,
+    ) -> FieldHandleIndex {
+        self.module.field_handles.push(FieldHandle { owner, field });
+        FieldHandleIndex::new((self.module.field_handles.len() - 1) as u16)
+    }
+
+    /// A function of `module` without type parameters.
+    pub(crate) fn function_handle(
+        &mut self,
+        module: ModuleHandleIndex,
+        name: &str,
+        parameters: Vec<SignatureToken>,
+        returns: Vec<SignatureToken>,
+    ) -> FunctionHandleIndex {
+        self.generic_function_handle(module, name, vec![], parameters, returns)
+    }
+
+    /// A function of `module` with type parameters, referred to in `parameters` and
+    /// `returns` as `SignatureToken::TypeParameter`, e.g. `transfer::share_object<T: key>`.
+    pub(crate) fn generic_function_handle(
+        &mut self,
+        module: ModuleHandleIndex,
+        name: &str,
+        type_parameters: Vec<AbilitySet>,
+        parameters: Vec<SignatureToken>,
+        returns: Vec<SignatureToken>,
+    ) -> FunctionHandleIndex {
+        let handle = FunctionHandle {
+            module,
+            name: self.identifier(name),
+            parameters: self.signature(parameters),
+            return_: self.signature(returns),
+            type_parameters,
+        };
+        self.module.function_handles.push(handle);
+        FunctionHandleIndex::new((self.module.function_handles.len() - 1) as u16)
+    }
+
+    /// `handle<type_arguments>`, for `CallGeneric`.
+    pub(crate) fn instantiate(
+        &mut self,
+        handle: FunctionHandleIndex,
+        type_arguments: Vec<SignatureToken>,
+    ) -> FunctionInstantiationIndex {
+        let type_parameters = self.signature(type_arguments);
+        self.module
+            .function_instantiations
+            .push(FunctionInstantiation {
+                handle,
+                type_parameters,
+            });
+        FunctionInstantiationIndex::new((self.module.function_instantiations.len() - 1) as u16)
+    }
+
+    /// A function of this module, to be defined with `define`.
+    pub(crate) fn declare(
+        &mut self,
+        name: &str,
+        parameters: Vec<SignatureToken>,
+        returns: Vec<SignatureToken>,
+    ) -> FunctionHandleIndex {
+        assert_eq!(
+            self.module.function_handles.len(),
+            self.module.function_defs.len(),
+            "declare the functions of the module before other function handles"
+        );
+        let handle = self.function_handle(ModuleHandleIndex::new(0), name, parameters, returns);
+        self.module.function_defs.push(FunctionDefinition {
+            function: handle,
+            visibility: Visibility::Public,
+            is_entry: false,
+            acquires_global_resources: vec![],
+            code: None,
+        });
+        handle
+    }
+
+    /// Body of a declared function. `locals` are the locals after the parameters.
+    pub(crate) fn define(
+        &mut self,
+        function: FunctionHandleIndex,
+        visibility: Visibility,
+        locals: Vec<SignatureToken>,
+        code: Vec<Bytecode>,
+    ) {
+        let parameters = self.module.function_handles[function.0 as usize].parameters;
+        // 生成器按 locals 签名取所有局部变量（含参数）的类型
+        let mut tokens = self.module.signatures[parameters.0 as usize].0.clone();
+        tokens.extend(locals);
+        let locals = self.signature(tokens);
+        let def = &mut self.module.function_defs[function.0 as usize];
+        def.visibility = visibility;
+        def.code = Some(CodeUnit { locals, code });
+    }
+
+    /// Mark a declared function `entry`.
+    pub(crate) fn entry(&mut self, function: FunctionHandleIndex) {
+        self.module.function_defs[function.0 as usize].is_entry = true;
+    }
+
+    pub(crate) fn u64_constant(&mut self, value: u64) -> ConstantPoolIndex {
+        self.module.constant_pool.push(Constant {
+            type_: SignatureToken::U64,
+            data: value.to_le_bytes().to_vec(),
+        });
+        ConstantPoolIndex::new((self.module.constant_pool.len() - 1) as u16)
+    }
+
+    /// `&mut TxContext` and `&TxContext`, e.g. for the parameters of declared functions.
+    pub(crate) fn tx_context(&mut self) -> (SignatureToken, SignatureToken) {
+        let module = self.module_handle(2, "tx_context");
+        let ctx = self.struct_handle(module, "TxContext", AbilitySet::EMPTY | Ability::Drop);
+        let ctx = SignatureToken::Struct(ctx);
+        (
+            SignatureToken::MutableReference(Box::new(ctx.clone())),
+            SignatureToken::Reference(Box::new(ctx)),
+        )
+    }
+
+    /// Handles of the Sui framework items most fixtures use. Like any other handle, only
+    /// after the functions of the module are declared.
+    pub(crate) fn sui_handles(&mut self) -> SuiHandles {
+        let object = self.module_handle(2, "object");
+        let tx_context = self.module_handle(2, "tx_context");
+        let transfer = self.module_handle(2, "transfer");
+        let uid = self.struct_handle(object, "UID", AbilitySet::EMPTY | Ability::Store);
+        let uid = SignatureToken::Struct(uid);
+        let (ctx_mut, ctx_ref) = self.tx_context();
+        let object_new =
+            self.function_handle(object, "new", vec![ctx_mut.clone()], vec![uid.clone()]);
+        let sender = self.function_handle(
+            tx_context,
+            "sender",
+            vec![ctx_ref.clone()],
+            vec![SignatureToken::Address],
+        );
+        let key = AbilitySet::EMPTY | Ability::Key;
+        let share_object = self.generic_function_handle(
+            transfer,
+            "share_object",
+            vec![key],
+            vec![SignatureToken::TypeParameter(0)],
+            vec![],
+        );
+        let transfer = self.generic_function_handle(
+            transfer,
+            "transfer",
+            vec![key],
+            vec![SignatureToken::TypeParameter(0), SignatureToken::Address],
+            vec![],
+        );
+        SuiHandles {
+            uid,
+            ctx_mut,
+            ctx_ref,
+            object_new,
+            sender,
+            share_object,
+            transfer,
+        }
+    }
+
+    pub(crate) fn build(self) -> CompiledModule {
+        self.module
+    }
+}
+
+/// `0x2::object::UID`, `0x2::tx_context::TxContext` and the functions creating, sharing
+/// and transferring objects.
+pub(crate) struct SuiHandles {
+    pub uid: SignatureToken,
+    pub ctx_mut: SignatureToken,
+    pub ctx_ref: SignatureToken,
+    pub object_new: FunctionHandleIndex,
+    pub sender: FunctionHandleIndex,
+    pub share_object: FunctionHandleIndex,
+    pub transfer: FunctionHandleIndex,
+}
+
+/// `pool::Pool has key { id, admin: address, balance: u64 }`, shared in `init`, and the
+/// `pool::AdminCap` `init` sends to the publisher, with functions writing `balance`:
+/// - `withdraw(pool, amount)` without any check;
+/// - `admin_set(cap: &AdminCap, pool, amount)`;
+/// - `checked_set(pool, amount, ctx)` after `assert!(sender(ctx) == pool.admin)`;
+/// - `unchecked_set(pool, amount, ctx)`, comparing the sender but ignoring the result.
+pub(crate) fn shared_pool_module() -> CompiledModule {
+    let mut builder = ModuleBuilder::new("pool");
+    let key = AbilitySet::EMPTY | Ability::Key;
+    let object = builder.module_handle(2, "object");
+    let uid = SignatureToken::Struct(builder.struct_handle(
+        object,
+        "UID",
+        AbilitySet::EMPTY | Ability::Store,
+    ));
+    let (pool, pool_def) = builder.define_struct(
+        "Pool",
+        key,
+        vec![
+            ("id", uid.clone()),
+            ("admin", SignatureToken::Address),
+            ("balance", SignatureToken::U64),
+        ],
+    );
+    let (cap, cap_def) = builder.define_struct("AdminCap", key, vec![("id", uid)]);
+    let pool_mut = SignatureToken::MutableReference(Box::new(SignatureToken::Struct(pool)));
+    let cap_ref = SignatureToken::Reference(Box::new(SignatureToken::Struct(cap)));
+    let (ctx_mut, ctx_ref) = builder.tx_context();
+    let init = builder.declare("init", vec![ctx_mut], vec![]);
+    let withdraw = builder.declare(
+        "withdraw",
+        vec![pool_mut.clone(), SignatureToken::U64],
+        vec![],
+    );
+    let admin_set = builder.declare(
+        "admin_set",
+        vec![cap_ref, pool_mut.clone(), SignatureToken::U64],
+        vec![],
+    );
+    let checked_set = builder.declare(
+        "checked_set",
+        vec![pool_mut.clone(), SignatureToken::U64, ctx_ref.clone()],
+        vec![],
+    );
+    let unchecked_set = builder.declare(
+        "unchecked_set",
+        vec![pool_mut, SignatureToken::U64, ctx_ref],
+        vec![],
+    );
+    let sui = builder.sui_handles();
+    let admin = builder.field_handle(pool_def, 1);
+    let balance = builder.field_handle(pool_def, 2);
+    let share_pool = builder.instantiate(sui.share_object, vec![SignatureToken::Struct(pool)]);
+    let transfer_cap = builder.instantiate(sui.transfer, vec![SignatureToken::Struct(cap)]);
+
+    // init(ctx) {
+    //     transfer::transfer(AdminCap { id: object::new(ctx) }, sender(ctx));
+    //     transfer::share_object(Pool { id: object::new(ctx), admin: sender(ctx), balance: 0 });
+    // }
+    builder.define(
+        init,
+        Visibility::Private,
+        vec![],
+        vec![
+            Bytecode::CopyLoc(0),
+            Bytecode::Call(sui.object_new),
+            Bytecode::Pack(cap_def),
+            Bytecode::CopyLoc(0),
+            Bytecode::FreezeRef,
+            Bytecode::Call(sui.sender),
+            Bytecode::CallGeneric(transfer_cap),
+            Bytecode::CopyLoc(0),
+            Bytecode::Call(sui.object_new),
+            Bytecode::CopyLoc(0),
+            Bytecode::FreezeRef,
+            Bytecode::Call(sui.sender),
+            Bytecode::LdU64(0),
+            Bytecode::Pack(pool_def),
+            Bytecode::CallGeneric(share_pool),
+            Bytecode::Ret,
+        ],
+    );
+    // pool.balance = amount
+    let write_balance = |pool: u8, amount: u8| {
+        vec![
+            Bytecode::MoveLoc(amount),
+            Bytecode::MoveLoc(pool),
+            Bytecode::MutBorrowField(balance),
+            Bytecode::WriteRef,
+        ]
+    };
+    let mut code = write_balance(0, 1);
+    code.push(Bytecode::Ret);
+    builder.define(withdraw, Visibility::Public, vec![], code);
+    let mut code = write_balance(1, 2);
+    code.push(Bytecode::Ret);
+    builder.define(admin_set, Visibility::Public, vec![], code);
+    // sender(ctx) == pool.admin
+    let compare_sender = vec![
+        Bytecode::MoveLoc(2),
+        Bytecode::Call(sui.sender),
+        Bytecode::CopyLoc(0),
+        Bytecode::ImmBorrowField(admin),
+        Bytecode::ReadRef,
+        Bytecode::Eq,
+    ];
+    let mut code = compare_sender.clone();
+    code.extend([Bytecode::BrTrue(9), Bytecode::LdU64(0), Bytecode::Abort]);
+    code.extend(write_balance(0, 1));
+    code.push(Bytecode::Ret);
+    builder.define(checked_set, Visibility::Public, vec![], code);
+    let mut code = compare_sender;
+    code.push(Bytecode::Pop);
+    code.extend(write_balance(0, 1));
+    code.push(Bytecode::Ret);
+    builder.define(unchecked_set, Visibility::Public, vec![], code);
+    builder.build()
+}
+
+/// `m` with a live private `helper` of `api`, an uncalled `dead`, a `#[test_only] setup`
+/// and an untagged private `test_looking`, each loading its own constant but `api`.
+pub(crate) fn test_only_module() -> CompiledModule {
+    let mut builder = ModuleBuilder::new("m");
+    let api = builder.declare("api", vec![], vec![]);
+    let helper = builder.declare("helper", vec![], vec![]);
+    let dead = builder.declare("dead", vec![], vec![]);
+    let setup = builder.declare("setup", vec![], vec![]);
+    let test_looking = builder.declare("test_looking", vec![], vec![]);
+    builder.define(
+        api,
+        Visibility::Public,
+        vec![],
+        vec![Bytecode::Call(helper), Bytecode::Ret],
+    );
+    for (value, function) in [helper, dead, setup, test_looking].into_iter().enumerate() {
+        let constant = builder.u64_constant(value as u64);
+        builder.define(
+            function,
+            Visibility::Private,
+            vec![],
+            vec![Bytecode::LdConst(constant), Bytecode::Pop, Bytecode::Ret],
+        );
+    }
+    builder.build()
+}
+
+/// Source of `test_only_module`.
+pub(crate) const TEST_ONLY_SOURCE: &str = "module 0x0::m {
+    public fun api() { helper() }
+    fun helper() { 0; }
+    fun dead() { 1; }
+    #[test_only]
+    fun setup() { 2; }
+    fun test_looking() { 3; }
+}
+";
+
+/// Write `source` to a temporary file and load it for the module `mname`, as the scanner
+/// does for the modules found under `--sources`.
+pub(crate) fn load_source(packages: &mut Packages, mname: &str, source: &str) {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "move_scanner_{}_{}.move",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::SeqCst)
+    ));
+    std::fs::write(&path, source).unwrap();
+    packages.load_source_code(mname, &path);
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Modules as read from a package without source maps.
+pub(crate) fn with_paths(modules: Vec<CompiledModule>) -> Vec<(PathBuf, CompiledModule)> {
+    modules
+        .into_iter()
+        .map(|module| {
+            let path = PathBuf::from(format!(
+                "build/test/bytecode_modules/{}.mv",
+                module.self_id().name()
+            ));
+            (path, module)
+        })
+        .collect()
+}
+
+/// `module::function` of the package function named `fname`.
+pub(crate) fn full_name(packages: &Packages, fname: &str) -> String {
+    packages
+        .func_to_node
+        .keys()
+        .find(|name| name.ends_with(&format!("::{}", fname)))
+        .unwrap_or_else(|| panic!("no function {} in the package", fname))
+        .clone()
+}
+
+/// Findings of a detector run over every module, in module order.
+pub(crate) fn findings(content: &DetectContent) -> Vec<String> {
+    let mut modules: Vec<_> = content.result.iter().collect();
+    modules.sort();
+    modules
+        .into_iter()
+        .flat_map(|(_, findings)| findings.iter().cloned())
+        .collect()
+}
+
+/// Whether a finding `function(..)` is reported for `fname`.
+pub(crate) fn reports(findings: &[String], fname: &str) -> bool {
+    findings
+        .iter()
+        .any(|finding| finding.starts_with(&format!("{}(", fname)))
+}
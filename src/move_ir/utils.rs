@@ -1,12 +1,12 @@
 use itertools::Itertools;
-use std::{collections::BTreeMap, fmt::Write};
+use std::{collections::BTreeMap, fmt::Write, fs};
 
 use crate::{move_ir::generate_bytecode::StacklessBytecodeGenerator, utils::utils::DotWeight};
 use move_binary_format::{
     access::ModuleAccess, file_format::FunctionDefinitionIndex, internals::ModuleIndex,
 };
 use move_model::model::{FunId, ModuleId, QualifiedId};
-use move_stackless_bytecode::stackless_bytecode::Bytecode;
+use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
 use petgraph::{
     graph::{DiGraph, Graph, NodeIndex},
     visit::EdgeRef,
@@ -14,26 +14,33 @@ use petgraph::{
 
 use move_binary_format::{
     file_format::{
-        CodeOffset, FieldHandleIndex, FunctionHandleIndex, SignatureIndex, SignatureToken,
-        StructDefinitionIndex, StructFieldInformation, StructHandleIndex,
+        AbilitySet, CodeOffset, Constant as VMConstant, FieldHandleIndex, FunctionHandleIndex,
+        SignatureIndex, SignatureToken, StructDefinitionIndex, StructFieldInformation,
+        StructHandleIndex, Visibility,
     },
     views::FunctionDefinitionView,
     CompiledModule,
 };
+use move_bytecode_source_map::source_map::SourceMap;
+use move_ir_types::location::Loc as IrLoc;
 use move_core_types::value::MoveValue;
 use move_model::{
     ast::{Attribute, Spec, TempIndex},
-    model::{FieldData, FieldId, FieldInfo, Loc, StructData, StructId, StructInfo},
+    model::{FieldData, FieldId, FieldInfo, Loc, NodeId, StructData, StructId, StructInfo},
     symbol::{Symbol, SymbolPool},
     ty::{PrimitiveType, Type, TypeDisplayContext},
 };
 use move_stackless_bytecode::stackless_bytecode::{AttrId, Constant};
 use num::BigUint;
+use regex::Regex;
 
 use crate::move_ir::bytecode_display;
 use crate::utils::utils;
 
-use super::generate_bytecode::FunctionInfo;
+use super::{
+    data_dependency::DataDepent, dataflow::DefSite, generate_bytecode::FunctionInfo,
+    summary::FunctionSummary,
+};
 
 impl<'a> StacklessBytecodeGenerator<'a> {
     pub fn call_graph2str(&self) -> Graph<String, DotWeight> {
@@ -61,9 +68,105 @@ impl<'a> StacklessBytecodeGenerator<'a> {
         let loc = self.get_bytecode_loc(func_def_idx, code_offset);
         let attr = AttrId::new(function.location_table.len());
         function.location_table.insert(attr, loc);
+        function.code_offsets.insert(attr, code_offset);
         attr
     }
 
+    /// Load the source map emitted next to `bytecode_modules` by the compiler,
+    /// `source_maps/<module>.mvsm` or `debug_info/<module>.mvd`.
+    pub fn load_source_map(&mut self) -> bool {
+        let build_path = match self.bytecode_file_path.parent().and_then(|p| p.parent()) {
+            Some(build_path) => build_path,
+            None => return false,
+        };
+        let stem = match self.bytecode_file_path.file_stem() {
+            Some(stem) => stem.to_string_lossy(),
+            None => return false,
+        };
+        let candidates = [
+            build_path.join("source_maps").join(format!("{}.mvsm", stem)),
+            build_path.join("debug_info").join(format!("{}.mvd", stem)),
+        ];
+        for path in candidates.iter() {
+            if let Ok(bytes) = fs::read(path) {
+                if let Ok(source_map) = bcs::from_bytes::<SourceMap>(&bytes) {
+                    self.module_data.source_map = source_map;
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Attributes of each function, e.g. `#[test_only]`, which the bytecode does not keep,
+    /// read from the declarations in the source code.
+    pub fn load_attributes(&mut self) {
+        let source_code = match self.source_code.as_ref() {
+            Some(source_code) => source_code,
+            None => return,
+        };
+        let mut attributes = BTreeMap::new();
+        for function in self.functions.iter() {
+            let pattern = format!(
+                r"((?:#\[[^\]]*\]\s*)*)(?:(?:public(?:\(\w+\))?|entry|native)\s+)*fun\s+({})\b",
+                regex::escape(&function.name)
+            );
+            let re = Regex::new(&pattern).unwrap();
+            // 一个源文件可以有多个 module，有源码映射时取离函数定义最近的声明
+            let def_start = self
+                .get_source_loc(function, None)
+                .map(|loc| loc.start() as usize);
+            let declaration = re.captures_iter(source_code).min_by_key(|caps| {
+                def_start.map_or(0, |start| caps.get(2).unwrap().start().abs_diff(start))
+            });
+            if let Some(caps) = declaration {
+                attributes.insert(function.idx, get_attribute_names(&caps[1]));
+            }
+        }
+        for (idx, names) in attributes {
+            let func_id =
+                self.module_data.function_idx_to_id[&FunctionDefinitionIndex::new(idx as u16)];
+            let func_data = self.module_data.function_data.get_mut(&func_id).unwrap();
+            func_data.attributes = names
+                .iter()
+                .map(|name| Attribute::Apply(NodeId::new(0), self.symbol_pool.make(name), vec![]))
+                .collect();
+        }
+    }
+
+    /// Source location of the instruction at `offset`, or of the whole function if `offset` is None.
+    fn get_source_loc(&self, function: &FunctionInfo, offset: Option<usize>) -> Option<IrLoc> {
+        let func_def_idx = FunctionDefinitionIndex::new(function.idx as u16);
+        let source_map = &self.module_data.source_map;
+        match offset {
+            Some(offset) => {
+                let attr_id = function.code.get(offset)?.get_attr_id();
+                let code_offset = *function.code_offsets.get(&attr_id)?;
+                source_map.get_code_location(func_def_idx, code_offset).ok()
+            }
+            None => source_map
+                .get_function_source_map(func_def_idx)
+                .ok()
+                .map(|fmap| fmap.definition_location),
+        }
+    }
+
+    /// Source text of the instruction at `offset`, needs both the source map and the source code.
+    pub fn get_source_snippet(&self, function: &FunctionInfo, offset: usize) -> Option<String> {
+        let source_code = self.source_code.as_ref()?;
+        let loc = self.get_source_loc(function, Some(offset))?;
+        let snippet = source_code.get(loc.start() as usize..loc.end() as usize)?;
+        Some(snippet.split_whitespace().join(" "))
+    }
+
+    /// 1-based source line of the instruction at `offset`, or of the function definition.
+    pub fn get_source_line(&self, function: &FunctionInfo, offset: Option<usize>) -> Option<usize> {
+        let source_code = self.source_code.as_ref()?;
+        let loc = self.get_source_loc(function, offset)?;
+        let prefix = source_code.get(..loc.start() as usize)?;
+        Some(prefix.matches('\n').count() + 1)
+    }
+
     pub fn get_bytecode_loc(&self, func_def_idx: FunctionDefinitionIndex, _: u16) -> Loc {
         let func_id = self.module_data.function_idx_to_id[&func_def_idx];
         let func_data = &self.module_data.function_data[&func_id];
@@ -282,6 +385,59 @@ impl<'a> StacklessBytecodeGenerator<'a> {
         format!("{}::{}", mname, fname)
     }
 
+    /// Full name of a function as used across packages, e.g. `0x2::coin::split`.
+    pub fn get_full_fname(&self, mid: ModuleId, fid: FunId) -> String {
+        format!(
+            "{}::{}",
+            self.module_names[mid.to_usize()].display(&self.symbol_pool),
+            fid.symbol().display(&self.symbol_pool)
+        )
+    }
+
+    /// Full name of a struct as used across packages, e.g. `0x2::coin::Coin`.
+    pub fn get_struct_full_name(&self, mid: ModuleId, sid: StructId) -> String {
+        format!(
+            "{}::{}",
+            self.module_names[mid.to_usize()].display(&self.symbol_pool),
+            sid.symbol().display(&self.symbol_pool)
+        )
+    }
+
+    /// Abilities of any struct this module refers to, including structs of other modules.
+    pub fn get_struct_abilities(&self, mid: ModuleId, sid: StructId) -> Option<AbilitySet> {
+        let name = sid.symbol().display(&self.symbol_pool).to_string();
+        self.module
+            .struct_handles()
+            .iter()
+            .find(|handle| {
+                handle.module.into_index() == mid.to_usize()
+                    && self.module.identifier_at(handle.name).as_str() == name
+            })
+            .map(|handle| handle.abilities)
+    }
+
+    /// Name of a field of a struct declared in this module.
+    pub fn get_field_name(&self, sid: StructId, offset: usize) -> String {
+        self.module_data
+            .struct_data
+            .get(&sid)
+            .and_then(|struct_data| {
+                struct_data
+                    .field_data
+                    .values()
+                    .find(|data| data.offset == offset)
+            })
+            .map_or(format!("field_{}", offset), |data| {
+                data.name.display(&self.symbol_pool).to_string()
+            })
+    }
+
+    pub fn get_constant_value(&self, idx: usize) -> Constant {
+        let constant = &self.module.constant_pool()[idx];
+        let ty = self.globalize_signature(&constant.type_);
+        Self::translate_value(&ty, &VMConstant::deserialize_constant(constant).unwrap())
+    }
+
     pub fn display(&self, display_function_body: bool, display_one_or_all: Option<usize>) -> String {
         let mut f = String::new();
         let mut idxs = vec![];
@@ -575,6 +731,159 @@ pub fn get_def_bytecode(function: &FunctionInfo, sid: usize, code_offset: usize)
     }
 }
 
+/// The only definition of `temp` reaching `code_offset`, if it is an instruction.
+fn get_def_offset(data_depent: &DataDepent, temp: usize, code_offset: usize) -> Option<usize> {
+    let defs = data_depent.defs_reaching(code_offset, temp);
+    match defs.iter().next() {
+        Some(DefSite::Code(offset)) if defs.len() == 1 => Some(*offset as usize),
+        _ => None,
+    }
+}
+
+/// Follow copy/move/store chains back to the bytecode that produced `temp` at `code_offset`.
+/// Returns None for parameters, which have no defining bytecode, and when more than one
+/// definition reaches a step of the chain, e.g. a local assigned in both arms of an `if`.
+pub fn get_origin_bytecode<'a>(
+    function: &'a FunctionInfo,
+    data_depent: &DataDepent,
+    temp: usize,
+    code_offset: usize,
+) -> Option<&'a Bytecode> {
+    let mut temp = temp;
+    let mut code_offset = code_offset;
+    // 限制步数，避免循环中 x := x 之类的赋值导致死循环
+    for _ in 0..function.code.len() {
+        let def_offset = get_def_offset(data_depent, temp, code_offset)?;
+        match &function.code[def_offset] {
+            Bytecode::Assign(_, _, src, _) => {
+                temp = *src;
+                code_offset = def_offset;
+            }
+            bytecode => return Some(bytecode),
+        }
+    }
+    None
+}
+
+pub fn get_param_count(idx: usize, stbgr: &StacklessBytecodeGenerator) -> usize {
+    let func_def = stbgr
+        .module
+        .function_def_at(FunctionDefinitionIndex::new(idx as u16));
+    FunctionDefinitionView::new(stbgr.module, func_def)
+        .parameters()
+        .len()
+}
+
+/// Which parameter a reference temp was borrowed from, and the first field borrowed on the way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefRoot {
+    pub param: TempIndex,
+    pub field: Option<(ModuleId, StructId, usize)>,
+}
+
+/// Trace references back to reference parameters through copies and field borrows.
+/// A single forward pass is enough since references are never redefined across loop iterations.
+pub fn get_ref_roots(function: &FunctionInfo, param_count: usize) -> BTreeMap<TempIndex, RefRoot> {
+    let mut roots = BTreeMap::new();
+    for param in 0..param_count {
+        if let Some(Type::Reference(..)) = function.local_types.get(param) {
+            roots.insert(param, RefRoot { param, field: None });
+        }
+    }
+    for bytecode in function.code.iter() {
+        match bytecode {
+            Bytecode::Assign(_, dst, src, _) => {
+                if let Some(root) = roots.get(src).copied() {
+                    roots.insert(*dst, root);
+                }
+            }
+            Bytecode::Call(_, dsts, Operation::BorrowField(mid, sid, _, offset), srcs, _) => {
+                if let Some(root) = roots.get(&srcs[0]).copied() {
+                    roots.insert(
+                        dsts[0],
+                        RefRoot {
+                            param: root.param,
+                            field: root.field.or(Some((*mid, *sid, *offset))),
+                        },
+                    );
+                }
+            }
+            Bytecode::Call(_, dsts, Operation::FreezeRef, srcs, _) => {
+                if let Some(root) = roots.get(&srcs[0]).copied() {
+                    roots.insert(dsts[0], root);
+                }
+            }
+            _ => {}
+        }
+    }
+    roots
+}
+
+/// A write through a field of a reference parameter: `write_ref`, or a `&mut` field
+/// reference handed to a callee which modifies it, by its summary for package functions.
+#[derive(Debug, Clone)]
+pub struct FieldWrite {
+    pub code_offset: usize,
+    pub param: TempIndex,
+    pub field: (ModuleId, StructId, usize),
+}
+
+pub fn get_field_writes(
+    function: &FunctionInfo,
+    stbgr: &StacklessBytecodeGenerator,
+    param_count: usize,
+    summaries: &BTreeMap<String, FunctionSummary>,
+) -> Vec<FieldWrite> {
+    let roots = get_ref_roots(function, param_count);
+    let mut writes = vec![];
+    for (code_offset, bytecode) in function.code.iter().enumerate() {
+        let written: Vec<&TempIndex> = match bytecode {
+            Bytecode::Call(_, _, Operation::WriteRef, srcs, _) => vec![&srcs[0]],
+            Bytecode::Call(_, _, Operation::Function(mid, fid, _), srcs, _) => {
+                let callee = summaries.get(&stbgr.get_full_fname(*mid, *fid));
+                srcs.iter()
+                    .enumerate()
+                    .filter(|(i, src)| {
+                        // 包外函数拿到 &mut 即视为会修改
+                        matches!(
+                            function.local_types.get(**src),
+                            Some(Type::Reference(true, _))
+                        ) && callee.map_or(true, |callee| callee.modified_params.contains(i))
+                    })
+                    .map(|(_, src)| src)
+                    .collect()
+            }
+            _ => vec![],
+        };
+        for temp in written {
+            if let Some(RefRoot {
+                param,
+                field: Some(field),
+            }) = roots.get(temp).copied()
+            {
+                writes.push(FieldWrite {
+                    code_offset,
+                    param,
+                    field,
+                });
+            }
+        }
+    }
+    writes
+}
+
+pub fn get_uint_bits(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::Primitive(PrimitiveType::U8) => Some(8),
+        Type::Primitive(PrimitiveType::U16) => Some(16),
+        Type::Primitive(PrimitiveType::U32) => Some(32),
+        Type::Primitive(PrimitiveType::U64) => Some(64),
+        Type::Primitive(PrimitiveType::U128) => Some(128),
+        Type::Primitive(PrimitiveType::U256) => Some(256),
+        _ => None,
+    }
+}
+
 pub fn get_function_name(idx: usize, stbgr: &StacklessBytecodeGenerator) -> String {
     let func_name = stbgr
         .module
@@ -595,6 +904,63 @@ pub fn is_native(idx: usize, stbgr: &StacklessBytecodeGenerator) -> bool {
         .is_native()
 }
 
+pub fn is_entry(idx: usize, stbgr: &StacklessBytecodeGenerator) -> bool {
+    stbgr
+        .module
+        .function_def_at(FunctionDefinitionIndex::new(idx as u16))
+        .is_entry
+}
+
+pub fn get_visibility(idx: usize, stbgr: &StacklessBytecodeGenerator) -> Visibility {
+    stbgr
+        .module
+        .function_def_at(FunctionDefinitionIndex::new(idx as u16))
+        .visibility
+}
+
+/// Names of the attributes in `#[test, expected_failure(abort_code = 1)] #[test_only]`.
+fn get_attribute_names(attributes: &str) -> Vec<String> {
+    let mut names = vec![];
+    for group in attributes.split("#[").skip(1) {
+        let group = &group[..group.rfind(']').unwrap_or(group.len())];
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in group.char_indices().chain([(group.len(), ',')]) {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    let name: String = group[start..i]
+                        .trim()
+                        .chars()
+                        .take_while(|c| c.is_alphanumeric() || *c == '_')
+                        .collect();
+                    if !name.is_empty() {
+                        names.push(name);
+                    }
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    names
+}
+
+/// `#[test]` and `#[test_only]` functions, known once `load_attributes` read the source.
+pub fn is_test_only(idx: usize, stbgr: &StacklessBytecodeGenerator) -> bool {
+    let func_id = stbgr.module_data.function_idx_to_id[&FunctionDefinitionIndex::new(idx as u16)];
+    stbgr.module_data.function_data[&func_id]
+        .attributes
+        .iter()
+        .any(|attr| match attr {
+            Attribute::Apply(_, name, _) | Attribute::Assign(_, name, _) => {
+                let name = name.display(&stbgr.symbol_pool).to_string();
+                name == "test" || name == "test_only"
+            }
+        })
+}
+
 pub fn get_struct_abilities_strs(
     module: &CompiledModule,
     def_idx: StructDefinitionIndex,
@@ -0,0 +1,221 @@
+// MoveScanner.toml: 关闭检测器、覆盖严重程度、屏蔽告警以及基线
+//
+// disable = ["unused_constant"]
+// suppress = ["0x0::legacy::*", "*::init_for_testing"]
+// suppress_fingerprints = ["9f2c4e0d1a7b3c55"]
+// baseline = "MoveScanner.baseline.json"
+//
+// [severity]
+// unused_private_functions = "info"
+//
+// [taint]
+// sources = ["0x0::oracle::get_price"]
+// sinks = [{ function = "0x0::pool::set_fee", arg = 1 }]
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    move_ir::taint::TaintConfig,
+    scanner::{
+        error::{ScanError, ScanResult},
+        result::{split_finding, DetectKind, Severity},
+    },
+};
+
+pub const CONFIG_FILE_NAME: &str = "MoveScanner.toml";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub disable: Vec<String>,
+    pub severity: HashMap<String, Severity>,
+    pub suppress: Vec<String>, // module::function, `*` matches anything
+    pub suppress_fingerprints: Vec<String>,
+    pub baseline: Option<PathBuf>, // relative to the config file
+    pub taint: TaintConfig,        // extra taint sources and sinks
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let mut config: Config = toml::from_str(&content)?;
+        for name in config.disable.iter().chain(config.severity.keys()) {
+            if detect_kind_by_name(name).is_none() {
+                anyhow::bail!("unknown detector `{}` in {}", name, path.display());
+            }
+        }
+        if let (Some(baseline), Some(dir)) = (&config.baseline, path.parent()) {
+            config.baseline = Some(dir.join(baseline));
+        }
+        Ok(config)
+    }
+
+    pub fn is_disabled(&self, kind: &DetectKind) -> bool {
+        self.disable.contains(&kind.to_string())
+    }
+
+    pub fn severity_overrides(&self) -> Vec<(DetectKind, Severity)> {
+        self.severity
+            .iter()
+            .filter_map(|(name, severity)| {
+                detect_kind_by_name(name).map(|kind| (kind, severity.clone()))
+            })
+            .collect()
+    }
+
+    /// Whether a finding matches a `suppress` pattern or a suppressed fingerprint.
+    pub fn is_suppressed(&self, kind: &DetectKind, module_name: &str, finding: &str) -> bool {
+        let (name, _) = split_finding(finding);
+        let full_name = format!("{}::{}", module_name, name);
+        self.suppress
+            .iter()
+            .any(|pattern| glob_match(pattern, &full_name))
+            || self
+                .suppress_fingerprints
+                .contains(&fingerprint(kind, module_name, finding))
+    }
+
+    /// Fingerprints recorded by `--write-baseline`, empty when no baseline is configured. A
+    /// configured baseline that is missing or corrupt is an error rather than an empty set,
+    /// which would report every baselined finding again.
+    pub fn load_baseline(&self) -> ScanResult<BTreeSet<String>> {
+        let path = match &self.baseline {
+            Some(path) => path,
+            None => return Ok(BTreeSet::new()),
+        };
+        let content = fs::read_to_string(path).map_err(|e| {
+            ScanError::InvalidInput(format!("read baseline {} failed: {}", path.display(), e))
+        })?;
+        serde_json::from_str(&content).map_err(|e| {
+            ScanError::InvalidInput(format!("parse baseline {} failed: {}", path.display(), e))
+        })
+    }
+
+    /// Record the fingerprints as the baseline.
+    pub fn write_baseline(&self, fingerprints: &BTreeSet<String>) -> ScanResult<()> {
+        let path = self.baseline.as_ref().ok_or_else(|| {
+            ScanError::InvalidInput("`baseline` is not set in config".to_string())
+        })?;
+        let content = serde_json::to_string_pretty(fingerprints)
+            .map_err(|e| ScanError::OutputFailure(e.to_string()))?;
+        fs::write(path, content).map_err(|e| {
+            ScanError::OutputFailure(format!("write baseline {} failed: {}", path.display(), e))
+        })
+    }
+}
+
+fn detect_kind_by_name(name: &str) -> Option<DetectKind> {
+    DetectKind::iter().find(|kind| kind.to_string() == name)
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let re = format!("^{}$", regex::escape(pattern).replace(r"\*", ".*"));
+    Regex::new(&re).map_or(false, |re| re.is_match(text))
+}
+
+/// Stable fingerprint of a finding; bytecode offsets are ignored when a source snippet is present.
+pub fn fingerprint(kind: &DetectKind, module_name: &str, finding: &str) -> String {
+    let (name, detail) = split_finding(finding);
+    let detail = detail.map(|detail| match detail.split_once(": ") {
+        Some((offset, snippet)) if offset.parse::<usize>().is_ok() => snippet,
+        _ => detail,
+    });
+    finding_hash(&format!(
+        "{}|{}|{}|{}",
+        kind,
+        module_name,
+        name,
+        detail.unwrap_or_default()
+    ))
+}
+
+// FNV-1a 64
+fn finding_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suppress_and_fingerprint() {
+        let config: Config = toml::from_str(
+            r#"
+            disable = ["unused_constant"]
+            suppress = ["0x0::legacy::*"]
+            [severity]
+            unused_private_functions = "info"
+            "#,
+        )
+        .unwrap();
+        assert!(config.is_disabled(&DetectKind::UnusedConstant));
+        let kind = DetectKind::UnusedPrivateFunctions;
+        assert!(config.is_suppressed(&kind, "0x0::legacy", "helper"));
+        assert!(!config.is_suppressed(&kind, "0x0::pool", "helper"));
+        assert_eq!(
+            config.severity_overrides(),
+            vec![(DetectKind::UnusedPrivateFunctions, Severity::Info)]
+        );
+
+        // 代码移动导致 offset 变化时指纹不变
+        let kind = DetectKind::UnnecessaryBoolJudgment;
+        assert_eq!(
+            fingerprint(&kind, "0x0::pool", "swap(12: x == true)"),
+            fingerprint(&kind, "0x0::pool", "swap(15: x == true)")
+        );
+        assert_ne!(
+            fingerprint(&kind, "0x0::pool", "swap(12)"),
+            fingerprint(&kind, "0x0::pool", "swap(15)")
+        );
+    }
+
+    #[test]
+    fn test_taint_table() {
+        let config: Config = toml::from_str(
+            r#"
+            [taint]
+            sources = ["oracle::get_price"]
+            sinks = [{ function = "pool::set_fee", arg = 1 }]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.taint.sources, vec!["oracle::get_price".to_string()]);
+        assert_eq!(config.taint.sinks[0].function, "pool::set_fee");
+        assert_eq!(config.taint.sinks[0].arg, 1);
+        assert!(Config::default().taint.sinks.is_empty());
+    }
+
+    #[test]
+    fn test_baseline() {
+        let dir =
+            std::env::temp_dir().join(format!("move_scanner_baseline_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = Config {
+            baseline: Some(dir.join("baseline.json")),
+            ..Config::default()
+        };
+        assert!(Config::default().load_baseline().unwrap().is_empty());
+        // 配置了基线但文件缺失或损坏时报错，而不是当作空集合
+        assert!(config.load_baseline().is_err());
+        fs::write(dir.join("baseline.json"), "not json").unwrap();
+        assert!(config.load_baseline().is_err());
+
+        let fingerprints = BTreeSet::from(["9f2c4e0d1a7b3c55".to_string()]);
+        config.write_baseline(&fingerprints).unwrap();
+        assert_eq!(config.load_baseline().unwrap(), fingerprints);
+        assert!(Config::default().write_baseline(&fingerprints).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
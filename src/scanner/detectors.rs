@@ -1,10 +1,28 @@
 use crate::{
+    detectors::{
+        dead_store::DeadStore, invalid_one_time_witness::InvalidOneTimeWitness,
+        mutable_reference_leak::MutableReferenceLeak, narrowing_cast::NarrowingCast,
+        randomness_misuse::RandomnessMisuse, recursive_function_call::RecursiveFunctionCall,
+        repeated_function_call::RepeatedFunctionCall,
+        shared_object_access_control::SharedObjectAccessControl, taint_flow::TaintFlow,
+        timestamp_dependence::TimestampDependence,
+        unbounded_iteration::UnboundedIteration, unchecked_hot_potato::UncheckedHotPotato,
+        unguarded_mint::UnguardedMint,
+        unnecessary_bool_judgment::UnnecessaryBoolJudgment,
+        unnecessary_type_conversion::UnnecessaryTypeConversion,
+        unreturned_coin_remainder::UnreturnedCoinRemainder, unused_constant::UnusedConstant,
+        unused_private_functions::UnusedPrivateFunctions, AbstractDetector,
+    },
     move_ir::{
+        fatloop,
         packages::{build_compiled_modules, Packages},
+        taint,
         utils,
     },
     scanner::{
-        option::{Options},
+        config::fingerprint,
+        error::{ScanError, ScanResult},
+        option::{Options, TerminalFormat},
         result::*,
     },
 
@@ -13,9 +31,10 @@ use move_binary_format::access::ModuleAccess;
 use num::ToPrimitive;
 use regex::Regex;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fs,
-    io::{BufRead, BufReader, Write},
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
     time::Instant,
 };
 use walkdir::WalkDir;
@@ -33,20 +52,26 @@ impl GraphGenerator {
         }
     }
 
-    pub fn run(&mut self) {
+    pub fn run(&mut self) -> ScanResult<()> {
         let clock = Instant::now();
         // build package
-        let cms = build_compiled_modules(&self.options.bytecode_path);
-        let packages = Packages::new(&cms);
+        let cms = build_compiled_modules(&self.options.bytecode_path)?;
+        let mut packages = Packages::new(&cms);
         self.init_result(&packages);
-
-        // No detectors to run
-        
+        self.load_source_code(&mut packages);
+        // 检测器与图导出共用同一次污点分析
+        let taint_flows = taint::get_taint_flows(&packages, &self.options.config.taint);
+        self.run_detectors(&packages, &taint_flows);
+        self.apply_config()?;
         self.complete_result(clock);
+        if let TerminalFormat::Block = self.options.terminal_format {
+            println!("{}", self.result);
+        }
 
         // Export Knowledge Graph
-        let graph_output = crate::scanner::exporter::GraphExporter::export(&packages, &self.result);
-        let graph_json = serde_json::to_string_pretty(&graph_output).expect("Failed to serialize graph");
+        let graph_output = crate::scanner::exporter::GraphExporter::export(&packages, &self.result, &taint_flows);
+        let graph_json = serde_json::to_string_pretty(&graph_output)
+            .map_err(|e| ScanError::OutputFailure(format!("serialize graph failed: {}", e)))?;
         
         let mut graph_path = self.options.output_path.clone();
         if let Some(file_name) = graph_path.file_stem() {
@@ -60,19 +85,21 @@ impl GraphGenerator {
              graph_path.set_extension("graph.json");
         }
 
-        if let Some(dir_path) = graph_path.parent() {
-            if !dir_path.exists() {
-                 let _ = fs::create_dir_all(dir_path);
-            }
+        write_output(&graph_path, &graph_json)?;
+
+        if let Some(sarif_path) = &self.options.sarif_path {
+            let sarif = crate::scanner::sarif::to_sarif(&self.result, &packages);
+            let sarif_json = serde_json::to_string_pretty(&sarif)
+                .map_err(|e| ScanError::OutputFailure(format!("serialize sarif failed: {}", e)))?;
+            write_output(sarif_path, &sarif_json)?;
         }
-        
-        let mut file = fs::File::create(graph_path).expect("Failed to create graph json file");
-        file.write_all(graph_json.as_bytes()).expect("Failed to write graph json");
+        Ok(())
     }
 
     /// Initialize ModuleInfo for each module
     fn init_result(&mut self, packages: &Packages) {
-        let locations = self.find_module_path(&packages.get_module_names());
+        let locations =
+            find_module_path(self.options.sources_path.as_ref(), &packages.get_module_names());
 
         for (module_name, &ref stbgr) in packages.get_all_stbgr().iter() {
             let mut module_info = ModuleInfo::empty();
@@ -82,12 +109,25 @@ impl GraphGenerator {
                 .get_mut(&FunctionType::All)
                 .unwrap() = stbgr.functions.len();
             module_info.location = locations.get(module_name).unwrap().clone();
-            for (idx, _function) in stbgr.functions.iter().enumerate() {
+            for (idx, function) in stbgr.functions.iter().enumerate() {
                 if utils::is_native(idx, stbgr) {
                     *module_info
                         .function_count
                         .get_mut(&FunctionType::Native)
                         .unwrap() += 1;
+                    continue;
+                }
+                match fatloop::get_loops(function) {
+                    Ok((_, annotation)) if !annotation.reducible => {
+                        module_info.diagnostics.push(format!(
+                            "{}: irreducible control flow, loops approximated by strongly connected components",
+                            function.name
+                        ));
+                    }
+                    Ok(_) => {}
+                    Err(e) => module_info
+                        .diagnostics
+                        .push(format!("{}: {}", function.name, e)),
                 }
             }
             
@@ -108,86 +148,248 @@ impl GraphGenerator {
         }
     }
 
-    fn complete_result(&mut self, clock: Instant) {
-        self.result.total_time = clock.elapsed().as_micros().to_usize().unwrap();
-        // Always pass since we aren't checking anything
-        for (_module_name, module_info) in self.result.modules.iter_mut() {
-            module_info.status = Status::Pass;
+    /// Attach module sources found by find_module_path, used for source snippets
+    fn load_source_code(&self, packages: &mut Packages) {
+        for (module_name, module_info) in self.result.modules.iter() {
+            if let Some((path, _)) = module_info.location.as_ref().and_then(|l| l.rsplit_once(':')) {
+                packages.load_source_code(module_name, &PathBuf::from(path));
+            }
         }
     }
 
-    fn find_module_path(
-        &self,
-        module_name_list: &Vec<String>,
-    ) -> HashMap<ModuleName, Option<Location>> {
-        let mut res = HashMap::new();
-        let mut used_sources_path = Vec::new();
-        let mut all_sources_path = Vec::new();
-        if let Some(source_path) = self.options.sources_path.clone() {
-            for entry in WalkDir::new(source_path).into_iter().filter_map(|e| e.ok()) {
-                if entry.file_type().is_file()
-                    && entry.file_name().to_str().unwrap().ends_with(".move")
-                {
-                    all_sources_path.push(entry.path().to_path_buf());
+    /// Run all detectors on the whole package and merge findings into ModuleInfo
+    fn run_detectors(&mut self, packages: &Packages, taint_flows: &[taint::TaintFlow]) {
+        let config = &self.options.config;
+        let mut detectors: Vec<(DetectKind, Box<dyn AbstractDetector<'_> + '_>)> = vec![
+            (
+                DetectKind::UnusedConstant,
+                Box::new(UnusedConstant::new(packages)),
+            ),
+            (
+                DetectKind::UnusedPrivateFunctions,
+                Box::new(UnusedPrivateFunctions::new(packages)),
+            ),
+            (
+                DetectKind::RecursiveFunctionCall,
+                Box::new(RecursiveFunctionCall::new(packages)),
+            ),
+            (
+                DetectKind::RepeatedFunctionCall,
+                Box::new(RepeatedFunctionCall::new(packages)),
+            ),
+            (
+                DetectKind::UnnecessaryTypeConversion,
+                Box::new(UnnecessaryTypeConversion::new(packages)),
+            ),
+            (
+                DetectKind::UnnecessaryBoolJudgment,
+                Box::new(UnnecessaryBoolJudgment::new(packages)),
+            ),
+            (
+                DetectKind::SharedObjectAccessControl,
+                Box::new(SharedObjectAccessControl::new(packages)),
+            ),
+            (
+                DetectKind::InvalidOneTimeWitness,
+                Box::new(InvalidOneTimeWitness::new(packages)),
+            ),
+            (
+                DetectKind::UncheckedHotPotato,
+                Box::new(UncheckedHotPotato::new(packages)),
+            ),
+            (
+                DetectKind::MutableReferenceLeak,
+                Box::new(MutableReferenceLeak::new(packages)),
+            ),
+            (
+                DetectKind::UnreturnedCoinRemainder,
+                Box::new(UnreturnedCoinRemainder::new(packages)),
+            ),
+            (
+                DetectKind::UnguardedMint,
+                Box::new(UnguardedMint::new(packages)),
+            ),
+            (
+                DetectKind::UnboundedIteration,
+                Box::new(UnboundedIteration::new(packages)),
+            ),
+            (
+                DetectKind::RandomnessMisuse,
+                Box::new(RandomnessMisuse::new(packages)),
+            ),
+            (
+                DetectKind::TimestampDependence,
+                Box::new(TimestampDependence::new(packages)),
+            ),
+            (
+                DetectKind::NarrowingCast,
+                Box::new(NarrowingCast::new(packages)),
+            ),
+            (DetectKind::DeadStore, Box::new(DeadStore::new(packages))),
+            (
+                DetectKind::TaintFlow,
+                Box::new(TaintFlow::with_flows(packages, taint_flows)),
+            ),
+        ];
+        // 关闭的检测器不运行
+        detectors.retain(|(kind, _)| !config.is_disabled(kind));
+        for (_, detector) in detectors.iter_mut() {
+            let content = detector.run();
+            self.result
+                .severities
+                .insert(content.kind.clone(), content.severity.clone());
+            for (module_name, values) in content.result.iter() {
+                if let Some(module_info) = self.result.modules.get_mut(module_name) {
+                    module_info
+                        .detectors
+                        .get_mut(&content.kind)
+                        .unwrap()
+                        .extend(values.iter().cloned());
                 }
             }
+        }
+    }
+
+    /// Apply MoveScanner.toml: severity overrides, suppressions and baseline; disabled
+    /// detectors are not run at all
+    fn apply_config(&mut self) -> ScanResult<()> {
+        let config = &self.options.config;
+        for (kind, severity) in config.severity_overrides() {
+            if let Some(s) = self.result.severities.get_mut(&kind) {
+                *s = severity;
+            }
+        }
+        // 写基线时基线文件可能尚不存在
+        let baseline = if self.options.write_baseline {
+            BTreeSet::new()
         } else {
-            for module_name in module_name_list {
-                res.insert(module_name.to_string(), None);
+            config.load_baseline()?
+        };
+        let mut fingerprints = BTreeSet::new();
+        for (module_name, module_info) in self.result.modules.iter_mut() {
+            for (kind, values) in module_info.detectors.iter_mut() {
+                values.retain(|finding| !config.is_suppressed(kind, module_name, finding));
+                if self.options.write_baseline {
+                    fingerprints.extend(
+                        values
+                            .iter()
+                            .map(|finding| fingerprint(kind, module_name, finding)),
+                    );
+                } else {
+                    // 基线中已有的告警不再报告
+                    values.retain(|finding| {
+                        !baseline.contains(&fingerprint(kind, module_name, finding))
+                    });
+                }
+            }
+        }
+        if self.options.write_baseline {
+            config.write_baseline(&fingerprints)?;
+        }
+        Ok(())
+    }
+
+    fn complete_result(&mut self, clock: Instant) {
+        self.result.total_time = clock.elapsed().as_micros().to_usize().unwrap();
+        for (module_name, module_info) in self.result.modules.iter_mut() {
+            module_info.status = if module_info.detectors.values().all(|v| v.is_empty()) {
+                Status::Pass
+            } else {
+                Status::Wrong
+            };
+            self.result
+                .modules_status
+                .get_mut(&module_info.status)
+                .unwrap()
+                .push(module_name.to_string());
+        }
+    }
+}
+
+/// Write an output file, creating its directory; failures are reported, not panicked on.
+fn write_output(path: &Path, content: &str) -> ScanResult<()> {
+    let output_failure =
+        |e: std::io::Error| ScanError::OutputFailure(format!("{}: {}", path.display(), e));
+    if let Some(dir_path) = path.parent() {
+        if !dir_path.as_os_str().is_empty() && !dir_path.exists() {
+            fs::create_dir_all(dir_path).map_err(output_failure)?;
+        }
+    }
+    fs::write(path, content).map_err(output_failure)
+}
+
+/// `path:line` of the `module` declaration of each module in the `.move` files under `sources_path`.
+pub fn find_module_path(
+    sources_path: Option<&PathBuf>,
+    module_name_list: &Vec<String>,
+) -> HashMap<ModuleName, Option<Location>> {
+    let mut res = HashMap::new();
+    let mut used_sources_path = Vec::new();
+    let mut all_sources_path = Vec::new();
+    if let Some(source_path) = sources_path {
+        for entry in WalkDir::new(source_path).into_iter().filter_map(|e| e.ok()) {
+            if entry.file_type().is_file()
+                && entry.file_name().to_str().unwrap().ends_with(".move")
+            {
+                all_sources_path.push(entry.path().to_path_buf());
             }
-            return res;
         }
+    } else {
         for module_name in module_name_list {
-            let re =
-                Regex::new(format!(r"module .*::{}([{{\s]|$)", module_name).to_string().as_str()).unwrap();
-            let mut find = false;
-            // 1. Check unused sources
-            for source_path in all_sources_path.iter() {
-                if !used_sources_path.contains(&source_path) {
-                    let file = fs::File::open(source_path).unwrap();
-                    let reader = BufReader::new(file);
-                    for (line_num, line) in reader.lines().enumerate() {
-                        if let Ok(line) = line {
-                            if re.is_match(&line) {
-                                let location =
-                                    format!("{}:{}", source_path.display(), line_num + 1);
-                                res.insert(module_name.to_string(), Some(location));
-                                used_sources_path.push(source_path);
-                                find = true;
-                                break;
-                            }
+            res.insert(module_name.to_string(), None);
+        }
+        return res;
+    }
+    for module_name in module_name_list {
+        let re =
+            Regex::new(format!(r"module .*::{}([{{\s]|$)", module_name).to_string().as_str()).unwrap();
+        let mut find = false;
+        // 1. Check unused sources
+        for source_path in all_sources_path.iter() {
+            if !used_sources_path.contains(&source_path) {
+                let file = fs::File::open(source_path).unwrap();
+                let reader = BufReader::new(file);
+                for (line_num, line) in reader.lines().enumerate() {
+                    if let Ok(line) = line {
+                        if re.is_match(&line) {
+                            let location =
+                                format!("{}:{}", source_path.display(), line_num + 1);
+                            res.insert(module_name.to_string(), Some(location));
+                            used_sources_path.push(source_path);
+                            find = true;
+                            break;
                         }
                     }
                 }
+            }
 
-                if find {
-                    break;
-                }
+            if find {
+                break;
             }
-            // 2. Check used sources
-            if !find {
-                for used_source_path in used_sources_path.iter() {
-                    let file = fs::File::open(used_source_path).unwrap();
-                    let reader = BufReader::new(file);
-                    for (line_num, line) in reader.lines().enumerate() {
-                        if let Ok(line) = line {
-                            if re.is_match(&line) {
-                                let location =
-                                    format!("{}:{}", used_source_path.display(), line_num + 1);
-                                res.insert(module_name.to_string(), Some(location));
-                                find = true;
-                                break;
-                            }
+        }
+        // 2. Check used sources
+        if !find {
+            for used_source_path in used_sources_path.iter() {
+                let file = fs::File::open(used_source_path).unwrap();
+                let reader = BufReader::new(file);
+                for (line_num, line) in reader.lines().enumerate() {
+                    if let Ok(line) = line {
+                        if re.is_match(&line) {
+                            let location =
+                                format!("{}:{}", used_source_path.display(), line_num + 1);
+                            res.insert(module_name.to_string(), Some(location));
+                            find = true;
+                            break;
                         }
                     }
                 }
             }
-            // 3. Not found
-            if !find {
-                res.insert(module_name.to_string(), None);
-                println!("Info: {} not found in source code！", module_name);
-            }
         }
-        res
+        // 3. Not found
+        if !find {
+            res.insert(module_name.to_string(), None);
+            println!("Info: {} not found in source code！", module_name);
+        }
     }
+    res
 }
@@ -0,0 +1,46 @@
+// 进程退出码，供 CI 判断扫描结果
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCode {
+    Success = 0,
+    Findings = 1, // findings at or above --fail-on
+    InvalidInput = 2,
+    CompileFailure = 3,
+    DeserializationFailure = 4,
+    OutputFailure = 5, // graph, SARIF or baseline could not be written
+}
+
+#[derive(Debug)]
+pub enum ScanError {
+    InvalidInput(String),
+    CompileFailure(String),
+    DeserializationFailure(String),
+    OutputFailure(String),
+}
+
+pub type ScanResult<T> = std::result::Result<T, ScanError>;
+
+impl ScanError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            ScanError::InvalidInput(_) => ExitCode::InvalidInput,
+            ScanError::CompileFailure(_) => ExitCode::CompileFailure,
+            ScanError::DeserializationFailure(_) => ExitCode::DeserializationFailure,
+            ScanError::OutputFailure(_) => ExitCode::OutputFailure,
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+            ScanError::CompileFailure(msg) => write!(f, "compile failed: {}", msg),
+            ScanError::DeserializationFailure(msg) => write!(f, "deserialization failed: {}", msg),
+            ScanError::OutputFailure(msg) => write!(f, "write output failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
@@ -1,22 +1,33 @@
+use crate::move_ir::abort::{self, AbortKind};
 use crate::move_ir::packages::Packages;
 use crate::move_ir::generate_bytecode::StacklessBytecodeGenerator;
-use crate::scanner::graph::{GraphOutput, NodeWrapper, ModuleNode, FunctionNode, StructNode, EdgeWrapper};
+use crate::move_ir::sui::{self, CapabilityKind};
+use crate::move_ir::taint::{TaintFlow, TaintSource};
+use crate::scanner::graph::{GraphOutput, NodeWrapper, ModuleNode, FunctionNode, StructNode, ConstantNode, ParameterNode, AbortConditionNode, EdgeWrapper};
 use move_binary_format::access::ModuleAccess;
+use move_binary_format::file_format::FunctionDefinitionIndex;
 use move_model::model::{FunId, ModuleId, QualifiedId, StructId};
+use std::collections::BTreeSet;
 use move_stackless_bytecode::stackless_bytecode::{Bytecode, Operation};
 
 
 pub struct GraphExporter;
 
-use crate::scanner::result::Result;
+use crate::scanner::result::{DetectKind, Result};
+use itertools::Itertools;
 use regex::Regex;
 
 impl GraphExporter {
-    pub fn export(packages: &Packages, result: &Result) -> GraphOutput {
+    pub fn export(packages: &Packages, result: &Result, taint_flows: &[TaintFlow]) -> GraphOutput {
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
+        let shared = sui::get_shared_structs(packages);
+        let capabilities = sui::get_capabilities(packages);
+        let potatoes = sui::get_hot_potatoes(packages);
 
         for (module_name_str, stbgr) in packages.get_all_stbgr() {
+            let otw = sui::get_one_time_witness(stbgr);
+            let init = sui::get_init(stbgr);
             // Load source code if available
             let mut source_content_opt = None;
             if let Some(mod_info) = result.modules.get(module_name_str) {
@@ -30,6 +41,16 @@ impl GraphExporter {
                  }
             }
 
+            let findings = |kind: DetectKind| -> Vec<String> {
+                result
+                    .modules
+                    .get(module_name_str)
+                    .map(|mod_info| mod_info.detectors[&kind].clone())
+                    .unwrap_or_default()
+            };
+            let unused_functions = findings(DetectKind::UnusedPrivateFunctions);
+            let unused_constants = findings(DetectKind::UnusedConstant);
+
             // Helper to stringify Loc
             let get_src = |loc: &move_model::model::Loc, name: &str, type_desc: &str| -> String {
                  if let Some(source_content) = &source_content_opt {
@@ -86,6 +107,9 @@ impl GraphExporter {
                     name: s_name,
                     abilities,
                     is_resource,
+                    is_capability: capabilities.get(&full_struct_id) == Some(&CapabilityKind::Capability),
+                    is_witness: capabilities.get(&full_struct_id) == Some(&CapabilityKind::Witness),
+                    is_otw: otw == Some(*struct_id),
                     source,
                 }));
 
@@ -95,6 +119,28 @@ impl GraphExporter {
                 });
             }
 
+            // 2.1 Constant Nodes
+            for idx in 0..stbgr.module.constant_pool().len() {
+                let const_name = format!("const_{}", idx);
+                let full_const_id = format!("{}::{}", mod_id_str, const_name);
+                let is_dead = unused_constants
+                    .iter()
+                    .any(|v| v.split('(').next() == Some(const_name.as_str()));
+
+                nodes.push(NodeWrapper::Constant(ConstantNode {
+                    id: full_const_id.clone(),
+                    module_id: mod_id_str.clone(),
+                    index: idx,
+                    value: stbgr.get_constant_value(idx).to_string(),
+                    is_dead,
+                }));
+
+                edges.push(EdgeWrapper::Defines {
+                    from: mod_id_str.clone(),
+                    to: full_const_id,
+                });
+            }
+
             // 3. Function Nodes and Call Graph
             for function in &stbgr.functions {
                 let f_name = function.name.clone();
@@ -122,6 +168,13 @@ impl GraphExporter {
                     is_native,
                     arg_count: function.args_count,
                     source,
+                    is_dead: unused_functions.contains(&f_name),
+                    is_init: init == Some(function.idx),
+                    coin_flows: packages
+                        .get_coin_flows(&full_func_id)
+                        .iter()
+                        .map(|flow| flow.to_string())
+                        .collect(),
                 }));
 
                 edges.push(EdgeWrapper::Defines {
@@ -129,7 +182,63 @@ impl GraphExporter {
                     to: full_func_id.clone(),
                 });
 
-                // 3.1 Extract internal body relationships (Packs, Unpacks, Acquires)
+                for guard in sui::get_guards(function.idx, stbgr, &capabilities, &shared) {
+                    edges.push(EdgeWrapper::GuardedBy {
+                        from: full_func_id.clone(),
+                        to: guard,
+                    });
+                }
+                for potato in sui::get_produced_potatoes(function.idx, stbgr, &potatoes) {
+                    edges.push(EdgeWrapper::ProducesPotato {
+                        from: full_func_id.clone(),
+                        to: potato,
+                    });
+                }
+                for potato in sui::get_consumed_potatoes(function, stbgr, &potatoes)
+                    .into_iter()
+                    .map(|(_, potato)| potato)
+                    .unique()
+                {
+                    edges.push(EdgeWrapper::ConsumesPotato {
+                        from: full_func_id.clone(),
+                        to: potato,
+                    });
+                }
+
+                // 3.1 Abort conditions, linked to the constant of the abort code
+                for (i, condition) in abort::get_abort_conditions(packages, &full_func_id)
+                    .into_iter()
+                    .enumerate()
+                {
+                    let id = format!("{}::abort_{}", full_func_id, i);
+                    edges.push(EdgeWrapper::MayAbort {
+                        from: full_func_id.clone(),
+                        to: id.clone(),
+                    });
+                    // 只连接确定加载的常量，同值的其他常量不作猜测
+                    if let Some(idx) = condition.error_constant {
+                        edges.push(EdgeWrapper::AbortsWith {
+                            from: id.clone(),
+                            to: format!("{}::const_{}", mod_id_str, idx),
+                        });
+                    }
+                    if let AbortKind::Callee(callee) = &condition.kind {
+                        edges.push(EdgeWrapper::AbortsIn {
+                            from: id.clone(),
+                            to: callee.clone(),
+                        });
+                    }
+                    nodes.push(NodeWrapper::AbortCondition(AbortConditionNode {
+                        id,
+                        function_id: full_func_id.clone(),
+                        code_offset: condition.code_offset as usize,
+                        kind: condition.kind.to_string(),
+                        conditions: condition.conditions,
+                        error_code: condition.error_code.map(|code| code.to_string()),
+                    }));
+                }
+
+                // 3.2 Extract internal body relationships (Packs, Unpacks, Acquires)
                 for code in &function.code {
                     if let Bytecode::Call(_, _, operation, _, _) = code {
                         match operation {
@@ -181,6 +290,42 @@ impl GraphExporter {
             }
         }
 
+        // 5. Taint flows from entry parameters to sink sites
+        let mut parameters = BTreeSet::new();
+        for flow in taint_flows.iter() {
+            let (stbgr, idx) = match packages.get_function_by_full_name(&flow.entry) {
+                Some(function) => function,
+                None => continue,
+            };
+            let (from, source) = match &flow.source {
+                TaintSource::Param(param) => {
+                    let id = format!("{}::param_{}", flow.entry, param);
+                    let name = stbgr
+                        .get_local_name(FunctionDefinitionIndex::new(idx as u16), *param)
+                        .display(&stbgr.symbol_pool)
+                        .to_string();
+                    if parameters.insert(id.clone()) {
+                        nodes.push(NodeWrapper::Parameter(ParameterNode {
+                            id: id.clone(),
+                            function_id: flow.entry.clone(),
+                            index: *param,
+                            name: name.clone(),
+                        }));
+                    }
+                    (id, name)
+                }
+                TaintSource::Call(source) => (flow.entry.clone(), source.clone()),
+            };
+            edges.push(EdgeWrapper::TaintFlow {
+                from,
+                to: flow.sink.function.clone(),
+                source,
+                sink: flow.sink.kind.clone(),
+                code_offset: flow.sink.code_offset as usize,
+                path: flow.path.clone(),
+            });
+        }
+
         GraphOutput { nodes, edges }
     }
 }
@@ -240,3 +385,73 @@ fn extract_definition(source: &str, kind: &str, name: &str) -> String {
     }
     String::new()
 }
+
+#[cfg(test)]
+mod tests {
+    use move_binary_format::file_format::{Ability, AbilitySet, SignatureToken};
+
+    use super::*;
+    use crate::move_ir::test_utils::{shared_pool_module, with_paths, ModuleBuilder};
+
+    fn struct_nodes(output: &GraphOutput) -> Vec<&StructNode> {
+        output
+            .nodes
+            .iter()
+            .filter_map(|node| match node {
+                NodeWrapper::Struct(node) => Some(node),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_capabilities_exclude_shared_objects() {
+        let cms = with_paths(vec![shared_pool_module()]);
+        let packages = Packages::new(&cms);
+        let output = GraphExporter::export(&packages, &Result::empty(), &[]);
+        let structs = struct_nodes(&output);
+        let pool = structs.iter().find(|node| node.name == "Pool").unwrap();
+        let cap = structs.iter().find(|node| node.name == "AdminCap").unwrap();
+        assert!(!pool.is_capability);
+        assert!(cap.is_capability);
+
+        let guards: Vec<(&String, &String)> = output
+            .edges
+            .iter()
+            .filter_map(|edge| match edge {
+                EdgeWrapper::GuardedBy { from, to } => Some((from, to)),
+                _ => None,
+            })
+            .collect();
+        assert!(guards.iter().all(|(_, to)| **to != pool.id));
+        assert_eq!(guards.len(), 1);
+        assert!(guards[0].0.ends_with("::admin_set"));
+        assert_eq!(*guards[0].1, cap.id);
+    }
+
+    #[test]
+    fn test_witnesses() {
+        let mut builder = ModuleBuilder::new("auth");
+        let drop = AbilitySet::EMPTY | Ability::Drop;
+        builder.define_struct("Auth", drop, vec![("dummy_field", SignatureToken::Bool)]);
+        // 有字段的 drop 结构体携带数据，不是 witness
+        builder.define_struct("Ticket", drop, vec![("value", SignatureToken::U64)]);
+        // 能复制的空结构体可以凭空复制，也不是 witness
+        builder.define_struct(
+            "Marker",
+            drop | Ability::Copy,
+            vec![("dummy_field", SignatureToken::Bool)],
+        );
+
+        let cms = with_paths(vec![builder.build()]);
+        let packages = Packages::new(&cms);
+        let output = GraphExporter::export(&packages, &Result::empty(), &[]);
+        let witnesses: Vec<&str> = struct_nodes(&output)
+            .iter()
+            .filter(|node| node.is_witness)
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(witnesses, vec!["Auth"]);
+        assert!(struct_nodes(&output).iter().all(|node| !node.is_capability));
+    }
+}
@@ -12,6 +12,9 @@ pub enum NodeWrapper {
     Module(ModuleNode),
     Function(FunctionNode),
     Struct(StructNode),
+    Constant(ConstantNode),
+    Parameter(ParameterNode),
+    AbortCondition(AbortConditionNode),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,6 +33,9 @@ pub struct FunctionNode {
     pub is_native: bool,
     pub arg_count: usize,
     pub source: String,
+    pub is_dead: bool, // unreachable from public/entry/init/test functions
+    pub is_init: bool, // Sui module initializer
+    pub coin_flows: Vec<String>, // e.g. "withdraw amount via 0x2::coin::split at 12"
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,15 +45,66 @@ pub struct StructNode {
     pub name: String,
     pub abilities: Vec<String>,
     pub is_resource: bool, // true if has 'key' ability
+    pub is_capability: bool, // key-only object created only in init or by capability holders
+    pub is_witness: bool,    // field-less drop-only struct
+    pub is_otw: bool,        // one-time witness consumed by init
     pub source: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConstantNode {
+    pub id: String, // e.g., "0x1::coin::const_0"
+    pub module_id: String,
+    pub index: usize,
+    pub value: String,
+    pub is_dead: bool, // never loaded by live code
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParameterNode {
+    pub id: String, // e.g., "0x0::pool::swap::param_1"
+    pub function_id: String,
+    pub index: usize,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AbortConditionNode {
+    pub id: String, // e.g., "0x0::pool::swap::abort_0"
+    pub function_id: String,
+    pub code_offset: usize,
+    pub kind: String,               // abort, overflow, division by zero, ...
+    pub conditions: Vec<String>,    // conjunction over parameters and object fields
+    pub error_code: Option<String>, // abort code or VM status, None when a callee aborts
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum EdgeWrapper {
-    Defines { from: String, to: String }, // Module defines Function/Struct
+    Defines { from: String, to: String }, // Module defines Function/Struct/Constant
     Calls { from: String, to: String },   // Function calls Function
     Packs { from: String, to: String },   // Function packs Struct
     Unpacks { from: String, to: String }, // Function unpacks Struct
     Acquires { from: String, to: String }, // Function acquires/moves Struct
+    #[serde(rename = "GUARDED_BY")]
+    GuardedBy { from: String, to: String }, // Function requires a capability/witness Struct parameter
+    #[serde(rename = "PRODUCES_POTATO")]
+    ProducesPotato { from: String, to: String }, // Function returns a no-ability Struct
+    #[serde(rename = "CONSUMES_POTATO")]
+    ConsumesPotato { from: String, to: String }, // Function unpacks a no-ability Struct
+    #[serde(rename = "TAINT_FLOW")]
+    TaintFlow {
+        from: String, // Parameter, or the entry Function for a source call such as tx_context::sender
+        to: String,   // Function containing the sink
+        source: String,
+        sink: String,
+        code_offset: usize,
+        path: Vec<String>,
+    },
+    #[serde(rename = "MAY_ABORT")]
+    MayAbort { from: String, to: String }, // Function to its AbortCondition
+    #[serde(rename = "ABORTS_WITH")]
+    AbortsWith { from: String, to: String }, // AbortCondition to the Constant of its abort code
+    #[serde(rename = "ABORTS_IN")]
+    AbortsIn { from: String, to: String }, // AbortCondition to the callee Function aborting
 }
@@ -4,4 +4,7 @@ pub mod detectors;
 pub mod result;
 pub mod option;
 pub mod compile;pub mod graph;
+pub mod sarif;
+pub mod config;
+pub mod error;
 pub mod exporter;
@@ -1,8 +1,11 @@
 use crate::cli::parser::{Args, IR};
+use crate::move_ir::slice::SliceCriterion;
 use crate::scanner::compile::compile;
+use crate::scanner::config::{Config, CONFIG_FILE_NAME};
+use crate::scanner::error::{ScanError, ScanResult};
+use crate::scanner::result::Severity;
 use crate::utils::utils::{find_path_by_dir_name, toml_file_count};
 use std::path::PathBuf;
-// use toml::Value;
 // 终端输出格式
 #[derive(Debug, Clone)]
 pub enum TerminalFormat {
@@ -17,15 +20,29 @@ pub struct Options {
     pub output_path: PathBuf,
     pub terminal_format: TerminalFormat,
     pub ir_type: Option<IR>,
-    // pub config:Value
+    pub sarif_path: Option<PathBuf>,
+    pub config: Config,
+    pub write_baseline: bool,
+    pub fail_on: Option<Severity>,
+    pub slice: Option<SliceCriterion>,
+    pub slice_forward: bool,
 }
 
 impl Options {
-    // TODO: 增加从配置文件读入配置
-    pub fn build_options(args: Args) -> Self {
+    pub fn build_options(args: Args) -> ScanResult<Self> {
         let path = PathBuf::from(&args.path);
+        if !path.exists() {
+            return Err(ScanError::InvalidInput(format!(
+                "{} does not exist",
+                path.display()
+            )));
+        }
         // 只允许一个子项目
-        assert!(toml_file_count(&path) < 2, "up to one project is allowed");
+        if toml_file_count(&path) >= 2 {
+            return Err(ScanError::InvalidInput(
+                "up to one project is allowed".to_string(),
+            ));
+        }
         let sources_path = if let Some(s) = &args.source {
             Some(PathBuf::from(s))
         } else {
@@ -36,7 +53,11 @@ impl Options {
         // println!("Detected /sources, Try to compile.");
         if let Some(sources_path) = sources_path.clone() {
             if !args.skip_build {
-                assert!(compile(&sources_path), "compile project failed");
+                if !compile(&sources_path) {
+                    return Err(ScanError::CompileFailure(
+                        sources_path.display().to_string(),
+                    ));
+                }
                 bytecode_path = find_path_by_dir_name(&path, "bytecode_modules");
             }
         }
@@ -46,12 +67,48 @@ impl Options {
         } else {
             terminal_format = TerminalFormat::Block;
         }
-        Self {
+        // 未指定 --config 时，使用项目根目录下的 MoveScanner.toml（若存在）
+        let config_path = match &args.config {
+            Some(c) => Some(PathBuf::from(c)),
+            None => Some(path.join(CONFIG_FILE_NAME)).filter(|p| p.is_file()),
+        };
+        let config = match config_path {
+            Some(config_path) => Config::load(&config_path).map_err(|e| {
+                ScanError::InvalidInput(format!("load config failed: {}", e))
+            })?,
+            None => Config::default(),
+        };
+        if args.write_baseline && config.baseline.is_none() {
+            return Err(ScanError::InvalidInput(
+                "--write-baseline needs `baseline` in the config file".to_string(),
+            ));
+        }
+        let slice = args
+            .slice
+            .as_deref()
+            .map(str::parse::<SliceCriterion>)
+            .transpose()
+            .map_err(ScanError::InvalidInput)?;
+        if let (Some(IR::SL), None) = (&args.ir_type, &slice) {
+            return Err(ScanError::InvalidInput(
+                "-i sl needs a slicing criterion, e.g. --slice 0x0::pool::swap@12".to_string(),
+            ));
+        }
+        let bytecode_path = bytecode_path.ok_or_else(|| {
+            ScanError::InvalidInput(format!("no bytecode found in {}", path.display()))
+        })?;
+        Ok(Self {
             sources_path: sources_path,
-            bytecode_path: bytecode_path.unwrap(),
+            bytecode_path,
             output_path: PathBuf::from(args.output.unwrap()),
             terminal_format: terminal_format,
             ir_type: args.ir_type,
-        }
+            sarif_path: args.sarif.map(PathBuf::from),
+            config,
+            write_baseline: args.write_baseline,
+            fail_on: args.fail_on,
+            slice,
+            slice_forward: args.forward,
+        })
     }
 }
@@ -1,14 +1,27 @@
 use crate::{
     cli::parser::IR,
     move_ir::{
-        control_flow_graph::generate_cfg_in_dot_format,
+        control_flow_graph::{
+            generate_cdg_in_dot_format, generate_cfg_in_dot_format, generate_dom_tree_in_dot_format,
+        },
+        def_use::DefUse,
+        interval, liveness,
         packages::{build_compiled_modules, Packages},
+        slice::{self, DependenceGraph, SliceCriterion},
+    },
+    scanner::{
+        detectors::find_module_path,
+        error::{ScanError, ScanResult},
+        option::Options,
     },
-    scanner::option::Options,
 };
 // use move_binary_format::access::ModuleAccess;
 use petgraph::dot::Dot;
-use std::{fs, io::Write, path::PathBuf};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
 pub struct Printer {
     options: Options,
     // pub result: Result,
@@ -19,13 +32,16 @@ impl Printer {
         Self { options }
     }
 
-    pub fn run(&mut self) {
-        let cms = build_compiled_modules(&self.options.bytecode_path);
-        let packages = Packages::new(&cms);
+    pub fn run(&mut self) -> ScanResult<()> {
+        let cms = build_compiled_modules(&self.options.bytecode_path)?;
+        let mut packages = Packages::new(&cms);
         let printer_path = PathBuf::from("./printer");
         if !printer_path.exists() {
             fs::create_dir_all(&printer_path).expect("create path failed.");
         }
+        if let (Some(IR::SL), Some(criterion)) = (&self.options.ir_type, &self.options.slice) {
+            return self.print_slice(&mut packages, criterion, &printer_path);
+        }
         // }else{
         //     fs::remove_dir_all(&printer_path).expect("remove dir failed");
         //     fs::create_dir_all(&printer_path).expect("create path failed.");
@@ -42,6 +58,29 @@ impl Printer {
                     }
                     println!("cfg has been generated to folder ./printer/cfg success");
                 }
+                Some(IR::DOM) | Some(IR::PDOM) => {
+                    let (kind, post) = match self.options.ir_type {
+                        Some(IR::PDOM) => ("pdom", true),
+                        _ => ("dom", false),
+                    };
+                    for function in stbgr.functions.iter() {
+                        let dom_path =
+                            printer_path.join(format!("{}/{}/{}.dot", kind, mname, function.name));
+                        generate_dom_tree_in_dot_format(function, dom_path, stbgr, post);
+                    }
+                    println!(
+                        "{} tree has been generated to folder ./printer/{} success",
+                        kind, kind
+                    );
+                }
+                Some(IR::CDG) => {
+                    for function in stbgr.functions.iter() {
+                        let cdg_path =
+                            printer_path.join(format!("cdg/{}/{}.dot", mname, function.name));
+                        generate_cdg_in_dot_format(function, cdg_path, stbgr);
+                    }
+                    println!("cdg has been generated to folder ./printer/cdg success");
+                }
                 Some(IR::CG) => {
                     let graph = stbgr.call_graph2str();
                     let dot_graph = format!(
@@ -105,8 +144,125 @@ impl Printer {
                         println!("{:?}", &function.use_attrid);
                     }
                 }
+                Some(IR::DUC) => {
+                    let duc_path = printer_path.join(format!("duc/{}.duc", mname));
+                    if let Some(parent) = duc_path.parent() {
+                        if !parent.exists() {
+                            fs::create_dir_all(parent).expect("create path failed.");
+                        }
+                    }
+                    let mut duc_file = fs::File::create(&duc_path).expect("无法创建文件");
+                    for idx in 0..stbgr.functions.len() {
+                        if let Some(def_use) = DefUse::new(idx, stbgr) {
+                            writeln!(&mut duc_file, "{}", def_use.display(idx, stbgr))
+                                .expect("写入文件失败");
+                        }
+                    }
+                    println!(
+                        "def-use chains have been generated to file {} success",
+                        duc_path.to_string_lossy()
+                    );
+                }
+                Some(IR::LV) => {
+                    let lv_path = printer_path.join(format!("lv/{}.lv", mname));
+                    if let Some(parent) = lv_path.parent() {
+                        if !parent.exists() {
+                            fs::create_dir_all(parent).expect("create path failed.");
+                        }
+                    }
+                    let mut lv_file = fs::File::create(&lv_path).expect("无法创建文件");
+                    for idx in 0..stbgr.functions.len() {
+                        if let Some(annotated) = liveness::display(idx, stbgr) {
+                            writeln!(&mut lv_file, "{}", annotated).expect("写入文件失败");
+                        }
+                    }
+                    println!(
+                        "live variables have been generated to file {} success",
+                        lv_path.to_string_lossy()
+                    );
+                }
+                Some(IR::VR) => {
+                    let vr_path = printer_path.join(format!("vr/{}.vr", mname));
+                    if let Some(parent) = vr_path.parent() {
+                        if !parent.exists() {
+                            fs::create_dir_all(parent).expect("create path failed.");
+                        }
+                    }
+                    let mut vr_file = fs::File::create(&vr_path).expect("无法创建文件");
+                    for idx in 0..stbgr.functions.len() {
+                        if let Some(annotated) = interval::display(idx, stbgr) {
+                            writeln!(&mut vr_file, "{}", annotated).expect("写入文件失败");
+                        }
+                    }
+                    println!(
+                        "value ranges have been generated to file {} success",
+                        vr_path.to_string_lossy()
+                    );
+                }
                 _ => {}
             }
         }
+        Ok(())
+    }
+    /// Slice of the criterion function, as trimmed stackless bytecode followed by the
+    /// highlighted source when the sources are found.
+    fn print_slice(
+        &self,
+        packages: &mut Packages,
+        criterion: &SliceCriterion,
+        printer_path: &Path,
+    ) -> ScanResult<()> {
+        let locations = find_module_path(
+            self.options.sources_path.as_ref(),
+            &packages.get_module_names(),
+        );
+        for (mname, location) in locations.iter() {
+            if let Some((path, _)) = location.as_ref().and_then(|l| l.rsplit_once(':')) {
+                packages.load_source_code(mname, &PathBuf::from(path));
+            }
+        }
+        let (stbgr, idx) = packages
+            .get_function_by_full_name(&criterion.function)
+            .ok_or_else(|| {
+                ScanError::InvalidInput(format!("function {} not found", criterion.function))
+            })?;
+        let function = &stbgr.functions[idx];
+        let offset =
+            slice::get_criterion_offset(function, stbgr, criterion.point).ok_or_else(|| {
+                ScanError::InvalidInput(format!(
+                    "no instruction at {:?} of {}",
+                    criterion.point, criterion.function
+                ))
+            })?;
+        let pdg = DependenceGraph::new(idx, stbgr).ok_or_else(|| {
+            ScanError::InvalidInput(format!("{} is a native function", criterion.function))
+        })?;
+        let sliced = if self.options.slice_forward {
+            pdg.forward_slice(offset)
+        } else {
+            pdg.backward_slice(offset)
+        };
+        let (mname, fname) = criterion.function.rsplit_once("::").unwrap();
+        let sl_path = printer_path.join(format!("sl/{}/{}.sl", mname, fname));
+        if let Some(parent) = sl_path.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent).expect("create path failed.");
+            }
+        }
+        let mut sl_file = fs::File::create(&sl_path).expect("无法创建文件");
+        writeln!(
+            &mut sl_file,
+            "{}",
+            slice::display(idx, stbgr, &sliced, offset)
+        )
+        .expect("写入文件失败");
+        if let Some(source) = slice::display_source(idx, stbgr, &sliced, offset) {
+            writeln!(&mut sl_file, "{}", source).expect("写入文件失败");
+        }
+        println!(
+            "program slice has been generated to file {} success",
+            sl_path.to_string_lossy()
+        );
+        Ok(())
     }
 }
@@ -33,6 +33,18 @@ pub enum DetectKind {
     UnusedPrivateFunctions,
     RecursiveFunctionCall,
     RepeatedFunctionCall,
+    SharedObjectAccessControl,
+    InvalidOneTimeWitness,
+    UncheckedHotPotato,
+    MutableReferenceLeak,
+    UnreturnedCoinRemainder,
+    UnguardedMint,
+    UnboundedIteration,
+    RandomnessMisuse,
+    TimestampDependence,
+    NarrowingCast,
+    DeadStore,
+    TaintFlow,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
@@ -42,7 +54,10 @@ pub enum FunctionType {
     Native,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
+// 声明顺序即严重程度顺序，--fail-on 依赖 Ord
+#[derive(
+    Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, clap::ValueEnum,
+)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Info,
@@ -52,6 +67,14 @@ pub enum Severity {
     Critical,
 }
 
+/// Split a finding `name(detail)` into its name and detail.
+pub fn split_finding(finding: &str) -> (&str, Option<&str>) {
+    match finding.split_once('(') {
+        Some((name, detail)) => (name, Some(detail.strip_suffix(')').unwrap_or(detail))),
+        None => (finding, None),
+    }
+}
+
 pub struct DetectContent {
     pub severity: Severity,
     pub kind: DetectKind,
@@ -80,6 +103,7 @@ pub struct Result {
     pub modules_status: HashMap<Status, Vec<String>>,
     pub total_time: usize,
     pub modules: HashMap<ModuleName, ModuleInfo>,
+    pub severities: HashMap<DetectKind, Severity>, // severity of each detector that ran
 }
 
 impl Result {
@@ -87,11 +111,13 @@ impl Result {
         modules_status: HashMap<Status, Vec<String>>,
         total_time: usize,
         modules: HashMap<ModuleName, ModuleInfo>,
+        severities: HashMap<DetectKind, Severity>,
     ) -> Self {
         Self {
             modules_status,
             total_time,
             modules,
+            severities,
         }
     }
 
@@ -100,12 +126,26 @@ impl Result {
             HashMap::from([(Status::Pass, Vec::new()), (Status::Wrong, Vec::new())]),
             0,
             HashMap::new(),
+            HashMap::new(),
         );
     }
 
     pub fn add_module(&mut self, module_name: ModuleName, module_info: ModuleInfo) {
         self.modules.insert(module_name, module_info);
     }
+
+    /// Whether any detector at or above `threshold` reported a finding.
+    pub fn has_findings_at_least(&self, threshold: &Severity) -> bool {
+        self.modules.values().any(|module_info| {
+            module_info.detectors.iter().any(|(kind, values)| {
+                !values.is_empty()
+                    && self
+                        .severities
+                        .get(kind)
+                        .map_or(false, |severity| severity >= threshold)
+            })
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -116,6 +156,9 @@ pub struct ModuleInfo {
     pub constant_count: usize,
     pub structs: Vec<StructResult>,
     pub detectors: HashMap<DetectKind, Vec<String>>,
+    // 分析无法完整进行的函数，如 "fname: reason"
+    #[serde(default)]
+    pub diagnostics: Vec<String>,
 }
 impl ModuleInfo {
     pub fn new(
@@ -125,6 +168,7 @@ impl ModuleInfo {
         constant_count: usize,
         structs: Vec<StructResult>,
         detectors: HashMap<DetectKind, Vec<String>>,
+        diagnostics: Vec<String>,
     ) -> Self {
         Self {
             status,
@@ -133,6 +177,7 @@ impl ModuleInfo {
             constant_count,
             structs,
             detectors,
+            diagnostics,
         }
     }
     pub fn empty() -> Self {
@@ -141,7 +186,15 @@ impl ModuleInfo {
         for detect_kind in DetectKind::iter() {
             detectors.insert(detect_kind, Vec::<String>::new());
         }
-        return Self::new(Status::Wrong, None, function_count, 0, Vec::new(), detectors);
+        return Self::new(
+            Status::Wrong,
+            None,
+            function_count,
+            0,
+            Vec::new(),
+            detectors,
+            Vec::new(),
+        );
     }
 }
 
@@ -0,0 +1,372 @@
+// SARIF 2.1.0 output, only the subset needed by code-review tools
+use clap::CommandFactory;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, env, path::Path};
+use strum::IntoEnumIterator;
+
+use crate::{
+    cli::parser::Cli,
+    move_ir::{generate_bytecode::StacklessBytecodeGenerator, packages::Packages},
+    scanner::config::fingerprint,
+    scanner::result::{split_finding, DetectKind, Result, Severity},
+};
+
+const SARIF_VERSION: &str = "2.1.0";
+const FINGERPRINT_KEY: &str = "moveScanner/v1";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    pub version: String,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Run {
+    pub tool: Tool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tool {
+    pub driver: Driver,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    pub name: String,
+    pub version: String,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    pub id: String,
+    pub name: String,
+    pub short_description: Message,
+    pub default_configuration: Configuration,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Configuration {
+    pub level: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Message {
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: String,
+    pub rule_index: usize,
+    pub level: String,
+    pub message: Message,
+    pub locations: Vec<SarifLocation>,
+    pub partial_fingerprints: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLocation {
+    pub physical_location: PhysicalLocation,
+    pub logical_locations: Vec<LogicalLocation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArtifactLocation {
+    pub uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogicalLocation {
+    pub fully_qualified_name: String,
+    pub kind: String,
+}
+
+pub fn severity_level(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Info | Severity::Minor => "note",
+        Severity::Medium => "warning",
+        Severity::Major | Severity::Critical => "error",
+    }
+}
+
+pub fn to_sarif(result: &Result, packages: &Packages) -> SarifLog {
+    let rule_kinds: Vec<DetectKind> = DetectKind::iter()
+        .filter(|kind| result.severities.contains_key(kind))
+        .collect();
+    let rules = rule_kinds
+        .iter()
+        .map(|kind| Rule {
+            id: kind.to_string(),
+            name: format!("{:?}", kind),
+            short_description: Message {
+                text: kind.to_string().replace('_', " "),
+            },
+            default_configuration: Configuration {
+                level: severity_level(&result.severities[kind]).to_string(),
+            },
+        })
+        .collect();
+
+    let mut module_names: Vec<&String> = result.modules.keys().collect();
+    module_names.sort();
+    let mut results = vec![];
+    for module_name in module_names {
+        let module_info = &result.modules[module_name];
+        let stbgr = packages.get_stbgr_by_mname(module_name.to_string());
+        for (rule_index, kind) in rule_kinds.iter().enumerate() {
+            for finding in module_info.detectors[kind].iter() {
+                let (name, detail) = split_finding(finding);
+                let physical_location = match stbgr {
+                    Some(stbgr) => physical_location(
+                        stbgr,
+                        module_info.location.as_deref(),
+                        name,
+                        detail,
+                    ),
+                    None => module_physical_location(module_info.location.as_deref(), ""),
+                };
+                results.push(SarifResult {
+                    rule_id: kind.to_string(),
+                    rule_index,
+                    level: severity_level(&result.severities[kind]).to_string(),
+                    message: Message {
+                        text: format!("{} in {}::{}", kind, module_name, finding),
+                    },
+                    locations: vec![SarifLocation {
+                        physical_location,
+                        logical_locations: vec![LogicalLocation {
+                            fully_qualified_name: format!("{}::{}", module_name, name),
+                            kind: logical_kind(stbgr, name).to_string(),
+                        }],
+                    }],
+                    partial_fingerprints: HashMap::from([(
+                        FINGERPRINT_KEY.to_string(),
+                        fingerprint(kind, module_name, finding),
+                    )]),
+                });
+            }
+        }
+    }
+
+    let command = Cli::command();
+    SarifLog {
+        schema: SARIF_SCHEMA.to_string(),
+        version: SARIF_VERSION.to_string(),
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: command.get_name().to_string(),
+                    version: command.get_version().unwrap_or_default().to_string(),
+                    rules,
+                },
+            },
+            results,
+        }],
+    }
+}
+
+/// SARIF kind of the item a finding is named after: a function, a struct, or a constant
+/// such as `const_3`.
+fn logical_kind(stbgr: Option<&StacklessBytecodeGenerator>, name: &str) -> &'static str {
+    let stbgr = match stbgr {
+        Some(stbgr) => stbgr,
+        None => return "member",
+    };
+    if stbgr.functions.iter().any(|f| f.name == name) {
+        "function"
+    } else if stbgr
+        .module_data
+        .struct_data
+        .values()
+        .any(|data| stbgr.symbol_pool.string(data.name).as_str() == name)
+    {
+        "type"
+    } else if name.starts_with("const_") {
+        "variable"
+    } else {
+        "member"
+    }
+}
+
+/// Absolute `file://` URI of a path, relative paths being resolved against the working
+/// directory. Characters other than unreserved ones, `/` and `:` are percent-encoded.
+/// An empty path, i.e. an unknown artifact, stays empty.
+fn file_uri(path: &str) -> String {
+    if path.is_empty() {
+        return String::new();
+    }
+    let path = Path::new(path);
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    };
+    let mut uri = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        // Windows 盘符路径 C:/... 需要 file:///C:/...
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Prefer the source map location of the instruction or function, then the module location.
+fn physical_location(
+    stbgr: &StacklessBytecodeGenerator,
+    module_location: Option<&str>,
+    name: &str,
+    detail: Option<&str>,
+) -> PhysicalLocation {
+    let offset = detail
+        .and_then(|d| d.split(':').next())
+        .and_then(|d| d.trim().parse::<usize>().ok());
+    let line = stbgr
+        .functions
+        .iter()
+        .find(|f| f.name == name)
+        .and_then(|function| {
+            offset
+                .and_then(|offset| stbgr.get_source_line(function, Some(offset)))
+                .or_else(|| stbgr.get_source_line(function, None))
+        });
+    match (module_location.and_then(|l| l.rsplit_once(':')), line) {
+        (Some((path, _)), Some(line)) => PhysicalLocation {
+            artifact_location: ArtifactLocation {
+                uri: file_uri(path),
+            },
+            region: Some(Region { start_line: line }),
+        },
+        _ => module_physical_location(
+            module_location,
+            &stbgr.bytecode_file_path.to_string_lossy(),
+        ),
+    }
+}
+
+/// Module declaration line, or just the bytecode file when sources are missing.
+fn module_physical_location(module_location: Option<&str>, bytecode_path: &str) -> PhysicalLocation {
+    match module_location.and_then(|l| l.rsplit_once(':')) {
+        Some((path, line)) => PhysicalLocation {
+            artifact_location: ArtifactLocation {
+                uri: file_uri(path),
+            },
+            region: line.parse().ok().map(|start_line| Region { start_line }),
+        },
+        None => PhysicalLocation {
+            artifact_location: ArtifactLocation {
+                uri: file_uri(bytecode_path),
+            },
+            region: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        move_ir::test_utils::{shared_pool_module, with_paths},
+        scanner::result::ModuleInfo,
+    };
+
+    #[test]
+    fn test_to_sarif() {
+        let cms = with_paths(vec![shared_pool_module()]);
+        let packages = Packages::new(&cms);
+        let mname = packages.get_module_names()[0].clone();
+        let mut module_info = ModuleInfo::empty();
+        module_info.location = Some("my sources/pool.move:3".to_string());
+        let findings = [
+            (DetectKind::UnusedConstant, "const_0(0)"),
+            (
+                DetectKind::SharedObjectAccessControl,
+                "withdraw(Pool.balance)",
+            ),
+            (
+                DetectKind::InvalidOneTimeWitness,
+                "Pool(must have no fields)",
+            ),
+        ];
+        let mut result = Result::empty();
+        for (kind, finding) in findings.iter() {
+            module_info
+                .detectors
+                .insert(kind.clone(), vec![finding.to_string()]);
+            result.severities.insert(kind.clone(), Severity::Medium);
+        }
+        result.add_module(mname.clone(), module_info);
+
+        let sarif = serde_json::to_value(to_sarif(&result, &packages)).unwrap();
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        let logical: Vec<(&str, &str)> = results
+            .iter()
+            .map(|result| {
+                let location = &result["locations"][0]["logicalLocations"][0];
+                (
+                    location["fullyQualifiedName"].as_str().unwrap(),
+                    location["kind"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            logical,
+            vec![
+                (format!("{}::const_0", mname).as_str(), "variable"),
+                (format!("{}::withdraw", mname).as_str(), "function"),
+                (format!("{}::Pool", mname).as_str(), "type"),
+            ]
+        );
+        for result in results {
+            let location = &result["locations"][0]["physicalLocation"];
+            let uri = location["artifactLocation"]["uri"].as_str().unwrap();
+            assert!(uri.starts_with("file:///"));
+            assert!(uri.ends_with("/my%20sources/pool.move"));
+            // 没有源码映射时退回到 module 声明所在行
+            assert_eq!(location["region"]["startLine"], 3);
+        }
+    }
+
+    #[test]
+    fn test_file_uri() {
+        assert_eq!(file_uri(""), "");
+        assert_eq!(
+            file_uri("/pkg/sources/a b.move"),
+            "file:///pkg/sources/a%20b.move"
+        );
+        assert!(file_uri("sources/a.move").starts_with("file:///"));
+    }
+}
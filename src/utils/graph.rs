@@ -82,31 +82,152 @@ impl<T: Ord + Copy + Debug> Graph<T> {
         }
     }
 
-    fn is_acyclic(&self) -> bool {
-        let mut visited = BTreeMap::new();
-        let mut stack = vec![];
-        visited.insert(self.entry, false);
-        stack.push(self.entry);
-        while !stack.is_empty() {
-            let n = stack.pop().unwrap();
-            if visited[&n] {
-                visited.entry(n).and_modify(|x| {
-                    *x = false;
-                });
+    /// Loops of a possibly irreducible graph: one per edge back into the header of each
+    /// cyclic strongly connected component reachable from the entry. The header is the
+    /// smallest node entered from outside the component and the body is the whole component,
+    /// so loops nested in it are not told apart.
+    pub fn compute_loops_by_scc(&self) -> Vec<NaturalLoop<T>> {
+        let mut natural_loops = vec![];
+        for scc in self.strongly_connected_components() {
+            let is_cyclic = scc.len() > 1 || scc.iter().any(|x| self.successors[x].contains(x));
+            if !is_cyclic {
                 continue;
             }
-            stack.push(n);
-            visited.entry(n).and_modify(|x| {
-                *x = true;
-            });
-            for s in &self.successors[&n] {
-                if visited.contains_key(s) {
-                    if visited[s] {
-                        return false;
+            let loop_header = scc
+                .iter()
+                .copied()
+                .find(|x| *x == self.entry || self.predecessors[x].iter().any(|p| !scc.contains(p)))
+                .unwrap_or_else(|| *scc.iter().next().unwrap());
+            for loop_latch in self.predecessors[&loop_header].iter() {
+                if scc.contains(loop_latch) {
+                    natural_loops.push(NaturalLoop {
+                        loop_header,
+                        loop_latch: *loop_latch,
+                        loop_body: scc.clone(),
+                    });
+                }
+            }
+        }
+        natural_loops
+    }
+
+    /// Immediate dominator of every node reachable from the entry, the entry excluded.
+    pub fn compute_immediate_dominators(&self) -> BTreeMap<T, T> {
+        let dom_relation = DomRelation::new(self);
+        dom_relation
+            .idom_tree
+            .iter()
+            .filter(|(node_num, idom_num)| node_num != idom_num)
+            .map(|(node_num, idom_num)| {
+                (
+                    dom_relation.postorder_num_to_node[*node_num],
+                    dom_relation.postorder_num_to_node[*idom_num],
+                )
+            })
+            .collect()
+    }
+
+    /// The same graph with every edge reversed, entered at `entry`. The dominators of the
+    /// reverse graph entered at the exit node are the post-dominators of this graph.
+    pub fn reverse(&self, entry: T) -> Self {
+        let edges = self.edges.iter().map(|(from, to)| (*to, *from)).collect();
+        Graph::new(entry, self.nodes.clone(), edges)
+    }
+
+    /// Immediate post-dominator of every node that reaches `exit`, `exit` excluded.
+    pub fn compute_immediate_post_dominators(&self, exit: T) -> BTreeMap<T, T> {
+        self.reverse(exit).compute_immediate_dominators()
+    }
+
+    /// Nodes each node is control dependent on (Ferrante, Ottenstein and Warren): `y`
+    /// depends on `x` when `x` has an edge to a node post-dominated by `y` but `y` does not
+    /// strictly post-dominate `x`. `exit` must be reachable from every node that matters,
+    /// nodes that cannot reach it get no post-dominator and are treated as exiting.
+    pub fn compute_control_dependence(&self, exit: T) -> BTreeMap<T, BTreeSet<T>> {
+        self.compute_control_dependence_edges(exit)
+            .into_iter()
+            .map(|(node, edges)| (node, edges.into_iter().map(|(from, _)| from).collect()))
+            .collect()
+    }
+
+    /// Like `compute_control_dependence`, with the edge out of the controlling node that
+    /// leads to the dependent node, e.g. which side of a branch.
+    pub fn compute_control_dependence_edges(&self, exit: T) -> BTreeMap<T, BTreeSet<(T, T)>> {
+        let ipdom = self.compute_immediate_post_dominators(exit);
+        let mut control_dependence: BTreeMap<T, BTreeSet<(T, T)>> = BTreeMap::new();
+        for (from, to) in &self.edges {
+            let stop = ipdom.get(from);
+            let mut runner = Some(*to);
+            while let Some(node) = runner {
+                if Some(&node) == stop || node == exit {
+                    break;
+                }
+                control_dependence
+                    .entry(node)
+                    .or_default()
+                    .insert((*from, *to));
+                runner = ipdom.get(&node).copied();
+            }
+        }
+        control_dependence
+    }
+
+    /// Strongly connected components of the nodes reachable from the entry (Kosaraju).
+    fn strongly_connected_components(&self) -> Vec<BTreeSet<T>> {
+        let mut postorder = vec![];
+        let mut reachable = BTreeSet::from([self.entry]);
+        let mut stack = vec![(self.entry, self.successors[&self.entry].iter())];
+        while let Some((node, successors)) = stack.last_mut() {
+            match successors.next() {
+                Some(succ) => {
+                    if reachable.insert(*succ) {
+                        stack.push((*succ, self.successors[succ].iter()));
                     }
-                } else {
-                    visited.insert(*s, false);
-                    stack.push(*s);
+                }
+                None => {
+                    postorder.push(*node);
+                    stack.pop();
+                }
+            }
+        }
+        let mut assigned = BTreeSet::new();
+        let mut sccs = vec![];
+        for root in postorder.into_iter().rev() {
+            if !assigned.insert(root) {
+                continue;
+            }
+            let mut scc = BTreeSet::from([root]);
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                for pred in &self.predecessors[&node] {
+                    if reachable.contains(pred) && assigned.insert(*pred) {
+                        scc.insert(*pred);
+                        stack.push(*pred);
+                    }
+                }
+            }
+            sccs.push(scc);
+        }
+        sccs
+    }
+
+    fn is_acyclic(&self) -> bool {
+        // 节点在 DFS 栈上时为 true，完成后为 false；只有指向栈上节点的边才构成环
+        let mut on_stack = BTreeMap::from([(self.entry, true)]);
+        let mut stack = vec![(self.entry, self.successors[&self.entry].iter())];
+        while let Some((node, successors)) = stack.last_mut() {
+            match successors.next() {
+                Some(succ) => match on_stack.get(succ) {
+                    Some(true) => return false,
+                    Some(false) => {}
+                    None => {
+                        on_stack.insert(*succ, true);
+                        stack.push((*succ, self.successors[succ].iter()));
+                    }
+                },
+                None => {
+                    on_stack.insert(*node, false);
+                    stack.pop();
                 }
             }
         }
@@ -304,4 +425,54 @@ mod tests {
         assert_eq!(outer_loop.loop_header, 2);
         assert_eq!(outer_loop.loop_body, vec![2, 3, 4, 5].into_iter().collect());
     }
+
+    #[test]
+    fn test_irreducible_loops() {
+        // the cycle 2 <-> 3 is entered at both 2 and 3, plus a self loop on 4
+        let nodes = vec![1, 2, 3, 4, 5];
+        let edges = vec![(1, 2), (1, 3), (2, 3), (3, 2), (3, 4), (4, 4), (4, 5)];
+        let graph = Graph::new(1, nodes, edges);
+        assert!(graph.compute_reducible().is_none());
+
+        let mut natural_loops = graph.compute_loops_by_scc();
+        natural_loops.sort_by_key(|l| l.loop_header);
+        assert_eq!(natural_loops.len(), 2);
+        assert_eq!(natural_loops[0].loop_header, 2);
+        assert_eq!(natural_loops[0].loop_latch, 3);
+        assert_eq!(natural_loops[0].loop_body, vec![2, 3].into_iter().collect());
+        assert_eq!(natural_loops[1].loop_header, 4);
+        assert_eq!(natural_loops[1].loop_latch, 4);
+        assert_eq!(natural_loops[1].loop_body, vec![4].into_iter().collect());
+    }
+
+    #[test]
+    fn test_control_dependence() {
+        // 1 -> 2 -> {3, 4} -> 5 -> 6, with a loop 5 -> 2
+        let nodes = vec![1, 2, 3, 4, 5, 6];
+        let edges = vec![(1, 2), (2, 3), (2, 4), (3, 5), (4, 5), (5, 2), (5, 6)];
+        let graph = Graph::new(1, nodes, edges);
+
+        let idom = graph.compute_immediate_dominators();
+        assert_eq!(idom[&2], 1);
+        assert_eq!(idom[&5], 2);
+        assert!(!idom.contains_key(&1));
+
+        let ipdom = graph.compute_immediate_post_dominators(6);
+        assert_eq!(ipdom[&2], 5);
+        assert_eq!(ipdom[&3], 5);
+        assert_eq!(ipdom[&5], 6);
+
+        let cd = graph.compute_control_dependence(6);
+        assert_eq!(cd[&3], vec![2].into_iter().collect());
+        assert_eq!(cd[&4], vec![2].into_iter().collect());
+        // the loop body runs again only if 5 branches back
+        assert_eq!(cd[&2], vec![5].into_iter().collect());
+        assert_eq!(cd[&5], vec![5].into_iter().collect());
+        assert!(!cd.contains_key(&1));
+        assert!(!cd.contains_key(&6));
+
+        let cd_edges = graph.compute_control_dependence_edges(6);
+        assert_eq!(cd_edges[&3], vec![(2, 3)].into_iter().collect());
+        assert_eq!(cd_edges[&2], vec![(5, 2)].into_iter().collect());
+    }
 }
@@ -6,6 +6,8 @@ use move_model::{
 use std::{fs, path::PathBuf};
 use walkdir::WalkDir;
 
+use crate::scanner::config::CONFIG_FILE_NAME;
+
 // 依赖的 module 的 address
 const DEPADDRESSES: [&str; 2] = ["0x1::", "0x3::"];
 
@@ -203,7 +205,9 @@ pub fn toml_file_count(path: &PathBuf) -> i32 {
         return count;
     }
     for entry in WalkDir::new(path).into_iter().filter_map(|e| e.ok()) {
-        if entry.file_type().is_file() && entry.file_name().to_str().unwrap().ends_with(".toml") {
+        let file_name = entry.file_name().to_str().unwrap();
+        // MoveScanner.toml 是扫描配置，不是 Move 项目
+        if entry.file_type().is_file() && file_name.ends_with(".toml") && file_name != CONFIG_FILE_NAME {
             count += 1;
         }
     }